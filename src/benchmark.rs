@@ -0,0 +1,438 @@
+use chrono::{DateTime, Duration, Months, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::trade_btc::EquityPoint;
+
+/// Intervalo de aporte do DCA ingênuo
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum DcaInterval {
+    Weekly,
+    Monthly,
+}
+
+impl DcaInterval {
+    /// Horário do aporte de número `n` (o primeiro é o `n = 0`, em `start`)
+    ///
+    /// Sempre contado a partir do início: somar um mês por vez ao aporte anterior
+    /// prenderia a série no dia 28 depois de passar por fevereiro.
+    fn nth(&self, start: DateTime<Utc>, n: u32) -> DateTime<Utc> {
+        match self {
+            DcaInterval::Weekly => start + Duration::weeks(n as i64),
+            DcaInterval::Monthly => start
+                .checked_add_months(Months::new(n))
+                .unwrap_or(start + Duration::days(30 * n as i64)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum BenchmarkKind {
+    /// Compra todo o saldo no primeiro candle e segura até o fim
+    BuyAndHold,
+    /// Divide o saldo em aportes iguais em intervalo fixo
+    Dca(DcaInterval),
+}
+
+/// Segundos num ano de 365 dias, para anualizar o alfa
+const SECONDS_PER_YEAR: f64 = 365.0 * 24.0 * 3600.0;
+
+/// Amostra da curva de um benchmark, gravada no `benchmarks.csv` da execução
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BenchmarkSample {
+    pub time: DateTime<Utc>,
+    pub benchmark: String,
+    pub value: f64,
+}
+
+/// Momentos acumulados dos retornos da estratégia e do benchmark entre amostras
+/// tomadas no mesmo horário
+///
+/// Guarda só médias e co-momentos (atualizados como no algoritmo de Welford), então o
+/// estado salvo não cresce com a duração da simulação; as curvas ficam nos CSVs da
+/// execução.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReturnMoments {
+    last: Option<(DateTime<Utc>, f64, f64)>,
+    count: u64,
+    elapsed_seconds: i64,
+    mean_strategy: f64,
+    mean_benchmark: f64,
+    co_moment: f64,
+    benchmark_m2: f64,
+}
+
+impl ReturnMoments {
+    /// Monta os momentos a partir de duas curvas completas, pareando os timestamps iguais
+    pub fn from_curves(strategy: &[EquityPoint], benchmark: &[(DateTime<Utc>, f64)]) -> Self {
+        let mut moments = Self::default();
+        let mut j = 0;
+        for point in strategy {
+            while j < benchmark.len() && benchmark[j].0 < point.time {
+                j += 1;
+            }
+            if j < benchmark.len() && benchmark[j].0 == point.time {
+                moments.push(point.time, point.total_value, benchmark[j].1);
+            }
+        }
+        moments
+    }
+
+    /// Acrescenta o valor das duas carteiras num mesmo horário
+    pub fn push(&mut self, time: DateTime<Utc>, strategy: f64, benchmark: f64) {
+        if let Some((last_time, last_strategy, last_benchmark)) = self.last {
+            if last_strategy > 0.0 && last_benchmark > 0.0 {
+                let r_s = strategy / last_strategy - 1.0;
+                let r_b = benchmark / last_benchmark - 1.0;
+                self.count += 1;
+                self.elapsed_seconds += (time - last_time).num_seconds();
+                let n = self.count as f64;
+                let d_s = r_s - self.mean_strategy;
+                let d_b = r_b - self.mean_benchmark;
+                self.mean_strategy += d_s / n;
+                self.mean_benchmark += d_b / n;
+                self.co_moment += d_s * (r_b - self.mean_benchmark);
+                self.benchmark_m2 += d_b * (r_b - self.mean_benchmark);
+            }
+        }
+        self.last = Some((time, strategy, benchmark));
+    }
+
+    /// Alfa (anualizado, %) e beta da estratégia
+    ///
+    /// Os períodos por ano saem do espaçamento médio real entre as amostras.
+    pub fn alpha_beta(&self) -> Option<(f64, f64)> {
+        if self.count < 2 || self.benchmark_m2 <= 0.0 || self.elapsed_seconds <= 0 {
+            return None;
+        }
+        let beta = self.co_moment / self.benchmark_m2;
+        let periods_per_year = self.count as f64 * SECONDS_PER_YEAR / self.elapsed_seconds as f64;
+        let alpha = (self.mean_strategy - beta * self.mean_benchmark) * periods_per_year * 100.0;
+        Some((alpha, beta))
+    }
+}
+
+/// Carteira de referência simulada sobre os mesmos candles da estratégia
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Benchmark {
+    pub kind: BenchmarkKind,
+    pub initial_balance: f64,
+    pub saldo_fiat: f64,
+    pub saldo_btc: f64,
+    pub contribution: f64,
+    #[serde(default)]
+    pub start: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub buys_done: u32,
+    pub next_buy: Option<DateTime<Utc>>,
+    pub last_value: f64,
+    pub max_drawdown: f64,
+    #[serde(default)]
+    pub returns: ReturnMoments,
+    /// Curva embutida por estados antigos; passa para o `benchmarks.csv` da próxima execução
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub samples: Vec<(DateTime<Utc>, f64)>,
+}
+
+impl Benchmark {
+    pub fn buy_and_hold(initial_balance: f64, start: DateTime<Utc>) -> Self {
+        Self::with_contribution(
+            BenchmarkKind::BuyAndHold,
+            initial_balance,
+            initial_balance,
+            start,
+        )
+    }
+
+    pub fn dca(
+        interval: DcaInterval,
+        initial_balance: f64,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Self {
+        // Número de aportes que cabem no período, incluindo o primeiro
+        let mut buys = 0u32;
+        while interval.nth(start, buys) < end {
+            buys += 1;
+        }
+        let contribution = initial_balance / buys.max(1) as f64;
+        Self::with_contribution(
+            BenchmarkKind::Dca(interval),
+            initial_balance,
+            contribution,
+            start,
+        )
+    }
+
+    fn with_contribution(
+        kind: BenchmarkKind,
+        initial_balance: f64,
+        contribution: f64,
+        start: DateTime<Utc>,
+    ) -> Self {
+        Self {
+            kind,
+            initial_balance,
+            saldo_fiat: initial_balance,
+            saldo_btc: 0.0,
+            contribution,
+            start: Some(start),
+            buys_done: 0,
+            next_buy: Some(start),
+            last_value: initial_balance,
+            max_drawdown: 0.0,
+            returns: ReturnMoments::default(),
+            samples: Vec::new(),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self.kind {
+            BenchmarkKind::BuyAndHold => "Buy & Hold",
+            BenchmarkKind::Dca(DcaInterval::Weekly) => "DCA semanal",
+            BenchmarkKind::Dca(DcaInterval::Monthly) => "DCA mensal",
+        }
+    }
+
+    /// Processa um candle: executa aportes pendentes e atualiza valor e drawdown
+    pub fn on_tick(&mut self, time: DateTime<Utc>, price: f64) {
+        while let Some(buy_time) = self.next_buy {
+            if time < buy_time || self.saldo_fiat <= 0.0 || price <= 0.0 {
+                break;
+            }
+            let amount = self.contribution.min(self.saldo_fiat);
+            self.saldo_fiat -= amount;
+            self.saldo_btc += amount / price;
+            self.buys_done += 1;
+            self.next_buy = match self.kind {
+                BenchmarkKind::BuyAndHold => None,
+                // Estados antigos não guardavam o início: contar a partir do último aporte
+                BenchmarkKind::Dca(interval) => Some(match self.start {
+                    Some(start) => interval.nth(start, self.buys_done),
+                    None => interval.nth(buy_time, 1),
+                }),
+            };
+        }
+
+        self.last_value = self.saldo_fiat + self.saldo_btc * price;
+        // Mesmo critério de drawdown da estratégia: queda relativa ao saldo inicial
+        let drawdown =
+            ((self.initial_balance - self.last_value) / self.initial_balance * 100.0).max(0.0);
+        if drawdown > self.max_drawdown {
+            self.max_drawdown = drawdown;
+        }
+    }
+
+    /// Registra o valor atual junto da amostra da curva de patrimônio da estratégia
+    pub fn sample(&mut self, time: DateTime<Utc>, strategy_value: f64) -> BenchmarkSample {
+        self.returns.push(time, strategy_value, self.last_value);
+        BenchmarkSample {
+            time,
+            benchmark: self.name().to_string(),
+            value: self.last_value,
+        }
+    }
+
+    pub fn return_percentage(&self) -> f64 {
+        (self.last_value - self.initial_balance) / self.initial_balance * 100.0
+    }
+}
+
+/// Benchmarks padrão executados junto de toda simulação
+pub fn default_benchmarks(
+    initial_balance: f64,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Vec<Benchmark> {
+    vec![
+        Benchmark::buy_and_hold(initial_balance, start),
+        Benchmark::dca(DcaInterval::Weekly, initial_balance, start, end),
+        Benchmark::dca(DcaInterval::Monthly, initial_balance, start, end),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn hour(n: i64) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap() + Duration::hours(n)
+    }
+
+    /// Curva que parte de 100 e aplica os retornos em sequência, uma amostra por hora
+    fn curve(returns: &[f64]) -> Vec<(DateTime<Utc>, f64)> {
+        let mut value = 100.0;
+        let mut points = vec![(hour(0), value)];
+        for (i, r) in returns.iter().enumerate() {
+            value *= 1.0 + r;
+            points.push((hour(i as i64 + 1), value));
+        }
+        points
+    }
+
+    fn equity(points: &[(DateTime<Utc>, f64)]) -> Vec<EquityPoint> {
+        points
+            .iter()
+            .map(|&(time, total_value)| EquityPoint {
+                time,
+                price: 0.0,
+                saldo_fiat: total_value,
+                saldo_btc: 0.0,
+                total_value,
+                drawdown: 0.0,
+            })
+            .collect()
+    }
+
+    fn alpha_beta(
+        strategy: &[EquityPoint],
+        benchmark: &[(DateTime<Utc>, f64)],
+    ) -> Option<(f64, f64)> {
+        ReturnMoments::from_curves(strategy, benchmark).alpha_beta()
+    }
+
+    const MARKET: [f64; 4] = [0.10, -0.05, 0.02, -0.03];
+
+    #[test]
+    fn leveraged_strategy_has_beta_two_and_no_alpha() {
+        let leveraged: Vec<f64> = MARKET.iter().map(|r| 2.0 * r).collect();
+        let (alpha, beta) = alpha_beta(&equity(&curve(&leveraged)), &curve(&MARKET)).unwrap();
+        assert!((beta - 2.0).abs() < 1e-9);
+        assert!(alpha.abs() < 1e-6);
+    }
+
+    #[test]
+    fn constant_excess_return_is_annualised_alpha() {
+        let excess: Vec<f64> = MARKET.iter().map(|r| r + 0.001).collect();
+        let (alpha, beta) = alpha_beta(&equity(&curve(&excess)), &curve(&MARKET)).unwrap();
+        assert!((beta - 1.0).abs() < 1e-9);
+        assert!((alpha - 0.001 * 24.0 * 365.0 * 100.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn only_matching_timestamps_are_paired() {
+        let benchmark = curve(&MARKET);
+        let mut strategy = equity(&benchmark);
+        // Amostra sem par no benchmark não pode entrar no cálculo
+        strategy.insert(
+            2,
+            EquityPoint {
+                time: hour(1) + Duration::minutes(30),
+                price: 0.0,
+                saldo_fiat: 1.0,
+                saldo_btc: 0.0,
+                total_value: 1.0,
+                drawdown: 0.0,
+            },
+        );
+        let (alpha, beta) = alpha_beta(&strategy, &benchmark).unwrap();
+        assert!((beta - 1.0).abs() < 1e-9);
+        assert!(alpha.abs() < 1e-6);
+    }
+
+    #[test]
+    fn too_few_samples_or_flat_benchmark_give_none() {
+        assert!(alpha_beta(&equity(&curve(&[0.1])), &curve(&[0.1])).is_none());
+        let flat = curve(&[0.0, 0.0, 0.0]);
+        assert!(alpha_beta(&equity(&curve(&[0.1, -0.1, 0.1])), &flat).is_none());
+    }
+
+    #[test]
+    fn buy_and_hold_buys_once_and_tracks_drawdown() {
+        let mut benchmark = Benchmark::buy_and_hold(1000.0, hour(0));
+        benchmark.on_tick(hour(0), 100.0);
+        assert_eq!(benchmark.saldo_fiat, 0.0);
+        assert_eq!(benchmark.saldo_btc, 10.0);
+        assert!(benchmark.next_buy.is_none());
+
+        benchmark.on_tick(hour(1), 80.0);
+        benchmark.on_tick(hour(2), 120.0);
+        assert_eq!(benchmark.saldo_btc, 10.0);
+        assert_eq!(benchmark.last_value, 1200.0);
+        assert!((benchmark.max_drawdown - 20.0).abs() < 1e-9);
+        assert!((benchmark.return_percentage() - 20.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn weekly_dca_splits_balance_across_the_period() {
+        let start = hour(0);
+        let end = start + Duration::weeks(4);
+        let mut benchmark = Benchmark::dca(DcaInterval::Weekly, 1000.0, start, end);
+        assert_eq!(benchmark.contribution, 250.0);
+
+        // Um candle atrasado executa todos os aportes vencidos de uma vez
+        benchmark.on_tick(start + Duration::weeks(1), 50.0);
+        assert_eq!(benchmark.saldo_fiat, 500.0);
+        assert_eq!(benchmark.saldo_btc, 10.0);
+        assert_eq!(benchmark.next_buy, Some(start + Duration::weeks(2)));
+
+        benchmark.on_tick(start + Duration::weeks(3), 100.0);
+        assert_eq!(benchmark.saldo_fiat, 0.0);
+        assert_eq!(benchmark.saldo_btc, 15.0);
+        assert_eq!(benchmark.last_value, 1500.0);
+    }
+
+    #[test]
+    fn monthly_dca_stays_on_month_end_after_february() {
+        let start = Utc.with_ymd_and_hms(2024, 1, 31, 0, 0, 0).unwrap();
+        let end = Utc.with_ymd_and_hms(2024, 6, 1, 0, 0, 0).unwrap();
+        let mut benchmark = Benchmark::dca(DcaInterval::Monthly, 500.0, start, end);
+        // 31/jan, 29/fev, 31/mar, 30/abr e 31/mai
+        assert_eq!(benchmark.contribution, 100.0);
+
+        let mut buys = Vec::new();
+        let mut time = start;
+        while time < end {
+            if let Some(next) = benchmark.next_buy.filter(|next| *next <= time) {
+                buys.push(next.format("%m-%d").to_string());
+            }
+            benchmark.on_tick(time, 100.0);
+            time += Duration::days(1);
+        }
+        assert_eq!(buys, vec!["01-31", "02-29", "03-31", "04-30", "05-31"]);
+        assert_eq!(benchmark.saldo_fiat, 0.0);
+    }
+
+    #[test]
+    fn alpha_is_annualised_from_the_sample_spacing() {
+        let daily = |points: Vec<(DateTime<Utc>, f64)>| -> Vec<(DateTime<Utc>, f64)> {
+            points
+                .into_iter()
+                .enumerate()
+                .map(|(i, (_, value))| (hour(24 * i as i64), value))
+                .collect()
+        };
+        let excess: Vec<f64> = MARKET.iter().map(|r| r + 0.001).collect();
+        let benchmark = daily(curve(&MARKET));
+        let strategy = equity(&daily(curve(&excess)));
+
+        let (alpha, beta) = alpha_beta(&strategy, &benchmark).unwrap();
+        assert!((beta - 1.0).abs() < 1e-9);
+        assert!((alpha - 0.001 * 365.0 * 100.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn sampling_accumulates_the_same_moments_as_the_full_curves() {
+        let mut benchmark = Benchmark::buy_and_hold(1000.0, hour(0));
+        let prices = [100.0, 110.0, 99.0, 105.0, 102.0];
+        let strategy_values = [1000.0, 1050.0, 1010.0, 1030.0, 1020.0];
+        let mut benchmark_curve = Vec::new();
+        for (i, (price, value)) in prices.iter().zip(strategy_values).enumerate() {
+            benchmark.on_tick(hour(i as i64), *price);
+            let sample = benchmark.sample(hour(i as i64), value);
+            assert_eq!(sample.benchmark, "Buy & Hold");
+            benchmark_curve.push((sample.time, sample.value));
+        }
+        let strategy: Vec<(DateTime<Utc>, f64)> = (0..prices.len())
+            .map(|i| (hour(i as i64), strategy_values[i]))
+            .collect();
+
+        let incremental = benchmark.returns.alpha_beta().unwrap();
+        let full = alpha_beta(&equity(&strategy), &benchmark_curve).unwrap();
+        assert!((incremental.0 - full.0).abs() < 1e-9);
+        assert!((incremental.1 - full.1).abs() < 1e-12);
+        // O estado salvo não carrega as amostras
+        let saved = serde_json::to_value(&benchmark).unwrap();
+        assert!(saved.get("samples").is_none());
+    }
+}
@@ -0,0 +1,662 @@
+use chrono::{DateTime, TimeZone, Utc};
+use memmap2::Mmap;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use tracing::{info, warn};
+
+use crate::reader_csv::{CsvBtcFile, CsvReadOptions, ReaderBtcFile, DEFAULT_CHUNK_SIZE};
+use crate::redis_client::{CandleScan, RedisClient};
+use crate::run_manifest::DatasetFingerprint;
+
+/// Arquivo de cache padrão
+pub const DEFAULT_CACHE_PATH: &str = "data/btc_candles.cache";
+
+const MAGIC: &[u8; 8] = b"BTCCACHE";
+const VERSION: u32 = 2;
+const HEADER_SIZE: usize = 64;
+// Ordem das colunas no arquivo, cada uma com `count` valores de 8 bytes
+const COLUMNS: usize = 6;
+const TIMESTAMP: usize = 0;
+const OPEN: usize = 1;
+const HIGH: usize = 2;
+const LOW: usize = 3;
+const CLOSE: usize = 4;
+const VOLUME: usize = 5;
+
+/// Cache binário colunar de candles, mapeado em memória
+///
+/// Layout (little-endian): cabeçalho de 64 bytes (`BTCCACHE`, versão, quantidade,
+/// primeiro e último timestamp, fingerprint) seguido das colunas `timestamp` (i64, ms),
+/// `open`, `high`, `low`, `close` e `volume` (f64).
+///
+/// A posição `i` no cache corresponde a `btc_i` no Redis, para que a simulação leia o
+/// mesmo candle pelos dois caminhos. Índices sem candle no Redis viram linhas ausentes
+/// (`close` NaN, timestamp do candle anterior) e linhas fora de ordem da entrada são
+/// mantidas, como na importação; a busca por tempo supõe a entrada ordenada. O
+/// fingerprint é o hash do dataset (mesma entrada do `DatasetFingerprint` do manifesto)
+/// sobre todos os candles do cache.
+pub struct CandleCache {
+    path: PathBuf,
+    mmap: Mmap,
+    count: usize,
+    first: i64,
+    last: i64,
+    fingerprint: u64,
+}
+
+impl CandleCache {
+    pub fn open(path: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        if cfg!(target_endian = "big") {
+            return Err("Cache de candles suportado apenas em plataformas little-endian".into());
+        }
+
+        let file = File::open(path)?;
+        // SAFETY: o cache é gravado por completo num arquivo temporário e renomeado;
+        // o arquivo final nunca é modificado no lugar enquanto estiver mapeado.
+        let mmap = unsafe { Mmap::map(&file)? };
+
+        if mmap.len() < HEADER_SIZE || &mmap[..8] != MAGIC {
+            return Err(format!("{} não é um cache de candles", path).into());
+        }
+        let version = u32::from_le_bytes(mmap[8..12].try_into()?);
+        if version != VERSION {
+            return Err(format!(
+                "Versão de cache não suportada: {} (recrie com `cargo run cache build`)",
+                version
+            )
+            .into());
+        }
+        // Quantidade corrompida não pode estourar o cálculo do tamanho esperado
+        let count = usize::try_from(u64::from_le_bytes(mmap[16..24].try_into()?)).ok();
+        let expected = count
+            .and_then(|count| count.checked_mul(COLUMNS * 8))
+            .and_then(|columns| columns.checked_add(HEADER_SIZE));
+        let count = match (count, expected) {
+            (Some(count), Some(expected)) if expected == mmap.len() => count,
+            _ => return Err(format!("Cache {} truncado ou corrompido", path).into()),
+        };
+        let first = i64::from_le_bytes(mmap[24..32].try_into()?);
+        let last = i64::from_le_bytes(mmap[32..40].try_into()?);
+        let fingerprint = u64::from_le_bytes(mmap[40..48].try_into()?);
+
+        Ok(Self {
+            path: PathBuf::from(path),
+            mmap,
+            count,
+            first,
+            last,
+            fingerprint,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn len(&self) -> usize {
+        self.count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// Hash do dataset gravado no cabeçalho
+    pub fn fingerprint(&self) -> String {
+        format!("{:016x}", self.fingerprint)
+    }
+
+    fn column_bytes(&self, column: usize) -> &[u8] {
+        let start = HEADER_SIZE + column * self.count * 8;
+        &self.mmap[start..start + self.count * 8]
+    }
+
+    fn f64_column(&self, column: usize) -> &[f64] {
+        let bytes = self.column_bytes(column);
+        // SAFETY: o mmap começa alinhado à página e cabeçalho e colunas têm tamanho
+        // múltiplo de 8; o tamanho foi validado em `open`.
+        unsafe { std::slice::from_raw_parts(bytes.as_ptr() as *const f64, self.count) }
+    }
+
+    /// Timestamps em milissegundos desde epoch (zero-copy)
+    pub fn timestamps(&self) -> &[i64] {
+        let bytes = self.column_bytes(TIMESTAMP);
+        // SAFETY: mesmo alinhamento e tamanho de `f64_column`
+        unsafe { std::slice::from_raw_parts(bytes.as_ptr() as *const i64, self.count) }
+    }
+
+    pub fn opens(&self) -> &[f64] {
+        self.f64_column(OPEN)
+    }
+
+    pub fn highs(&self) -> &[f64] {
+        self.f64_column(HIGH)
+    }
+
+    pub fn lows(&self) -> &[f64] {
+        self.f64_column(LOW)
+    }
+
+    pub fn closes(&self) -> &[f64] {
+        self.f64_column(CLOSE)
+    }
+
+    pub fn volumes(&self) -> &[f64] {
+        self.f64_column(VOLUME)
+    }
+
+    /// Candle da posição `index` (`btc_{index}`); `None` fora do cache ou em linha ausente
+    pub fn get(&self, index: usize) -> Option<CsvBtcFile> {
+        if index >= self.count || self.closes()[index].is_nan() {
+            return None;
+        }
+        Some(CsvBtcFile {
+            open: self.opens()[index],
+            high: self.highs()[index],
+            low: self.lows()[index],
+            close: self.closes()[index],
+            volume: self.volumes()[index],
+            timestamp: Utc
+                .timestamp_millis_opt(self.timestamps()[index])
+                .single()?,
+        })
+    }
+
+    /// Índice do primeiro candle com timestamp >= `time` (busca binária)
+    pub fn index_at_or_after(&self, time: DateTime<Utc>) -> usize {
+        let millis = time.timestamp_millis();
+        self.timestamps().partition_point(|&ts| ts < millis)
+    }
+
+    /// Índice do candle com exatamente este timestamp
+    pub fn find(&self, time: DateTime<Utc>) -> Option<usize> {
+        // Linhas ausentes repetem o timestamp anterior: o primeiro é o candle real
+        let index = self.index_at_or_after(time);
+        (self.timestamps().get(index) == Some(&time.timestamp_millis())
+            && self.get(index).is_some())
+        .then_some(index)
+    }
+
+    pub fn first_time(&self) -> Option<DateTime<Utc>> {
+        if self.is_empty() {
+            return None;
+        }
+        Utc.timestamp_millis_opt(self.first).single()
+    }
+
+    pub fn last_time(&self) -> Option<DateTime<Utc>> {
+        if self.is_empty() {
+            return None;
+        }
+        Utc.timestamp_millis_opt(self.last).single()
+    }
+
+    /// Confere o cache com o dataset atual do Redis numa amostra de índices
+    ///
+    /// Compara o primeiro e o último candle, posições espaçadas entre eles e o índice
+    /// logo após o fim; qualquer diferença indica um cache desatualizado.
+    pub fn verify_against(&self, redis: &RedisClient) -> Result<(), Box<dyn std::error::Error>> {
+        const SAMPLES: usize = 16;
+        let mut indexes: Vec<usize> = (0..SAMPLES)
+            .map(|i| i * self.count.saturating_sub(1) / (SAMPLES - 1))
+            .collect();
+        indexes.push(self.count);
+        indexes.dedup();
+
+        for index in indexes {
+            let cached = self.get(index);
+            let current = redis.load_by_index(index)?;
+            let same = match (&cached, &current) {
+                (None, None) => true,
+                (Some(a), Some(b)) => same_candle(a, b),
+                _ => false,
+            };
+            if !same {
+                return Err(format!(
+                    "Cache {} desatualizado: btc_{} difere do Redis (recrie com `cargo run cache build`)",
+                    self.path.display(),
+                    index
+                )
+                .into());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Mesmo candle, com o timestamp na precisão do cache (ms)
+fn same_candle(a: &CsvBtcFile, b: &CsvBtcFile) -> bool {
+    a.timestamp.timestamp_millis() == b.timestamp.timestamp_millis()
+        && [a.open, a.high, a.low, a.close, a.volume]
+            .iter()
+            .zip([b.open, b.high, b.low, b.close, b.volume])
+            .all(|(x, y)| x.to_bits() == y.to_bits())
+}
+
+/// Grava o cache em colunas temporárias e monta o arquivo final no `finish`
+///
+/// Cada coluna vai para um arquivo próprio durante a construção para manter a memória
+/// constante; o arquivo final é escrito em `<saída>.tmp` e renomeado.
+struct CacheWriter {
+    output: PathBuf,
+    column_paths: Vec<PathBuf>,
+    columns: Vec<BufWriter<File>>,
+    count: u64,
+    first: Option<i64>,
+    last: Option<i64>,
+    // Índices sem candle ainda não gravados (descartados se não houver candle depois)
+    missing: u64,
+    unordered: u64,
+    fingerprint: DatasetFingerprint,
+}
+
+impl CacheWriter {
+    fn create(output: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let output = PathBuf::from(output);
+        if let Some(parent) = output.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+        let mut column_paths = Vec::with_capacity(COLUMNS);
+        let mut columns = Vec::with_capacity(COLUMNS);
+        for column in 0..COLUMNS {
+            let path = PathBuf::from(format!("{}.col{}.tmp", output.display(), column));
+            columns.push(BufWriter::new(File::create(&path)?));
+            column_paths.push(path);
+        }
+        Ok(Self {
+            output,
+            column_paths,
+            columns,
+            count: 0,
+            first: None,
+            last: None,
+            missing: 0,
+            unordered: 0,
+            fingerprint: DatasetFingerprint::new(""),
+        })
+    }
+
+    /// Índice sem candle no Redis, mantido para não deslocar os seguintes
+    fn push_missing(&mut self) {
+        self.missing += 1;
+    }
+
+    fn push(&mut self, candle: &CsvBtcFile) -> Result<(), Box<dyn std::error::Error>> {
+        let millis = candle.timestamp.timestamp_millis();
+        for _ in 0..self.missing {
+            let previous = self.last.unwrap_or(0);
+            self.write_row(previous, [f64::NAN; 5])?;
+        }
+        self.missing = 0;
+
+        if self.last.is_some_and(|last| millis <= last) {
+            self.unordered += 1;
+        }
+        self.fingerprint.update(self.count as usize, candle);
+        self.write_row(
+            millis,
+            [
+                candle.open,
+                candle.high,
+                candle.low,
+                candle.close,
+                candle.volume,
+            ],
+        )?;
+
+        self.first.get_or_insert(millis);
+        self.last = Some(millis);
+        Ok(())
+    }
+
+    fn write_row(
+        &mut self,
+        millis: i64,
+        values: [f64; 5],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.columns[TIMESTAMP].write_all(&millis.to_le_bytes())?;
+        for (column, value) in [OPEN, HIGH, LOW, CLOSE, VOLUME].into_iter().zip(values) {
+            self.columns[column].write_all(&value.to_le_bytes())?;
+        }
+        self.count += 1;
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<(u64, u64), Box<dyn std::error::Error>> {
+        for column in &mut self.columns {
+            column.flush()?;
+        }
+
+        let tmp_path = PathBuf::from(format!("{}.tmp", self.output.display()));
+        let mut out = BufWriter::new(File::create(&tmp_path)?);
+
+        let mut header = [0u8; HEADER_SIZE];
+        header[..8].copy_from_slice(MAGIC);
+        header[8..12].copy_from_slice(&VERSION.to_le_bytes());
+        header[16..24].copy_from_slice(&self.count.to_le_bytes());
+        header[24..32].copy_from_slice(&self.first.unwrap_or(0).to_le_bytes());
+        header[32..40].copy_from_slice(&self.last.unwrap_or(0).to_le_bytes());
+        header[40..48].copy_from_slice(&self.fingerprint.digest().to_le_bytes());
+        out.write_all(&header)?;
+
+        for path in &self.column_paths {
+            std::io::copy(&mut File::open(path)?, &mut out)?;
+        }
+        out.flush()?;
+        drop(out);
+
+        fs::rename(&tmp_path, &self.output)?;
+        self.remove_columns();
+        Ok((self.count, self.unordered))
+    }
+
+    fn remove_columns(&self) {
+        for path in &self.column_paths {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+/// Constrói o cache a partir de um CSV (arquivo, diretório ou glob)
+pub fn build_from_input(
+    input: &str,
+    options: &CsvReadOptions,
+    output: &str,
+) -> Result<(u64, u64), Box<dyn std::error::Error>> {
+    let mut stream = ReaderBtcFile::open_stream(input, options)?;
+    let mut writer = CacheWriter::create(output)?;
+
+    loop {
+        let chunk = stream.next_chunk(DEFAULT_CHUNK_SIZE);
+        if let Err(e) = stream.ensure_complete() {
+            writer.remove_columns();
+            return Err(e);
+        }
+        if chunk.is_empty() {
+            break;
+        }
+        for candle in &chunk {
+            writer.push(candle)?;
+        }
+    }
+    stream.into_report().display();
+
+    writer.finish()
+}
+
+/// Constrói o cache a partir dos candles `btc_N` do Redis
+pub fn build_from_redis(
+    redis: &RedisClient,
+    output: &str,
+) -> Result<(u64, u64), Box<dyn std::error::Error>> {
+    const BATCH: usize = 10_000;
+    let mut writer = CacheWriter::create(output)?;
+
+    let mut scan = CandleScan::start(redis, BATCH)?;
+    while let Some((start, batch)) = scan.next_batch(redis)? {
+        for candle in &batch {
+            match candle {
+                Some(candle) => writer.push(candle)?,
+                None => writer.push_missing(),
+            }
+        }
+        println!("📥 {} índices lidos do Redis...", start + batch.len());
+    }
+
+    writer.finish()
+}
+
+/// Constrói o cache a partir de candles em memória (`None` é um índice sem candle)
+#[cfg(test)]
+pub fn build_from_candles(
+    candles: &[Option<CsvBtcFile>],
+    output: &str,
+) -> Result<(u64, u64), Box<dyn std::error::Error>> {
+    let mut writer = CacheWriter::create(output)?;
+    for candle in candles {
+        match candle {
+            Some(candle) => writer.push(candle)?,
+            None => writer.push_missing(),
+        }
+    }
+    writer.finish()
+}
+
+/// Comando `cache build|info`
+///
+/// - `cache build [entrada|--redis] [--output arquivo]`
+/// - `cache info [arquivo] [--at timestamp]`
+pub fn run_cache_command(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    match args.first().map(String::as_str) {
+        Some("build") => {
+            let mut input = "data/btc_historical_data.csv".to_string();
+            let mut use_redis = false;
+            let mut output = DEFAULT_CACHE_PATH.to_string();
+
+            let mut iter = args[1..].iter();
+            while let Some(arg) = iter.next() {
+                match arg.as_str() {
+                    "--redis" => use_redis = true,
+                    "--output" => {
+                        output = iter.next().ok_or("--output requer um arquivo")?.clone();
+                    }
+                    path => input = path.to_string(),
+                }
+            }
+
+            let start = Instant::now();
+            let (count, unordered) = if use_redis {
+                build_from_redis(&RedisClient::from_env()?, &output)?
+            } else {
+                build_from_input(&input, &CsvReadOptions::from_env()?, &output)?
+            };
+            if unordered > 0 {
+                warn!(
+                    "⚠️  {} candles fora de ordem no cache (busca por tempo aproximada)",
+                    unordered
+                );
+                println!(
+                    "⚠️  {} candles fora de ordem no cache (busca por tempo aproximada)",
+                    unordered
+                );
+            }
+            info!(
+                "🗄️  Cache {} criado: {} candles em {:.2?}",
+                output,
+                count,
+                start.elapsed()
+            );
+            println!(
+                "🗄️  Cache {} criado: {} candles em {:.2?}",
+                output,
+                count,
+                start.elapsed()
+            );
+            Ok(())
+        }
+        Some("info") => {
+            let mut path = DEFAULT_CACHE_PATH.to_string();
+            let mut at = None;
+
+            let mut iter = args[1..].iter();
+            while let Some(arg) = iter.next() {
+                match arg.as_str() {
+                    "--at" => at = iter.next().cloned(),
+                    other => path = other.to_string(),
+                }
+            }
+
+            let cache = CandleCache::open(&path)?;
+            println!("🗄️  Cache: {}", cache.path().display());
+            println!("📊 Candles: {}", cache.len());
+            println!("🔑 Dataset: {}", cache.fingerprint());
+            if cache.is_empty() {
+                println!("⚠️  Cache vazio");
+            }
+            if let (Some(first), Some(last)) = (cache.first_time(), cache.last_time()) {
+                println!(
+                    "📅 Período: {} → {}",
+                    first.format("%Y-%m-%d %H:%M"),
+                    last.format("%Y-%m-%d %H:%M")
+                );
+            }
+
+            if let Some(at) = at {
+                let time = crate::reader_csv::parse_timestamp(&at)
+                    .ok_or_else(|| format!("Timestamp inválido: {}", at))?;
+                let exact = cache.find(time);
+                let index = exact.unwrap_or_else(|| cache.index_at_or_after(time));
+                match cache.get(index) {
+                    Some(candle) => println!(
+                        "🔎 Índice {} ({}{}): O {:.2} H {:.2} L {:.2} C {:.2} V {:.4}",
+                        index,
+                        if exact.is_some() { "" } else { "próximo de " },
+                        candle.timestamp.format("%Y-%m-%d %H:%M"),
+                        candle.open,
+                        candle.high,
+                        candle.low,
+                        candle.close,
+                        candle.volume
+                    ),
+                    None => println!("🔎 Nenhum candle a partir de {}", time),
+                }
+            }
+            Ok(())
+        }
+        _ => Err("Subcomando desconhecido. Use: cargo run cache build|info".into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(minute: i64) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap() + chrono::Duration::minutes(minute)
+    }
+
+    fn candle(minute: i64) -> CsvBtcFile {
+        let price = 40_000.0 + minute as f64;
+        CsvBtcFile {
+            open: price,
+            high: price + 5.0,
+            low: price - 5.0,
+            close: price + 1.0,
+            volume: minute as f64,
+            timestamp: at(minute),
+        }
+    }
+
+    fn temp_path(name: &str) -> String {
+        let path =
+            std::env::temp_dir().join(format!("btc-cache-{}-{}.cache", name, std::process::id()));
+        path.to_str().unwrap().to_string()
+    }
+
+    fn build(name: &str, candles: &[Option<CsvBtcFile>]) -> String {
+        let path = temp_path(name);
+        build_from_candles(candles, &path).unwrap();
+        path
+    }
+
+    #[test]
+    fn cache_round_trips_candles_and_missing_rows() {
+        let candles = vec![Some(candle(0)), Some(candle(1)), None, Some(candle(3))];
+        let path = build("round-trip", &candles);
+        let cache = CandleCache::open(&path).unwrap();
+
+        assert_eq!(cache.len(), 4);
+        assert!(same_candle(&cache.get(1).unwrap(), &candle(1)));
+        assert!(cache.get(2).is_none());
+        assert!(cache.get(4).is_none());
+        assert_eq!(cache.first_time(), Some(at(0)));
+        assert_eq!(cache.last_time(), Some(at(3)));
+        assert_eq!(cache.find(at(3)), Some(3));
+        assert_eq!(cache.find(at(2)), None);
+        assert_eq!(cache.index_at_or_after(at(2)), 3);
+
+        // Mesmo hash do manifesto sobre os candles reais
+        let mut fingerprint = DatasetFingerprint::new("");
+        for (index, candle) in [candle(0), candle(1), candle(3)].iter().enumerate() {
+            fingerprint.update(index, candle);
+        }
+        assert_eq!(cache.fingerprint(), fingerprint.hash);
+        drop(cache);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn build_from_csv_matches_the_input() {
+        let csv = temp_path("input").replace(".cache", ".csv");
+        let mut data = String::from("timestamp,open,high,low,close,volume\n");
+        for minute in 0..5 {
+            let c = candle(minute);
+            data.push_str(&format!(
+                "{},{},{},{},{},{}\n",
+                c.timestamp.to_rfc3339(),
+                c.open,
+                c.high,
+                c.low,
+                c.close,
+                c.volume
+            ));
+        }
+        fs::write(&csv, data).unwrap();
+        let path = temp_path("from-csv");
+
+        let (count, unordered) = build_from_input(&csv, &CsvReadOptions::default(), &path).unwrap();
+        let cache = CandleCache::open(&path).unwrap();
+        let closes = cache.closes().to_vec();
+        drop(cache);
+        fs::remove_file(&csv).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!((count, unordered), (5, 0));
+        assert_eq!(
+            closes,
+            vec![40_001.0, 40_002.0, 40_003.0, 40_004.0, 40_005.0]
+        );
+        // As colunas temporárias não ficam para trás
+        assert!(!Path::new(&format!("{}.col0.tmp", path)).exists());
+    }
+
+    #[test]
+    fn truncated_cache_is_rejected() {
+        let path = build("truncated", &[Some(candle(0)), Some(candle(1))]);
+        let data = fs::read(&path).unwrap();
+        fs::write(&path, &data[..data.len() - 8]).unwrap();
+
+        let error = CandleCache::open(&path).err().unwrap().to_string();
+        fs::remove_file(&path).unwrap();
+        assert!(error.contains("truncado"), "{}", error);
+    }
+
+    #[test]
+    fn wrong_magic_is_rejected() {
+        let path = build("magic", &[Some(candle(0))]);
+        let mut data = fs::read(&path).unwrap();
+        data[..8].copy_from_slice(b"NOTCACHE");
+        fs::write(&path, &data).unwrap();
+
+        let error = CandleCache::open(&path).err().unwrap().to_string();
+        fs::remove_file(&path).unwrap();
+        assert!(error.contains("não é um cache"), "{}", error);
+    }
+
+    #[test]
+    fn overflowing_count_is_rejected() {
+        let path = build("overflow", &[Some(candle(0))]);
+        let mut data = fs::read(&path).unwrap();
+        // (2^60 + 1) * 48 + 64 dá a volta em u64 e, sem aritmética verificada, bateria
+        // exatamente com o tamanho do arquivo de um candle
+        let count: u64 = (1 << 60) + 1;
+        data[16..24].copy_from_slice(&count.to_le_bytes());
+        fs::write(&path, &data).unwrap();
+
+        let error = CandleCache::open(&path).err().unwrap().to_string();
+        fs::remove_file(&path).unwrap();
+        assert!(error.contains("truncado ou corrompido"), "{}", error);
+    }
+}
@@ -0,0 +1,284 @@
+use chrono::{DateTime, TimeZone, Utc};
+use std::fmt::Write;
+
+/// Paleta usada para as séries dos gráficos
+pub const COLORS: [&str; 6] = [
+    "#2563eb", "#dc2626", "#16a34a", "#d97706", "#7c3aed", "#0891b2",
+];
+
+/// Série temporal a ser desenhada em um gráfico de linhas
+pub struct ChartSeries<'a> {
+    pub label: &'a str,
+    pub color: &'a str,
+    pub points: Vec<(DateTime<Utc>, f64)>,
+}
+
+/// Marcador pontual (ex.: compras e vendas) sobre o gráfico
+pub struct ChartMarker {
+    pub time: DateTime<Utc>,
+    pub value: f64,
+    pub color: &'static str,
+    pub label: String,
+}
+
+/// Escapa texto para uso dentro de SVG/HTML
+pub fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Reduz uma polilinha (em pixels, ordenada no tempo) a no máximo quatro pontos por
+/// coluna de pixel: o primeiro, o mínimo, o máximo e o último, na ordem original
+///
+/// Curvas de anos de candles de minuto teriam milhões de pontos; na largura do gráfico
+/// o desenho é o mesmo, inclusive os picos.
+fn downsample(points: &[(f64, f64)]) -> Vec<(f64, f64)> {
+    let mut result = Vec::new();
+    let mut start = 0;
+    while start < points.len() {
+        let column = points[start].0.floor();
+        let end = start
+            + points[start..]
+                .iter()
+                .take_while(|p| p.0.floor() == column)
+                .count();
+        let bucket = &points[start..end];
+
+        let mut keep = vec![0, bucket.len() - 1];
+        let by_value =
+            |a: &(usize, &(f64, f64)), b: &(usize, &(f64, f64))| a.1 .1.total_cmp(&b.1 .1);
+        if let Some((i, _)) = bucket.iter().enumerate().min_by(by_value) {
+            keep.push(i);
+        }
+        if let Some((i, _)) = bucket.iter().enumerate().max_by(by_value) {
+            keep.push(i);
+        }
+        keep.sort_unstable();
+        keep.dedup();
+        result.extend(keep.into_iter().map(|i| bucket[i]));
+
+        start = end;
+    }
+    result
+}
+
+/// Gera um gráfico de linhas SVG autocontido com eixo de tempo compartilhado
+pub fn line_chart_svg(
+    title: &str,
+    series: &[ChartSeries],
+    markers: &[ChartMarker],
+    width: u32,
+    height: u32,
+) -> String {
+    let (left, right, top, bottom) = (70.0, 20.0, 30.0, 40.0);
+    let plot_w = width as f64 - left - right;
+    let plot_h = height as f64 - top - bottom;
+
+    let mut all_points: Vec<(DateTime<Utc>, f64)> = series
+        .iter()
+        .flat_map(|s| s.points.iter().copied())
+        .collect();
+    all_points.extend(markers.iter().map(|m| (m.time, m.value)));
+
+    let mut svg = String::new();
+    let _ = write!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}" font-family="sans-serif" font-size="11">"#,
+        w = width,
+        h = height
+    );
+    let _ = write!(
+        svg,
+        r##"<rect width="{}" height="{}" fill="#ffffff"/><text x="{}" y="18" font-size="13" font-weight="bold">{}</text>"##,
+        width,
+        height,
+        left,
+        escape(title)
+    );
+
+    if all_points.is_empty() {
+        let _ = write!(
+            svg,
+            r#"<text x="{}" y="{}">Sem dados</text></svg>"#,
+            left,
+            top + plot_h / 2.0
+        );
+        return svg;
+    }
+
+    let t_min = all_points
+        .iter()
+        .map(|p| p.0.timestamp())
+        .min()
+        .unwrap_or(0);
+    let t_max = all_points
+        .iter()
+        .map(|p| p.0.timestamp())
+        .max()
+        .unwrap_or(0);
+    let mut v_min = all_points.iter().map(|p| p.1).fold(f64::INFINITY, f64::min);
+    let mut v_max = all_points
+        .iter()
+        .map(|p| p.1)
+        .fold(f64::NEG_INFINITY, f64::max);
+    if (v_max - v_min).abs() < f64::EPSILON {
+        v_min -= 1.0;
+        v_max += 1.0;
+    }
+    let t_span = (t_max - t_min).max(1) as f64;
+
+    let x = |t: DateTime<Utc>| left + (t.timestamp() - t_min) as f64 / t_span * plot_w;
+    let y = |v: f64| top + (v_max - v) / (v_max - v_min) * plot_h;
+
+    // Grade e rótulos dos eixos
+    for i in 0..=4 {
+        let value = v_min + (v_max - v_min) * i as f64 / 4.0;
+        let py = y(value);
+        let _ = write!(
+            svg,
+            r##"<line x1="{l}" y1="{py:.1}" x2="{r}" y2="{py:.1}" stroke="#e5e7eb"/><text x="{tx}" y="{ty:.1}" text-anchor="end">{value:.2}</text>"##,
+            l = left,
+            r = left + plot_w,
+            py = py,
+            tx = left - 6.0,
+            ty = py + 4.0,
+            value = value
+        );
+
+        let ts = t_min + ((t_max - t_min) as f64 * i as f64 / 4.0) as i64;
+        if let Some(time) = Utc.timestamp_opt(ts, 0).single() {
+            let _ = write!(
+                svg,
+                r#"<text x="{:.1}" y="{:.1}" text-anchor="middle">{}</text>"#,
+                x(time),
+                top + plot_h + 16.0,
+                time.format("%Y-%m-%d")
+            );
+        }
+    }
+
+    for s in series {
+        if s.points.is_empty() {
+            continue;
+        }
+        let pixels: Vec<(f64, f64)> = s.points.iter().map(|(t, v)| (x(*t), y(*v))).collect();
+        let path = downsample(&pixels)
+            .iter()
+            .map(|(px, py)| format!("{:.1},{:.1}", px, py))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let _ = write!(
+            svg,
+            r#"<polyline fill="none" stroke="{}" stroke-width="1.5" points="{}"/>"#,
+            s.color, path
+        );
+    }
+
+    for m in markers {
+        let _ = write!(
+            svg,
+            r#"<circle cx="{:.1}" cy="{:.1}" r="3" fill="{}"><title>{}</title></circle>"#,
+            x(m.time),
+            y(m.value),
+            m.color,
+            escape(&m.label)
+        );
+    }
+
+    // Legenda
+    for (i, s) in series.iter().enumerate() {
+        let lx = left + 10.0 + (i as f64) * 160.0;
+        let _ = write!(
+            svg,
+            r#"<rect x="{lx:.1}" y="{ly:.1}" width="10" height="10" fill="{c}"/><text x="{tx:.1}" y="{ty:.1}">{label}</text>"#,
+            lx = lx,
+            ly = top + 4.0,
+            c = s.color,
+            tx = lx + 14.0,
+            ty = top + 13.0,
+            label = escape(s.label)
+        );
+    }
+
+    svg.push_str("</svg>");
+    svg
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(hour: i64) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap() + chrono::Duration::hours(hour)
+    }
+
+    #[test]
+    fn downsample_keeps_first_extremes_and_last_of_each_column() {
+        let points = [
+            (10.2, 5.0),
+            (10.4, 9.0),
+            (10.5, 1.0),
+            (10.7, 4.0),
+            (10.9, 6.0),
+            (11.1, 3.0),
+        ];
+        assert_eq!(
+            downsample(&points),
+            vec![
+                (10.2, 5.0),
+                (10.4, 9.0),
+                (10.5, 1.0),
+                (10.9, 6.0),
+                (11.1, 3.0)
+            ]
+        );
+        assert!(downsample(&[]).is_empty());
+    }
+
+    #[test]
+    fn long_series_is_bounded_by_the_plot_width() {
+        let series = [ChartSeries {
+            label: "BTC",
+            color: COLORS[0],
+            points: (0..50_000)
+                .map(|i| {
+                    (
+                        at(i),
+                        (i % 97) as f64 + if i == 31_337 { 1000.0 } else { 0.0 },
+                    )
+                })
+                .collect(),
+        }];
+        let svg = line_chart_svg("Longa", &series, &[], 400, 200);
+
+        let points = svg
+            .split("points=\"")
+            .nth(1)
+            .and_then(|rest| rest.split('"').next())
+            .unwrap();
+        let count = points.split(' ').count();
+        // 310 px de área útil, no máximo 4 pontos por coluna
+        assert!(count <= 4 * 311, "{} pontos", count);
+        // O pico continua no desenho, no topo da área do gráfico
+        assert!(points.split(' ').any(|p| p.ends_with(",30.0")));
+    }
+
+    #[test]
+    fn empty_chart_and_labels_are_escaped() {
+        let svg = line_chart_svg("A < B & C", &[], &[], 300, 100);
+        assert!(svg.contains("A &lt; B &amp; C"));
+        assert!(svg.contains("Sem dados"));
+        assert!(svg.ends_with("</svg>"));
+
+        let markers = [ChartMarker {
+            time: at(1),
+            value: 10.0,
+            color: "#000",
+            label: "compra \"1\"".to_string(),
+        }];
+        let svg = line_chart_svg("Marcadores", &[], &markers, 300, 100);
+        assert!(svg.contains("<title>compra &quot;1&quot;</title>"));
+    }
+}
@@ -0,0 +1,441 @@
+use chrono::Utc;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::PathBuf;
+use tracing::{info, warn};
+
+use crate::chart::{self, ChartSeries, COLORS};
+use crate::run_manifest::{SavedRun, RUNS_DIR};
+
+/// Linha da tabela comparativa: rótulo, valor por execução e se deve exibir diferença
+struct MetricRow {
+    label: &'static str,
+    values: Vec<f64>,
+    decimals: usize,
+    show_diff: bool,
+}
+
+fn metric_rows(runs: &[SavedRun]) -> Vec<MetricRow> {
+    let collect = |f: &dyn Fn(&SavedRun) -> f64| runs.iter().map(f).collect::<Vec<_>>();
+
+    vec![
+        MetricRow {
+            label: "Saldo inicial ($)",
+            values: collect(&|r| r.state.config.initial_balance),
+            decimals: 2,
+            show_diff: false,
+        },
+        MetricRow {
+            label: "Valor final ($)",
+            values: collect(&|r| r.final_value()),
+            decimals: 2,
+            show_diff: true,
+        },
+        MetricRow {
+            label: "Retorno (%)",
+            values: collect(&|r| r.return_percentage()),
+            decimals: 2,
+            show_diff: true,
+        },
+        MetricRow {
+            label: "Lucro líquido trades ($)",
+            values: collect(&|r| r.state.stats.net_profit()),
+            decimals: 2,
+            show_diff: true,
+        },
+        MetricRow {
+            label: "P&L de funding ($)",
+            values: collect(&|r| r.state.stats.total_funding),
+            decimals: 2,
+            show_diff: true,
+        },
+        MetricRow {
+            label: "Total de trades",
+            values: collect(&|r| r.state.stats.total_trades as f64),
+            decimals: 0,
+            show_diff: true,
+        },
+        MetricRow {
+            label: "Trades vencedores",
+            values: collect(&|r| r.state.stats.winning_trades as f64),
+            decimals: 0,
+            show_diff: true,
+        },
+        MetricRow {
+            label: "Win rate (%)",
+            values: collect(&|r| r.state.stats.win_rate()),
+            decimals: 1,
+            show_diff: true,
+        },
+        MetricRow {
+            label: "Drawdown máximo (%)",
+            values: collect(&|r| r.state.stats.max_drawdown),
+            decimals: 2,
+            show_diff: true,
+        },
+        MetricRow {
+            label: "Ordens abertas",
+            values: collect(&|r| r.state.buy_orders.len() as f64),
+            decimals: 0,
+            show_diff: true,
+        },
+        MetricRow {
+            label: "Saldo BTC",
+            values: collect(&|r| r.state.saldo_btc),
+            decimals: 6,
+            show_diff: false,
+        },
+    ]
+}
+
+/// Compara duas ou mais execuções salvas e gera um relatório HTML com as curvas de patrimônio
+///
+/// Argumentos: referências das execuções (run id, diretório ou arquivo de estado) e
+/// opcionalmente `--output <arquivo.html>`.
+pub fn compare_runs(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let mut references = Vec::new();
+    let mut output: Option<PathBuf> = None;
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if arg == "--output" {
+            output = iter.next().map(PathBuf::from);
+        } else {
+            references.push(arg.as_str());
+        }
+    }
+
+    if references.len() < 2 {
+        return Err("Informe pelo menos duas execuções para comparar".into());
+    }
+
+    let runs = references
+        .iter()
+        .map(|r| SavedRun::load(r))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    info!("⚖️  Comparando {} execuções", runs.len());
+
+    let mut hash_versions: Vec<u32> = runs
+        .iter()
+        .filter_map(|run| run.manifest.as_ref())
+        .map(|m| m.dataset.hash_version)
+        .collect();
+    hash_versions.sort_unstable();
+    hash_versions.dedup();
+    if hash_versions.len() > 1 {
+        warn!(
+            "⚠️  Hashes de dataset em versões diferentes ({:?}): não são comparáveis entre si",
+            hash_versions
+        );
+        println!(
+            "⚠️  Hashes de dataset em versões diferentes ({:?}): não são comparáveis entre si",
+            hash_versions
+        );
+    }
+
+    let settings: Vec<_> = runs
+        .iter()
+        .filter_map(|run| run.manifest.as_ref()?.settings.as_ref())
+        .collect();
+    if settings.windows(2).any(|pair| pair[0] != pair[1]) {
+        warn!("⚠️  Execuções com ambiente diferente (funding, candles ausentes ou cache)");
+        println!("⚠️  Execuções com ambiente diferente (funding, candles ausentes ou cache):");
+        for run in &runs {
+            if let Some(settings) = run.manifest.as_ref().and_then(|m| m.settings.as_ref()) {
+                println!("   {}: {}", run.name, settings.describe());
+            }
+        }
+    }
+
+    let rows = metric_rows(&runs);
+    print_table(&runs, &rows);
+
+    let output = output.unwrap_or_else(|| {
+        PathBuf::from(RUNS_DIR).join(format!(
+            "compare-{}.html",
+            Utc::now().format("%Y%m%d-%H%M%S")
+        ))
+    });
+    if let Some(parent) = output.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&output, render_html(&runs, &rows))?;
+
+    info!("📄 Relatório de comparação salvo em {}", output.display());
+    println!("\n📄 Relatório de comparação salvo em {}", output.display());
+
+    Ok(())
+}
+
+fn print_table(runs: &[SavedRun], rows: &[MetricRow]) {
+    let base = &runs[0].name;
+    let col = 22;
+
+    println!("\n⚖️  COMPARAÇÃO DE EXECUÇÕES (base: {})", base);
+    println!("{}", "=".repeat(28 + col * (runs.len() * 2 - 1)));
+
+    let mut header = format!("{:<28}", "Métrica");
+    for run in runs {
+        header.push_str(&format!(
+            "{:>col$}",
+            truncate(&run.name, col - 2),
+            col = col
+        ));
+    }
+    for run in &runs[1..] {
+        header.push_str(&format!(
+            "{:>col$}",
+            format!("Δ {}", truncate(&run.name, col - 4)),
+            col = col
+        ));
+    }
+    println!("{}", header);
+    println!("{}", "-".repeat(28 + col * (runs.len() * 2 - 1)));
+
+    for row in rows {
+        let mut line = format!("{:<28}", row.label);
+        for value in &row.values {
+            line.push_str(&format!(
+                "{:>col$.prec$}",
+                value,
+                col = col,
+                prec = row.decimals
+            ));
+        }
+        for value in &row.values[1..] {
+            if row.show_diff {
+                line.push_str(&format!(
+                    "{:>+col$.prec$}",
+                    value - row.values[0],
+                    col = col,
+                    prec = row.decimals
+                ));
+            } else {
+                line.push_str(&format!("{:>col$}", "-", col = col));
+            }
+        }
+        println!("{}", line);
+    }
+    println!("{}", "=".repeat(28 + col * (runs.len() * 2 - 1)));
+}
+
+fn truncate(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        text.to_string()
+    } else {
+        text.chars().take(max).collect()
+    }
+}
+
+fn render_html(runs: &[SavedRun], rows: &[MetricRow]) -> String {
+    let equity_series: Vec<ChartSeries> = runs
+        .iter()
+        .enumerate()
+        .map(|(i, run)| ChartSeries {
+            label: &run.name,
+            color: COLORS[i % COLORS.len()],
+            points: run
+                .equity_curve
+                .iter()
+                .map(|p| (p.time, p.total_value))
+                .collect(),
+        })
+        .collect();
+    let drawdown_series: Vec<ChartSeries> = runs
+        .iter()
+        .enumerate()
+        .map(|(i, run)| ChartSeries {
+            label: &run.name,
+            color: COLORS[i % COLORS.len()],
+            points: run
+                .equity_curve
+                .iter()
+                .map(|p| (p.time, -p.drawdown))
+                .collect(),
+        })
+        .collect();
+
+    let mut html = String::new();
+    let _ = write!(
+        html,
+        "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>Comparação de execuções</title>\
+         <style>body{{font-family:sans-serif;margin:24px}}table{{border-collapse:collapse}}\
+         td,th{{border:1px solid #ddd;padding:4px 10px;text-align:right}}th:first-child,td:first-child{{text-align:left}}\
+         .pos{{color:#16a34a}}.neg{{color:#dc2626}}</style></head><body>"
+    );
+    let _ = write!(
+        html,
+        "<h1>Comparação de execuções</h1><p>Base: {} — gerado em {}</p>",
+        chart::escape(&runs[0].name),
+        Utc::now().format("%Y-%m-%d %H:%M:%S UTC")
+    );
+
+    html.push_str("<table><tr><th>Métrica</th>");
+    for run in runs {
+        let _ = write!(html, "<th>{}</th>", chart::escape(&run.name));
+    }
+    for run in &runs[1..] {
+        let _ = write!(html, "<th>Δ {}</th>", chart::escape(&run.name));
+    }
+    html.push_str("</tr>");
+    for row in rows {
+        let _ = write!(html, "<tr><td>{}</td>", row.label);
+        for value in &row.values {
+            let _ = write!(html, "<td>{:.prec$}</td>", value, prec = row.decimals);
+        }
+        for value in &row.values[1..] {
+            if row.show_diff {
+                let diff = value - row.values[0];
+                let class = if diff >= 0.0 { "pos" } else { "neg" };
+                let _ = write!(
+                    html,
+                    "<td class=\"{}\">{:+.prec$}</td>",
+                    class,
+                    diff,
+                    prec = row.decimals
+                );
+            } else {
+                html.push_str("<td>-</td>");
+            }
+        }
+        html.push_str("</tr>");
+    }
+    html.push_str("</table>");
+
+    let _ = write!(
+        html,
+        "<h2>Curvas de patrimônio</h2>{}<h2>Drawdown</h2>{}",
+        chart::line_chart_svg("Valor do portfólio ($)", &equity_series, &[], 1000, 380),
+        chart::line_chart_svg("Drawdown (%)", &drawdown_series, &[], 1000, 260)
+    );
+
+    html.push_str(
+        "<h2>Configurações</h2><table><tr><th>Execução</th><th>Dataset</th><th>Índices</th><th>Configuração</th></tr>",
+    );
+    for run in runs {
+        let config = serde_json::to_string(&run.state.config).unwrap_or_default();
+        let (dataset, indexes) = match &run.manifest {
+            Some(m) => (
+                m.dataset.describe(),
+                format!(
+                    "{} → {}",
+                    m.start_index,
+                    m.end_index
+                        .map(|i| i.to_string())
+                        .unwrap_or_else(|| "?".to_string())
+                ),
+            ),
+            None => ("-".to_string(), format!("? → {}", run.state.data_index)),
+        };
+        let _ = write!(
+            html,
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td style=\"text-align:left\"><code>{}</code></td></tr>",
+            chart::escape(&run.name),
+            chart::escape(&dataset),
+            indexes,
+            chart::escape(&config)
+        );
+    }
+    html.push_str("</table></body></html>");
+    html
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trade_btc::{EquityPoint, SimulationState, TradeConfig, TradeStats};
+    use chrono::{DateTime, TimeZone};
+
+    fn at(hour: i64) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap() + chrono::Duration::hours(hour)
+    }
+
+    fn run(name: &str, saldo_fiat: f64, winning_trades: u32) -> SavedRun {
+        let config = TradeConfig::default();
+        let mut stats = TradeStats::new(config.initial_balance);
+        stats.total_trades = 4;
+        stats.winning_trades = winning_trades;
+        let price = 50_000.0;
+        SavedRun {
+            name: name.to_string(),
+            manifest: None,
+            state: SimulationState {
+                config: config.clone(),
+                stats,
+                current_time: at(2),
+                end_time: at(2),
+                data_index: 120,
+                total_records: 120,
+                saldo_fiat,
+                saldo_btc: 0.0,
+                preco_anterior: Some(price),
+                preco_pico_recente: price,
+                total_investido: 0.0,
+                buy_orders: Vec::new(),
+                transaction_history: Vec::new(),
+                next_order_id: 1,
+                next_transaction_id: 1,
+                quedas_detectadas: 0,
+                quedas_para_comprar: 3,
+                next_funding_time: None,
+                equity_curve: Vec::new(),
+                last_equity_time: None,
+                equity_file: None,
+                benchmarks_file: None,
+                benchmarks: Vec::new(),
+            },
+            equity_curve: (0..3)
+                .map(|hour| EquityPoint {
+                    time: at(hour),
+                    price,
+                    saldo_fiat,
+                    saldo_btc: 0.0,
+                    total_value: saldo_fiat,
+                    drawdown: 0.0,
+                })
+                .collect(),
+        }
+    }
+
+    fn row<'a>(rows: &'a [MetricRow], label: &str) -> &'a MetricRow {
+        rows.iter().find(|row| row.label == label).unwrap()
+    }
+
+    #[test]
+    fn metric_rows_hold_one_value_per_run() {
+        let runs = [run("base", 10_000.0, 1), run("outra", 11_000.0, 3)];
+        let rows = metric_rows(&runs);
+
+        assert!(rows.iter().all(|row| row.values.len() == runs.len()));
+        assert_eq!(
+            row(&rows, "Valor final ($)").values,
+            vec![10_000.0, 11_000.0]
+        );
+        assert_eq!(row(&rows, "Retorno (%)").values, vec![0.0, 10.0]);
+        assert_eq!(row(&rows, "Win rate (%)").values, vec![25.0, 75.0]);
+    }
+
+    #[test]
+    fn html_shows_signed_differences_against_the_base() {
+        let runs = [
+            run("base", 10_000.0, 2),
+            run("<melhor>", 11_000.0, 3),
+            run("pior", 9_000.0, 1),
+        ];
+        let html = render_html(&runs, &metric_rows(&runs));
+
+        assert!(html.contains("<th>&lt;melhor&gt;</th>"));
+        assert!(html.contains("<th>Δ pior</th>"));
+        assert!(html.contains("<td class=\"pos\">+1000.00</td>"));
+        assert!(html.contains("<td class=\"neg\">-1000.00</td>"));
+        // Uma curva de patrimônio e uma de drawdown por execução
+        assert_eq!(html.matches("<polyline").count(), 2 * runs.len());
+        assert!(html.ends_with("</table></body></html>"));
+    }
+
+    #[test]
+    fn truncate_counts_characters() {
+        assert_eq!(truncate("execução", 6), "execuç");
+        assert_eq!(truncate("curta", 10), "curta");
+    }
+}
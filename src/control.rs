@@ -0,0 +1,751 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+use crate::http_api::HttpServer;
+use crate::simulations::NamedSimulation;
+use crate::supervisor;
+use crate::trade_btc::{TradeConfig, Transaction};
+
+/// Tempo máximo que o canal espera o laço da simulação responder um comando
+const REPLY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Intervalo de espera por comandos enquanto a simulação está pausada
+const PAUSED_POLL: Duration = Duration::from_millis(200);
+
+/// Comando enviado a uma simulação rodando pelo canal de controle
+///
+/// No socket cada comando é uma linha JSON, ex.: `{"command":"step","count":10}`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum ControlCommand {
+    Pause,
+    Resume,
+    /// Processa `count` dados e volta a pausar
+    Step {
+        count: u64,
+    },
+    Speed {
+        speed: Speed,
+    },
+    /// Salva o estado agora (gera um snapshot no backend de estado)
+    Snapshot,
+    /// Altera um campo da `TradeConfig` (ver `SETTABLE_FIELDS`)
+    Set {
+        field: String,
+        value: f64,
+    },
+    Status,
+    /// Saldos e patrimônio ao último preço
+    Portfolio,
+    /// Ordens de compra abertas
+    Orders,
+    /// Histórico de transações, paginado
+    Transactions {
+        offset: usize,
+        limit: usize,
+        #[serde(default)]
+        newest_first: bool,
+    },
+    EquityCurve,
+    Stats,
+}
+
+/// Campos da `TradeConfig` que podem ser alterados com a simulação rodando
+pub const SETTABLE_FIELDS: &[&str] = &[
+    "trade_percentage",
+    "take_profit_percentage",
+    "percentual_queda_para_comprar",
+];
+
+/// Campos da `TradeConfig` que a estratégia não usa: alterá-los não teria efeito
+pub const INACTIVE_FIELDS: &[&str] = &["stop_loss_percentage", "max_loss_percentage"];
+
+/// Ritmo do laço da simulação
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Speed {
+    /// Ritmo padrão do modo (10ms por candle no replay do Redis, sem pausa nos ticks)
+    #[default]
+    Default,
+    /// Sem pausa entre os dados
+    Max,
+    CandlesPerSec(f64),
+}
+
+impl Speed {
+    /// `normal`, `max` ou candles por segundo
+    pub fn parse(value: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        match value {
+            "normal" | "default" => Ok(Self::Default),
+            "max" => Ok(Self::Max),
+            _ => {
+                let rate: f64 = value.parse().map_err(|_| {
+                    format!(
+                        "Velocidade inválida: {} (use normal, max ou candles/s)",
+                        value
+                    )
+                })?;
+                let speed = Self::CandlesPerSec(rate);
+                speed.validate()?;
+                Ok(speed)
+            }
+        }
+    }
+
+    /// Menor ritmo aceito (um candle a cada ~17 minutos)
+    pub const MIN_CANDLES_PER_SEC: f64 = 0.001;
+
+    /// Recusa ritmos não finitos ou abaixo de `MIN_CANDLES_PER_SEC`
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            Self::CandlesPerSec(rate) if !rate.is_finite() || *rate < Self::MIN_CANDLES_PER_SEC => {
+                Err(format!(
+                    "Velocidade inválida: {} (mínimo {} candles/s)",
+                    rate,
+                    Self::MIN_CANDLES_PER_SEC
+                ))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Pausa entre dois dados, dado o ritmo padrão do modo em execução
+    fn delay(&self, default: Duration) -> Duration {
+        match self {
+            Self::Default => default,
+            Self::Max => Duration::ZERO,
+            // Limitada mesmo se um ritmo fora da faixa chegar sem passar por `validate`
+            Self::CandlesPerSec(rate) => Duration::try_from_secs_f64(1.0 / rate)
+                .unwrap_or(Duration::MAX)
+                .min(Duration::from_secs_f64(1.0 / Self::MIN_CANDLES_PER_SEC)),
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            Self::Default => "normal".to_string(),
+            Self::Max => "máxima".to_string(),
+            Self::CandlesPerSec(rate) => format!("{} candles/s", rate),
+        }
+    }
+}
+
+/// Situação do laço da simulação
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum RunState {
+    #[default]
+    Running,
+    Paused,
+    /// Executando um `step`: pausa de novo depois de `remaining` dados
+    Stepping {
+        remaining: u64,
+    },
+}
+
+/// Resposta do canal de controle (uma linha JSON no socket)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ControlReply {
+    pub ok: bool,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<Box<LiveStatus>>,
+    /// Resultado das consultas (`portfolio`, `orders`, `transactions`, ...)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+}
+
+impl ControlReply {
+    pub fn ok(message: impl Into<String>) -> Self {
+        Self {
+            ok: true,
+            message: message.into(),
+            status: None,
+            data: None,
+        }
+    }
+
+    pub fn error(message: impl Into<String>) -> Self {
+        Self {
+            ok: false,
+            message: message.into(),
+            status: None,
+            data: None,
+        }
+    }
+
+    pub fn with_status(status: LiveStatus) -> Self {
+        Self {
+            ok: true,
+            message: "status".to_string(),
+            status: Some(Box::new(status)),
+            data: None,
+        }
+    }
+
+    pub fn with_data(data: &impl Serialize) -> Self {
+        match serde_json::to_value(data) {
+            Ok(data) => Self {
+                ok: true,
+                message: "ok".to_string(),
+                status: None,
+                data: Some(data),
+            },
+            Err(e) => Self::error(format!("Erro ao serializar resposta: {}", e)),
+        }
+    }
+}
+
+/// Status ao vivo montado pelo próprio processo da simulação
+///
+/// Diferente do `status` (que lê o último estado salvo), reflete o laço em execução:
+/// pausa, velocidade, ritmo de processamento e as ordens abertas a preço de agora.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LiveStatus {
+    pub simulation: String,
+    pub pid: u32,
+    pub run_id: Option<String>,
+    pub source: Option<String>,
+    #[serde(flatten)]
+    pub state: RunState,
+    pub speed: String,
+    pub started_at: Option<DateTime<Utc>>,
+    pub uptime_secs: f64,
+    pub processed: usize,
+    pub processed_per_sec: f64,
+    pub end_time: DateTime<Utc>,
+    pub data_index: usize,
+    pub total_records: usize,
+    pub progress_pct: f64,
+    pub peak_price: f64,
+    #[serde(flatten)]
+    pub portfolio: Portfolio,
+    pub realized_pnl: f64,
+    pub total_trades: u32,
+    pub winning_trades: u32,
+    pub losing_trades: u32,
+    pub current_drawdown: f64,
+    pub max_drawdown: f64,
+    pub total_funding: f64,
+    pub candles_missing: u64,
+    pub candles_filled: u64,
+    pub drops_detected: u32,
+    pub drops_to_buy: u32,
+    pub orders: Vec<OpenOrderStatus>,
+    pub config: TradeConfig,
+    pub state_store: String,
+}
+
+#[cfg(test)]
+impl LiveStatus {
+    /// Status de uma simulação `papel` rodando, para os testes de quem o consome
+    pub(crate) fn sample(processed: usize) -> Self {
+        use chrono::TimeZone;
+        let sim_time = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        LiveStatus {
+            simulation: "papel".to_string(),
+            pid: 1,
+            run_id: None,
+            source: None,
+            state: RunState::Running,
+            speed: "normal".to_string(),
+            started_at: None,
+            uptime_secs: 10.0,
+            processed,
+            processed_per_sec: 42.0,
+            end_time: sim_time,
+            data_index: processed,
+            total_records: 1000,
+            progress_pct: 12.5,
+            peak_price: 50_000.0,
+            portfolio: Portfolio {
+                sim_time,
+                last_price: Some(48_000.0),
+                fiat_balance: 900.0,
+                btc_balance: 0.002,
+                btc_value: 96.0,
+                equity: 996.0,
+                profit_loss: -4.0,
+                profit_loss_pct: -0.4,
+                total_invested: 100.0,
+                unrealized_pnl: -4.0,
+                open_orders: 1,
+            },
+            realized_pnl: 0.0,
+            total_trades: 3,
+            winning_trades: 2,
+            losing_trades: 1,
+            current_drawdown: 1.5,
+            max_drawdown: 4.0,
+            total_funding: 0.0,
+            candles_missing: 7,
+            candles_filled: 5,
+            drops_detected: 0,
+            drops_to_buy: 2,
+            orders: Vec::new(),
+            config: TradeConfig::default(),
+            state_store: "simulation_state.json".to_string(),
+        }
+    }
+}
+
+/// Carteira avaliada ao último preço processado
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Portfolio {
+    pub sim_time: DateTime<Utc>,
+    pub last_price: Option<f64>,
+    pub fiat_balance: f64,
+    pub btc_balance: f64,
+    pub btc_value: f64,
+    pub equity: f64,
+    pub profit_loss: f64,
+    pub profit_loss_pct: f64,
+    pub total_invested: f64,
+    pub unrealized_pnl: f64,
+    pub open_orders: usize,
+}
+
+/// Ordem de compra aberta, avaliada ao último preço
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpenOrderStatus {
+    pub id: u32,
+    pub btc_quantity: f64,
+    pub buy_price: f64,
+    pub buy_time: DateTime<Utc>,
+    pub invested_amount: f64,
+    pub take_profit_price: f64,
+    pub unrealized_pnl: f64,
+    pub unrealized_pnl_pct: f64,
+}
+
+/// Página do histórico de transações
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransactionPage {
+    pub total: usize,
+    pub offset: usize,
+    pub limit: usize,
+    pub items: Vec<Transaction>,
+}
+
+/// Execução em andamento, registrada quando o laço começa a processar
+#[derive(Debug, Clone)]
+pub struct Session {
+    pub run_id: String,
+    pub source: String,
+    pub started_at: DateTime<Utc>,
+    pub started: Instant,
+    pub start_index: usize,
+    /// Ritmo padrão do modo; `None` quando o ritmo é o da fonte (paper trading)
+    pub default_delay: Option<Duration>,
+}
+
+/// Comando recebido, com o caminho de volta para a resposta
+pub struct ControlRequest {
+    pub command: ControlCommand,
+    reply: Sender<ControlReply>,
+}
+
+impl ControlRequest {
+    pub fn reply(self, reply: ControlReply) {
+        // Quem pediu pode ter desistido de esperar
+        let _ = self.reply.send(reply);
+    }
+}
+
+/// Ponta de envio de comandos para o laço da simulação, usada pelo socket e pela API HTTP
+#[derive(Clone)]
+pub struct ControlHandle {
+    requests: Sender<ControlRequest>,
+}
+
+impl ControlHandle {
+    /// Envia o comando e espera a resposta do laço (até `REPLY_TIMEOUT`)
+    pub fn request(&self, command: ControlCommand) -> ControlReply {
+        let (reply, answer) = mpsc::channel();
+        if self
+            .requests
+            .send(ControlRequest { command, reply })
+            .is_err()
+        {
+            return ControlReply::error("A simulação já terminou");
+        }
+        answer
+            .recv_timeout(REPLY_TIMEOUT)
+            .unwrap_or_else(|_| ControlReply::error("A simulação não respondeu ao comando"))
+    }
+}
+
+/// Canal de controle de uma simulação: socket Unix `simulation.<nome>.sock`
+///
+/// Uma thread aceita as conexões e repassa os comandos ao laço da simulação, que os
+/// atende entre um dado e outro. O socket é removido quando a simulação termina.
+pub struct ControlServer {
+    path: PathBuf,
+}
+
+impl ControlServer {
+    /// Abre o socket da simulação; chamado depois do lock, então um socket que já
+    /// exista é de um processo que caiu
+    #[cfg(unix)]
+    pub fn start(
+        simulation: &NamedSimulation,
+        handle: ControlHandle,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        use std::os::unix::net::UnixListener;
+
+        let path = simulation.control_path();
+        if path.exists() {
+            fs::remove_file(&path)?;
+        }
+        let listener = UnixListener::bind(&path)?;
+
+        thread::Builder::new()
+            .name("control".to_string())
+            .spawn(move || {
+                for stream in listener.incoming() {
+                    match stream {
+                        Ok(stream) => {
+                            if let Err(e) = serve_connection(stream, &handle) {
+                                warn!("⚠️  Erro no canal de controle: {}", e);
+                            }
+                        }
+                        Err(e) => warn!("⚠️  Erro no canal de controle: {}", e),
+                    }
+                }
+            })?;
+
+        info!("🎛️  Canal de controle em {}", path.display());
+        Ok(Self { path })
+    }
+
+    #[cfg(not(unix))]
+    pub fn start(
+        _simulation: &NamedSimulation,
+        _handle: ControlHandle,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        Err("o canal de controle requer sockets Unix".into())
+    }
+}
+
+impl Drop for ControlServer {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Lê uma linha com o comando, espera a resposta do laço e a devolve
+#[cfg(unix)]
+fn serve_connection(
+    stream: std::os::unix::net::UnixStream,
+    handle: &ControlHandle,
+) -> Result<(), Box<dyn std::error::Error>> {
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    let mut line = String::new();
+    BufReader::new(&stream).read_line(&mut line)?;
+
+    let reply = match serde_json::from_str::<ControlCommand>(&line) {
+        Ok(command) => handle.request(command),
+        Err(e) => ControlReply::error(format!("Comando inválido: {}", e)),
+    };
+
+    let mut stream = stream;
+    writeln!(stream, "{}", serde_json::to_string(&reply)?)?;
+    Ok(())
+}
+
+/// Estado de controle do laço: pausa, velocidade e a fila de onde vêm os comandos
+pub struct RunControl {
+    requests: Receiver<ControlRequest>,
+    handle: ControlHandle,
+    server: Option<ControlServer>,
+    http: Option<HttpServer>,
+    pub state: RunState,
+    pub speed: Speed,
+    pub session: Option<Session>,
+}
+
+impl Default for RunControl {
+    fn default() -> Self {
+        let (tx, requests) = mpsc::channel();
+        Self {
+            requests,
+            handle: ControlHandle { requests: tx },
+            server: None,
+            http: None,
+            state: RunState::default(),
+            speed: Speed::default(),
+            session: None,
+        }
+    }
+}
+
+impl RunControl {
+    pub fn handle(&self) -> ControlHandle {
+        self.handle.clone()
+    }
+
+    pub fn set_server(&mut self, server: ControlServer) {
+        self.server = Some(server);
+    }
+
+    pub fn set_http_server(&mut self, server: HttpServer) {
+        self.http = Some(server);
+    }
+
+    /// Próximo comando pendente. Pausada e com um dado a processar (`tick`), a
+    /// simulação espera aqui até chegar um comando ou um sinal de parada.
+    pub fn next_request(&self, tick: bool) -> Option<ControlRequest> {
+        if !(tick && self.state == RunState::Paused) {
+            return self.requests.try_recv().ok();
+        }
+        loop {
+            match self.requests.recv_timeout(PAUSED_POLL) {
+                Ok(request) => return Some(request),
+                Err(RecvTimeoutError::Timeout) if !supervisor::shutdown_requested() => {}
+                Err(_) => return None,
+            }
+        }
+    }
+
+    /// Espera até `timeout` (no máximo `PAUSED_POLL`) por um comando
+    ///
+    /// Usada na pausa entre dados para atender comandos e verificar sinais de parada
+    /// durante esperas longas.
+    pub fn wait_request(&self, timeout: Duration) -> Option<ControlRequest> {
+        self.requests.recv_timeout(timeout.min(PAUSED_POLL)).ok()
+    }
+
+    /// Conta um dado processado durante um `step`
+    pub fn consume_step(&mut self) {
+        if let RunState::Stepping { remaining } = self.state {
+            if remaining <= 1 {
+                self.state = RunState::Paused;
+                info!("⏸️  Passos concluídos - simulação pausada");
+            } else {
+                self.state = RunState::Stepping {
+                    remaining: remaining - 1,
+                };
+            }
+        }
+    }
+
+    /// Pausa entre dois dados conforme a velocidade escolhida
+    pub fn tick_delay(&self) -> Duration {
+        let default = self
+            .session
+            .as_ref()
+            .and_then(|session| session.default_delay)
+            .unwrap_or_default();
+        self.speed.delay(default)
+    }
+}
+
+/// Envia um comando ao processo da simulação e devolve a resposta
+#[cfg(unix)]
+pub fn send_command(
+    simulation: &NamedSimulation,
+    command: &ControlCommand,
+) -> Result<ControlReply, Box<dyn std::error::Error>> {
+    use std::os::unix::net::UnixStream;
+
+    let path = simulation.control_path();
+    let mut stream = UnixStream::connect(&path).map_err(|_| {
+        format!(
+            "A simulação '{}' não está rodando (sem canal de controle em {})",
+            simulation.name(),
+            path.display()
+        )
+    })?;
+    stream.set_read_timeout(Some(REPLY_TIMEOUT + Duration::from_secs(5)))?;
+    writeln!(stream, "{}", serde_json::to_string(command)?)?;
+
+    let mut line = String::new();
+    BufReader::new(&stream).read_line(&mut line)?;
+    if line.is_empty() {
+        return Err(format!(
+            "A simulação '{}' fechou o canal sem responder",
+            simulation.name()
+        )
+        .into());
+    }
+    Ok(serde_json::from_str(&line)?)
+}
+
+#[cfg(not(unix))]
+pub fn send_command(
+    _simulation: &NamedSimulation,
+    _command: &ControlCommand,
+) -> Result<ControlReply, Box<dyn std::error::Error>> {
+    Err("o canal de controle requer sockets Unix".into())
+}
+
+/// Comando `control [--name nome] pause|resume|step [N]|speed normal|max|N|snapshot|set campo valor|status`
+pub fn run_control_command(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let mut simulation: Option<NamedSimulation> = None;
+    let mut words: Vec<&str> = Vec::new();
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--name" => {
+                simulation = Some(NamedSimulation::new(
+                    iter.next().ok_or("--name requer um nome")?,
+                )?)
+            }
+            other => words.push(other),
+        }
+    }
+    let simulation = match simulation {
+        Some(simulation) => simulation,
+        None => NamedSimulation::from_env()?,
+    };
+
+    let command = match words.as_slice() {
+        ["pause"] => ControlCommand::Pause,
+        ["resume"] => ControlCommand::Resume,
+        ["step"] => ControlCommand::Step { count: 1 },
+        ["step", count] => {
+            let count: u64 = count.parse()?;
+            if count == 0 {
+                return Err("step requer ao menos 1 passo".into());
+            }
+            ControlCommand::Step { count }
+        }
+        ["speed", speed] => ControlCommand::Speed {
+            speed: Speed::parse(speed)?,
+        },
+        ["snapshot"] => ControlCommand::Snapshot,
+        ["set", field, value] => ControlCommand::Set {
+            field: field.to_string(),
+            value: value
+                .parse()
+                .map_err(|_| format!("Valor inválido para {}: {}", field, value))?,
+        },
+        ["status"] => ControlCommand::Status,
+        [] => return Err("Informe o comando de controle".into()),
+        other => {
+            return Err(format!("Comando de controle desconhecido: {}", other.join(" ")).into())
+        }
+    };
+
+    let reply = send_command(&simulation, &command)?;
+    if !reply.ok {
+        return Err(reply.message.into());
+    }
+    match reply.status {
+        Some(status) => println!("{}", serde_json::to_string_pretty(&status)?),
+        None => {
+            info!("🎛️  {}: {}", simulation.name(), reply.message);
+            println!("🎛️  {}: {}", simulation.name(), reply.message);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn parses_speeds() {
+        assert_eq!(Speed::parse("normal").unwrap(), Speed::Default);
+        assert_eq!(Speed::parse("default").unwrap(), Speed::Default);
+        assert_eq!(Speed::parse("max").unwrap(), Speed::Max);
+        assert_eq!(Speed::parse("250").unwrap(), Speed::CandlesPerSec(250.0));
+        for value in ["0", "-1", "0.0001", "inf", "NaN", "rápido"] {
+            assert!(Speed::parse(value).is_err(), "{} deveria falhar", value);
+        }
+    }
+
+    #[test]
+    fn speed_delay_follows_the_rate() {
+        let default = Duration::from_millis(10);
+        assert_eq!(Speed::Default.delay(default), default);
+        assert_eq!(Speed::Max.delay(default), Duration::ZERO);
+        assert_eq!(
+            Speed::CandlesPerSec(4.0).delay(default),
+            Duration::from_millis(250)
+        );
+        // Ritmos fora da faixa ficam limitados à espera do ritmo mínimo
+        let longest = Duration::from_secs_f64(1.0 / Speed::MIN_CANDLES_PER_SEC);
+        assert_eq!(Speed::CandlesPerSec(0.0).delay(default), longest);
+        assert_eq!(Speed::CandlesPerSec(-1.0).delay(default), longest);
+    }
+
+    #[test]
+    fn commands_are_json_lines_tagged_by_name() {
+        let command: ControlCommand =
+            serde_json::from_str(r#"{"command":"step","count":10}"#).unwrap();
+        assert!(matches!(command, ControlCommand::Step { count: 10 }));
+
+        let command: ControlCommand =
+            serde_json::from_str(r#"{"command":"transactions","offset":5,"limit":20}"#).unwrap();
+        assert!(matches!(
+            command,
+            ControlCommand::Transactions {
+                offset: 5,
+                limit: 20,
+                newest_first: false
+            }
+        ));
+
+        let json = serde_json::to_string(&ControlCommand::Speed {
+            speed: Speed::CandlesPerSec(2.5),
+        })
+        .unwrap();
+        let back: ControlCommand = serde_json::from_str(&json).unwrap();
+        assert!(matches!(
+            back,
+            ControlCommand::Speed {
+                speed: Speed::CandlesPerSec(rate)
+            } if rate == 2.5
+        ));
+        assert!(serde_json::from_str::<ControlCommand>(r#"{"command":"reboot"}"#).is_err());
+    }
+
+    #[test]
+    fn steps_count_down_to_a_pause() {
+        let mut control = RunControl {
+            state: RunState::Stepping { remaining: 2 },
+            ..RunControl::default()
+        };
+        control.consume_step();
+        assert_eq!(control.state, RunState::Stepping { remaining: 1 });
+        control.consume_step();
+        assert_eq!(control.state, RunState::Paused);
+        control.consume_step();
+        assert_eq!(control.state, RunState::Paused);
+    }
+
+    #[test]
+    fn invalid_control_commands_fail_before_reaching_the_simulation() {
+        for line in [
+            "",
+            "step 0",
+            "step muitos",
+            "speed rápido",
+            "set take_profit_percentage oito",
+            "set take_profit_percentage",
+            "reboot",
+            "--name ../x pause",
+        ] {
+            assert!(
+                run_control_command(&args(line)).is_err(),
+                "'{}' deveria falhar",
+                line
+            );
+        }
+    }
+}
@@ -0,0 +1,556 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use std::env;
+use tracing::{info, warn};
+
+use crate::reader_csv::{CsvBtcFile, CsvReadOptions, ReaderBtcFile};
+use crate::redis_client::{CandleScan, RedisClient};
+
+/// Política para candles ausentes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum FillPolicy {
+    /// Ignora o intervalo sem dados (comportamento original)
+    Skip,
+    /// Repete o último fechamento conhecido
+    ForwardFill,
+    /// Interpola linearmente entre o último candle e o próximo disponível
+    Interpolate,
+}
+
+impl FillPolicy {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "skip" => Some(FillPolicy::Skip),
+            "forward" | "forward-fill" | "ffill" => Some(FillPolicy::ForwardFill),
+            "interpolate" | "linear" => Some(FillPolicy::Interpolate),
+            _ => None,
+        }
+    }
+
+    /// Lê a política de `DATA_FILL_POLICY` (padrão: `skip`)
+    pub fn from_env() -> Result<Self, Box<dyn std::error::Error>> {
+        match env::var("DATA_FILL_POLICY") {
+            Ok(value) => Self::parse(&value)
+                .ok_or_else(|| format!("DATA_FILL_POLICY inválido: {}", value).into()),
+            Err(_) => Ok(FillPolicy::Skip),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            FillPolicy::Skip => "skip",
+            FillPolicy::ForwardFill => "forward-fill",
+            FillPolicy::Interpolate => "interpolate",
+        }
+    }
+}
+
+/// Candle sintético entre dois candles reais (ou repetindo o anterior)
+pub fn synthetic_candle(
+    previous: &CsvBtcFile,
+    next: Option<&CsvBtcFile>,
+    fraction: f64,
+    time: DateTime<Utc>,
+) -> CsvBtcFile {
+    let close = match next {
+        Some(next) => previous.close + (next.close - previous.close) * fraction,
+        None => previous.close,
+    };
+    CsvBtcFile {
+        open: close,
+        high: close,
+        low: close,
+        close,
+        volume: 0.0,
+        timestamp: time,
+    }
+}
+
+/// Parâmetros da verificação de qualidade
+#[derive(Debug, Clone)]
+pub struct QualityConfig {
+    pub expected_interval: Duration,
+    /// Variação percentual entre fechamentos consecutivos considerada outlier
+    pub spike_threshold_pct: f64,
+    /// Número mínimo de candles seguidos com volume zero para reportar
+    pub min_zero_volume_stretch: usize,
+}
+
+impl Default for QualityConfig {
+    fn default() -> Self {
+        Self {
+            expected_interval: Duration::minutes(1),
+            spike_threshold_pct: 10.0,
+            min_zero_volume_stretch: 5,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Gap {
+    pub after_index: usize,
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub missing_candles: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RowAnomaly {
+    pub index: usize,
+    pub timestamp: DateTime<Utc>,
+    pub detail: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ZeroVolumeStretch {
+    pub start_index: usize,
+    pub from: DateTime<Utc>,
+    pub length: usize,
+}
+
+/// Relatório de qualidade de uma série de candles
+#[derive(Debug, Default, Serialize)]
+pub struct DataQualityReport {
+    pub source: String,
+    pub total_candles: usize,
+    pub first: Option<DateTime<Utc>>,
+    pub last: Option<DateTime<Utc>>,
+    pub missing_candles: i64,
+    pub gaps: Vec<Gap>,
+    pub duplicates: Vec<RowAnomaly>,
+    pub out_of_order: Vec<RowAnomaly>,
+    pub zero_volume_stretches: Vec<ZeroVolumeStretch>,
+    pub spikes: Vec<RowAnomaly>,
+    /// Índices do Redis sem candle (apenas ao verificar o Redis)
+    pub missing_indexes: Vec<usize>,
+}
+
+impl DataQualityReport {
+    const MAX_DISPLAYED: usize = 10;
+
+    /// Varre os candles na ordem em que estão armazenados
+    pub fn scan(source: &str, candles: &[CsvBtcFile], config: &QualityConfig) -> Self {
+        let mut report = Self {
+            source: source.to_string(),
+            total_candles: candles.len(),
+            first: candles.iter().map(|c| c.timestamp).min(),
+            last: candles.iter().map(|c| c.timestamp).max(),
+            ..Default::default()
+        };
+
+        let interval_ms = config.expected_interval.num_milliseconds().max(1);
+        let mut zero_start: Option<usize> = None;
+        let mut last_valid: Option<&CsvBtcFile> = None;
+
+        for (index, candle) in candles.iter().enumerate() {
+            if let Some(previous) = last_valid {
+                let delta = candle.timestamp - previous.timestamp;
+                if delta.num_milliseconds() == 0 {
+                    report.duplicates.push(RowAnomaly {
+                        index,
+                        timestamp: candle.timestamp,
+                        detail: "timestamp repetido".to_string(),
+                    });
+                    continue;
+                }
+                if delta.num_milliseconds() < 0 {
+                    report.out_of_order.push(RowAnomaly {
+                        index,
+                        timestamp: candle.timestamp,
+                        detail: format!("anterior: {}", previous.timestamp.to_rfc3339()),
+                    });
+                    continue;
+                }
+                if delta.num_milliseconds() > interval_ms {
+                    let missing = delta.num_milliseconds() / interval_ms - 1;
+                    if missing > 0 {
+                        report.missing_candles += missing;
+                        report.gaps.push(Gap {
+                            after_index: index - 1,
+                            from: previous.timestamp,
+                            to: candle.timestamp,
+                            missing_candles: missing,
+                        });
+                    }
+                }
+
+                if previous.close > 0.0 {
+                    let change = (candle.close - previous.close) / previous.close * 100.0;
+                    if change.abs() >= config.spike_threshold_pct {
+                        report.spikes.push(RowAnomaly {
+                            index,
+                            timestamp: candle.timestamp,
+                            detail: format!(
+                                "{:+.2}% ({:.2} → {:.2})",
+                                change, previous.close, candle.close
+                            ),
+                        });
+                    }
+                }
+            }
+
+            if candle.volume == 0.0 {
+                zero_start.get_or_insert(index);
+            } else if let Some(start) = zero_start.take() {
+                report.push_zero_volume(candles, start, index - start, config);
+            }
+
+            last_valid = Some(candle);
+        }
+        if let Some(start) = zero_start {
+            report.push_zero_volume(candles, start, candles.len() - start, config);
+        }
+
+        report
+    }
+
+    fn push_zero_volume(
+        &mut self,
+        candles: &[CsvBtcFile],
+        start: usize,
+        length: usize,
+        config: &QualityConfig,
+    ) {
+        if length >= config.min_zero_volume_stretch {
+            self.zero_volume_stretches.push(ZeroVolumeStretch {
+                start_index: start,
+                from: candles[start].timestamp,
+                length,
+            });
+        }
+    }
+
+    pub fn display(&self) {
+        info!(
+            "🔎 Qualidade de dados ({}): {} candles | {} gaps ({} candles faltando) | {} duplicados | {} fora de ordem | {} trechos sem volume | {} outliers | {} índices vazios",
+            self.source,
+            self.total_candles,
+            self.gaps.len(),
+            self.missing_candles,
+            self.duplicates.len(),
+            self.out_of_order.len(),
+            self.zero_volume_stretches.len(),
+            self.spikes.len(),
+            self.missing_indexes.len()
+        );
+
+        println!("\n🔎 RELATÓRIO DE QUALIDADE DE DADOS - {}", self.source);
+        println!("{}", "=".repeat(80));
+        println!("📊 Candles: {}", self.total_candles);
+        if let (Some(first), Some(last)) = (self.first, self.last) {
+            println!(
+                "📅 Período: {} → {}",
+                first.format("%Y-%m-%d %H:%M"),
+                last.format("%Y-%m-%d %H:%M")
+            );
+        }
+        println!(
+            "🕳️  Gaps: {} ({} candles faltando)",
+            self.gaps.len(),
+            self.missing_candles
+        );
+        for gap in self.gaps.iter().take(Self::MAX_DISPLAYED) {
+            println!(
+                "   • após índice {}: {} → {} ({} candles)",
+                gap.after_index,
+                gap.from.format("%Y-%m-%d %H:%M"),
+                gap.to.format("%Y-%m-%d %H:%M"),
+                gap.missing_candles
+            );
+        }
+        Self::display_anomalies("🔁 Duplicados", &self.duplicates);
+        Self::display_anomalies("↩️  Fora de ordem", &self.out_of_order);
+        println!(
+            "💤 Trechos sem volume: {}",
+            self.zero_volume_stretches.len()
+        );
+        for stretch in self.zero_volume_stretches.iter().take(Self::MAX_DISPLAYED) {
+            println!(
+                "   • índice {} ({}): {} candles",
+                stretch.start_index,
+                stretch.from.format("%Y-%m-%d %H:%M"),
+                stretch.length
+            );
+        }
+        Self::display_anomalies("⚡ Outliers de preço", &self.spikes);
+        if !self.missing_indexes.is_empty() {
+            println!("❓ Índices vazios no Redis: {}", self.missing_indexes.len());
+            for index in self.missing_indexes.iter().take(Self::MAX_DISPLAYED) {
+                println!("   • btc_{}", index);
+            }
+        }
+        println!("{}", "=".repeat(80));
+    }
+
+    fn display_anomalies(title: &str, anomalies: &[RowAnomaly]) {
+        println!("{}: {}", title, anomalies.len());
+        for anomaly in anomalies.iter().take(Self::MAX_DISPLAYED) {
+            println!(
+                "   • índice {} ({}): {}",
+                anomaly.index,
+                anomaly.timestamp.format("%Y-%m-%d %H:%M"),
+                anomaly.detail
+            );
+        }
+        if anomalies.len() > Self::MAX_DISPLAYED {
+            println!("   ... e mais {}", anomalies.len() - Self::MAX_DISPLAYED);
+        }
+    }
+}
+
+/// Ordena, remove duplicados e preenche gaps conforme a política escolhida
+///
+/// Com `interval` zero ou negativo não há como avançar no gap: nada é preenchido.
+pub fn fill_gaps(
+    candles: &[CsvBtcFile],
+    interval: Duration,
+    policy: FillPolicy,
+) -> Vec<CsvBtcFile> {
+    let mut sorted = candles.to_vec();
+    sorted.sort_by_key(|c| c.timestamp);
+    sorted.dedup_by_key(|c| c.timestamp);
+
+    if policy == FillPolicy::Skip || interval <= Duration::zero() {
+        return sorted;
+    }
+
+    let mut filled = Vec::with_capacity(sorted.len());
+    for candle in sorted {
+        if let Some(previous) = filled.last().cloned() {
+            let previous: CsvBtcFile = previous;
+            let total = candle.timestamp - previous.timestamp;
+            let mut time = previous.timestamp + interval;
+            while time < candle.timestamp {
+                let fraction = (time - previous.timestamp).num_milliseconds() as f64
+                    / total.num_milliseconds() as f64;
+                let next = (policy == FillPolicy::Interpolate).then_some(&candle);
+                filled.push(synthetic_candle(&previous, next, fraction, time));
+                time += interval;
+            }
+        }
+        filled.push(candle);
+    }
+    filled
+}
+
+/// Comando `data check`: verifica um CSV ou o dataset no Redis
+///
+/// Opções: `[arquivo.csv] [--redis] [--interval <segundos>] [--spike <percentual>]
+/// [--fill skip|forward|interpolate --output <arquivo.csv>]`
+pub fn run_data_command(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    if args.first().map(String::as_str) != Some("check") {
+        return Err("Subcomando desconhecido. Use: cargo run data check [opções]".into());
+    }
+
+    let mut csv_path = "data/btc_historical_data.csv".to_string();
+    let mut use_redis = false;
+    let mut config = QualityConfig::default();
+    let mut fill: Option<FillPolicy> = None;
+    let mut output: Option<String> = None;
+
+    let mut iter = args[1..].iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--redis" => use_redis = true,
+            "--interval" => {
+                let secs: i64 = iter.next().ok_or("--interval requer um valor")?.parse()?;
+                if secs <= 0 {
+                    return Err("Intervalo deve ser maior que zero".into());
+                }
+                config.expected_interval = Duration::seconds(secs);
+            }
+            "--spike" => {
+                config.spike_threshold_pct =
+                    iter.next().ok_or("--spike requer um valor")?.parse()?;
+            }
+            "--fill" => {
+                let value = iter.next().ok_or("--fill requer uma política")?;
+                fill = Some(
+                    FillPolicy::parse(value)
+                        .ok_or_else(|| format!("Política inválida: {}", value))?,
+                );
+            }
+            "--output" => output = iter.next().cloned(),
+            path => csv_path = path.to_string(),
+        }
+    }
+
+    let (candles, mut missing_indexes, source) = if use_redis {
+        let redis = RedisClient::from_env()?;
+        let (candles, missing) = load_all_from_redis(&redis)?;
+        (candles, missing, format!("redis:{}", redis.url()))
+    } else {
+        let (candles, validation) =
+            ReaderBtcFile::read_btc_csv_file_with(&csv_path, &CsvReadOptions::from_env()?)?;
+        validation.display();
+        (candles, Vec::new(), csv_path.clone())
+    };
+
+    let mut report = DataQualityReport::scan(&source, &candles, &config);
+    report.missing_indexes.append(&mut missing_indexes);
+    report.display();
+
+    let report_path = "data_quality_report.json";
+    std::fs::write(report_path, serde_json::to_string_pretty(&report)?)?;
+    println!("📝 Relatório completo salvo em {}", report_path);
+
+    if let Some(policy) = fill {
+        let output = output.ok_or("--fill requer --output <arquivo.csv>")?;
+        let repaired = fill_gaps(&candles, config.expected_interval, policy);
+        let mut writer = csv::Writer::from_path(&output)?;
+        for candle in &repaired {
+            writer.serialize(candle)?;
+        }
+        writer.flush()?;
+
+        info!(
+            "🩹 Dataset corrigido ({}) salvo em {}: {} → {} candles",
+            policy.name(),
+            output,
+            candles.len(),
+            repaired.len()
+        );
+        println!(
+            "🩹 Dataset corrigido ({}) salvo em {}: {} → {} candles",
+            policy.name(),
+            output,
+            candles.len(),
+            repaired.len()
+        );
+    }
+
+    Ok(())
+}
+
+/// Carrega o dataset completo do Redis em lotes, registrando índices vazios
+fn load_all_from_redis(
+    redis: &RedisClient,
+) -> Result<(Vec<CsvBtcFile>, Vec<usize>), Box<dyn std::error::Error>> {
+    const BATCH: usize = 10_000;
+    let mut candles = Vec::new();
+    let mut missing = Vec::new();
+    let mut last_found = 0;
+
+    let mut scan = CandleScan::start(redis, BATCH)?;
+    while let Some((start, batch)) = scan.next_batch(redis)? {
+        for (offset, candle) in batch.into_iter().enumerate() {
+            match candle {
+                Some(candle) => {
+                    last_found = start + offset;
+                    candles.push(candle);
+                }
+                None => missing.push(start + offset),
+            }
+        }
+        println!("📥 {} candles lidos do Redis...", candles.len());
+    }
+
+    // Sem o total gravado, índices vazios depois do último candle são apenas o fim do
+    // dataset; com ele, todo índice vazio dentro do total é uma falta
+    if scan.count().is_none() {
+        missing.retain(|&index| index < last_found);
+    }
+    if !missing.is_empty() {
+        warn!("⚠️  {} índices sem candle no Redis", missing.len());
+    }
+
+    Ok((candles, missing))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candle(minute: i64, close: f64, volume: f64) -> CsvBtcFile {
+        CsvBtcFile {
+            open: close,
+            high: close,
+            low: close,
+            close,
+            volume,
+            timestamp: DateTime::from_timestamp(1_704_067_200 + minute * 60, 0).unwrap(),
+        }
+    }
+
+    fn closes(candles: &[CsvBtcFile]) -> Vec<f64> {
+        candles.iter().map(|c| c.close).collect()
+    }
+
+    #[test]
+    fn synthetic_candle_repeats_or_interpolates_close() {
+        let previous = candle(0, 100.0, 5.0);
+        let next = candle(4, 200.0, 5.0);
+        let time = candle(1, 0.0, 0.0).timestamp;
+
+        let forward = synthetic_candle(&previous, None, 0.25, time);
+        assert_eq!(
+            (forward.open, forward.high, forward.low, forward.close),
+            (100.0, 100.0, 100.0, 100.0)
+        );
+        assert_eq!(forward.volume, 0.0);
+        assert_eq!(forward.timestamp, time);
+
+        let interpolated = synthetic_candle(&previous, Some(&next), 0.25, time);
+        assert_eq!(interpolated.close, 125.0);
+        assert_eq!(interpolated.open, 125.0);
+    }
+
+    #[test]
+    fn fill_gaps_sorts_dedupes_and_fills() {
+        let candles = [
+            candle(3, 130.0, 1.0),
+            candle(0, 100.0, 1.0),
+            candle(0, 999.0, 1.0),
+            candle(1, 110.0, 1.0),
+        ];
+        let interval = Duration::minutes(1);
+
+        let skipped = fill_gaps(&candles, interval, FillPolicy::Skip);
+        assert_eq!(closes(&skipped), vec![100.0, 110.0, 130.0]);
+
+        let forward = fill_gaps(&candles, interval, FillPolicy::ForwardFill);
+        assert_eq!(closes(&forward), vec![100.0, 110.0, 110.0, 130.0]);
+        assert_eq!(forward[2].timestamp, candle(2, 0.0, 0.0).timestamp);
+        assert_eq!(forward[2].volume, 0.0);
+
+        let interpolated = fill_gaps(&candles, interval, FillPolicy::Interpolate);
+        assert_eq!(closes(&interpolated), vec![100.0, 110.0, 120.0, 130.0]);
+    }
+
+    #[test]
+    fn fill_gaps_ignores_non_positive_interval() {
+        let candles = [candle(0, 100.0, 1.0), candle(5, 150.0, 1.0)];
+        for interval in [Duration::zero(), Duration::minutes(-1)] {
+            let filled = fill_gaps(&candles, interval, FillPolicy::ForwardFill);
+            assert_eq!(closes(&filled), vec![100.0, 150.0]);
+        }
+    }
+
+    #[test]
+    fn scan_reports_gaps_duplicates_and_spikes() {
+        let candles = [
+            candle(0, 100.0, 1.0),
+            candle(1, 100.0, 0.0),
+            candle(1, 100.0, 1.0),
+            candle(2, 100.0, 0.0),
+            candle(5, 150.0, 1.0),
+            candle(4, 150.0, 1.0),
+        ];
+        let config = QualityConfig {
+            min_zero_volume_stretch: 1,
+            ..QualityConfig::default()
+        };
+        let report = DataQualityReport::scan("teste", &candles, &config);
+
+        assert_eq!(report.total_candles, 6);
+        assert_eq!(report.missing_candles, 2);
+        assert_eq!(report.gaps.len(), 1);
+        assert_eq!(report.gaps[0].after_index, 3);
+        assert_eq!(report.duplicates.len(), 1);
+        assert_eq!(report.duplicates[0].index, 2);
+        assert_eq!(report.out_of_order.len(), 1);
+        assert_eq!(report.spikes.len(), 1);
+        assert_eq!(report.spikes[0].index, 4);
+        assert_eq!(report.zero_volume_stretches.len(), 1);
+    }
+}
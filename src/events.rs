@@ -0,0 +1,326 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::env;
+use std::sync::mpsc::{self, Sender};
+use std::thread::{self, JoinHandle};
+use tracing::{info, warn};
+
+use crate::redis_client::RedisClient;
+
+/// Evento da estratégia publicado para consumidores externos (dashboards, bots de alerta)
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SimEvent {
+    Buy {
+        order_id: u32,
+        kind: BuyKind,
+        btc_quantity: f64,
+        price: f64,
+        amount: f64,
+        fiat_balance: f64,
+        open_orders: usize,
+    },
+    Sell {
+        order_id: u32,
+        btc_quantity: f64,
+        buy_price: f64,
+        sell_price: f64,
+        amount: f64,
+        profit: f64,
+        profit_percentage: f64,
+        holding_hours: i64,
+    },
+    /// Venda forçada para cobrir um pagamento de funding maior que o saldo fiat
+    Liquidation {
+        order_id: u32,
+        btc_quantity: f64,
+        buy_price: f64,
+        sell_price: f64,
+        amount: f64,
+        profit: f64,
+        fiat_balance: f64,
+    },
+    DropDetected {
+        count: u32,
+        required: u32,
+        drop_percentage: f64,
+        peak_price: f64,
+        price: f64,
+    },
+    EmergencyBuy {
+        drop_percentage: f64,
+        threshold_percentage: f64,
+        peak_price: f64,
+        price: f64,
+    },
+    CapReached {
+        total_after_buy: f64,
+        limit: f64,
+    },
+}
+
+/// Motivo da compra, como exibido no terminal
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BuyKind {
+    First,
+    Emergency,
+    Drop,
+}
+
+impl BuyKind {
+    /// Mesmo nome do JSON, para o campo `reason` dos logs
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BuyKind::First => "first",
+            BuyKind::Emergency => "emergency",
+            BuyKind::Drop => "drop",
+        }
+    }
+}
+
+/// Envelope JSON enviado ao Redis
+#[derive(Serialize)]
+struct EventEnvelope<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    run_id: Option<&'a str>,
+    sim_time: DateTime<Utc>,
+    emitted_at: DateTime<Utc>,
+    #[serde(flatten)]
+    event: &'a SimEvent,
+}
+
+/// Destino dos eventos no Redis
+#[derive(Debug, Clone)]
+pub enum EventTarget {
+    /// Canal pub/sub (`pubsub:<canal>`)
+    Channel(String),
+    /// Stream com o evento no campo `data` (`stream:<chave>`)
+    Stream { key: String, max_len: Option<usize> },
+}
+
+impl EventTarget {
+    pub fn parse(spec: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        match spec.trim().split_once(':') {
+            Some(("pubsub", channel)) if !channel.is_empty() => {
+                Ok(EventTarget::Channel(channel.to_string()))
+            }
+            Some(("stream", key)) if !key.is_empty() => Ok(EventTarget::Stream {
+                key: key.to_string(),
+                max_len: None,
+            }),
+            _ => Err(format!(
+                "Destino de eventos inválido: {} (use pubsub:<canal> ou stream:<chave>)",
+                spec
+            )
+            .into()),
+        }
+    }
+
+    /// Lê `SIM_EVENTS` e `SIM_EVENTS_MAXLEN`; sem `SIM_EVENTS` a publicação fica desligada
+    pub fn from_env() -> Result<Option<Self>, Box<dyn std::error::Error>> {
+        let Ok(spec) = env::var("SIM_EVENTS") else {
+            return Ok(None);
+        };
+        let mut target = Self::parse(&spec)?;
+        if let EventTarget::Stream { max_len, .. } = &mut target {
+            if let Ok(value) = env::var("SIM_EVENTS_MAXLEN") {
+                *max_len = Some(value.parse()?);
+            }
+        }
+        Ok(Some(target))
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            EventTarget::Channel(channel) => format!("pubsub:{}", channel),
+            EventTarget::Stream { key, .. } => format!("stream:{}", key),
+        }
+    }
+}
+
+/// Publica eventos numa thread própria para não atrasar a simulação
+///
+/// Falhas de publicação são registradas e não interrompem a simulação.
+pub struct EventPublisher {
+    tx: Option<Sender<String>>,
+    worker: Option<JoinHandle<()>>,
+    run_id: Option<String>,
+}
+
+impl EventPublisher {
+    pub fn spawn(
+        redis: RedisClient,
+        target: EventTarget,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        info!(
+            "📣 Publicando eventos da simulação em {}",
+            target.describe()
+        );
+        println!(
+            "📣 Publicando eventos da simulação em {}",
+            target.describe()
+        );
+
+        let (tx, rx) = mpsc::channel::<String>();
+        let worker = thread::Builder::new()
+            .name("sim-events".to_string())
+            .spawn(move || {
+                let mut failures = 0u64;
+                for message in rx {
+                    let result = match &target {
+                        EventTarget::Channel(channel) => {
+                            redis.publish_message(channel, &message).map(|_| ())
+                        }
+                        EventTarget::Stream { key, max_len } => {
+                            redis.append_to_stream(key, &message, *max_len).map(|_| ())
+                        }
+                    };
+                    if let Err(e) = result {
+                        failures += 1;
+                        if failures == 1 || failures.is_multiple_of(100) {
+                            warn!(
+                                "⚠️  Falha ao publicar evento em {} ({} falhas): {}",
+                                target.describe(),
+                                failures,
+                                e
+                            );
+                        }
+                    }
+                }
+            })?;
+
+        Ok(Self {
+            tx: Some(tx),
+            worker: Some(worker),
+            run_id: None,
+        })
+    }
+
+    /// Cria o publicador configurado no ambiente, se houver
+    pub fn from_env(redis: &RedisClient) -> Result<Option<Self>, Box<dyn std::error::Error>> {
+        match EventTarget::from_env()? {
+            Some(target) => Ok(Some(Self::spawn(redis.clone(), target)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Identifica a execução atual nos próximos eventos
+    pub fn set_run_id(&mut self, run_id: &str) {
+        self.run_id = Some(run_id.to_string());
+    }
+
+    pub fn emit(&self, sim_time: DateTime<Utc>, event: &SimEvent) {
+        let envelope = EventEnvelope {
+            run_id: self.run_id.as_deref(),
+            sim_time,
+            emitted_at: Utc::now(),
+            event,
+        };
+        if let (Some(tx), Ok(message)) = (&self.tx, serde_json::to_string(&envelope)) {
+            let _ = tx.send(message);
+        }
+    }
+
+    /// Aguarda a publicação dos eventos pendentes
+    pub fn close(mut self) {
+        self.tx.take();
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use serde_json::Value;
+
+    fn buy() -> SimEvent {
+        SimEvent::Buy {
+            order_id: 7,
+            kind: BuyKind::Drop,
+            btc_quantity: 0.01,
+            price: 50_000.0,
+            amount: 500.0,
+            fiat_balance: 9_500.0,
+            open_orders: 2,
+        }
+    }
+
+    #[test]
+    fn parses_event_targets() {
+        assert!(matches!(
+            EventTarget::parse("pubsub:btc_sim_events").unwrap(),
+            EventTarget::Channel(channel) if channel == "btc_sim_events"
+        ));
+        assert!(matches!(
+            EventTarget::parse(" stream:btc_sim_events ").unwrap(),
+            EventTarget::Stream { key, max_len: None } if key == "btc_sim_events"
+        ));
+        for spec in ["", "pubsub:", "stream:", "ws://host", "btc_sim_events"] {
+            assert!(EventTarget::parse(spec).is_err(), "{} deveria falhar", spec);
+        }
+        for spec in ["pubsub:eventos", "stream:eventos"] {
+            assert_eq!(EventTarget::parse(spec).unwrap().describe(), spec);
+        }
+    }
+
+    #[test]
+    fn events_are_tagged_by_type_in_snake_case() {
+        let json: Value = serde_json::to_value(buy()).unwrap();
+        assert_eq!(json["type"], "buy");
+        assert_eq!(json["kind"], "drop");
+        assert_eq!(json["open_orders"], 2);
+
+        let json = serde_json::to_value(SimEvent::CapReached {
+            total_after_buy: 1_100.0,
+            limit: 1_000.0,
+        })
+        .unwrap();
+        assert_eq!(json["type"], "cap_reached");
+    }
+
+    #[test]
+    fn buy_kind_names_match_the_json() {
+        for kind in [BuyKind::First, BuyKind::Emergency, BuyKind::Drop] {
+            assert_eq!(serde_json::to_value(kind).unwrap(), kind.as_str());
+        }
+    }
+
+    #[test]
+    fn envelope_flattens_the_event_and_omits_a_missing_run_id() {
+        let event = buy();
+        let sim_time = Utc.with_ymd_and_hms(2024, 1, 1, 12, 0, 0).unwrap();
+        let envelope = EventEnvelope {
+            run_id: None,
+            sim_time,
+            emitted_at: sim_time,
+            event: &event,
+        };
+        let json: Value = serde_json::to_value(&envelope).unwrap();
+        assert!(json.get("run_id").is_none());
+        assert_eq!(json["type"], "buy");
+        assert_eq!(json["order_id"], 7);
+        assert_eq!(json["sim_time"], "2024-01-01T12:00:00Z");
+
+        let envelope = EventEnvelope {
+            run_id: Some("run-1"),
+            ..envelope
+        };
+        assert_eq!(serde_json::to_value(&envelope).unwrap()["run_id"], "run-1");
+    }
+
+    #[test]
+    fn publish_failures_do_not_stop_the_simulation() {
+        let mut publisher = EventPublisher::spawn(
+            RedisClient::offline(),
+            EventTarget::parse("pubsub:eventos").unwrap(),
+        )
+        .unwrap();
+        publisher.set_run_id("run-1");
+        publisher.emit(Utc::now(), &buy());
+        publisher.close();
+    }
+}
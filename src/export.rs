@@ -0,0 +1,197 @@
+use chrono::{DateTime, Utc};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fs::File;
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+
+use crate::benchmark::BenchmarkSample;
+use crate::periods::PeriodBreakdown;
+use crate::trade_btc::{EquityPoint, Transaction};
+
+/// Linha de uma curva gravada incrementalmente (`equity.csv`, `benchmarks.csv`)
+pub trait CurveRow: Serialize + DeserializeOwned + Clone {
+    fn time(&self) -> DateTime<Utc>;
+}
+
+impl CurveRow for EquityPoint {
+    fn time(&self) -> DateTime<Utc> {
+        self.time
+    }
+}
+
+impl CurveRow for BenchmarkSample {
+    fn time(&self) -> DateTime<Utc> {
+        self.time
+    }
+}
+
+/// Curva gravada incrementalmente num CSV da execução
+///
+/// Cada amostra é acrescentada ao arquivo; o estado salvo guarda só o caminho e o
+/// horário da última amostra, então a curva não é reescrita a cada salvamento.
+pub struct CurveLog<T> {
+    path: PathBuf,
+    writer: csv::Writer<File>,
+    rows: PhantomData<T>,
+}
+
+/// Curva de patrimônio da estratégia (`equity.csv`)
+pub type EquityLog = CurveLog<EquityPoint>;
+
+/// Curvas dos benchmarks (`benchmarks.csv`)
+pub type BenchmarkLog = CurveLog<BenchmarkSample>;
+
+impl<T: CurveRow> CurveLog<T> {
+    /// Cria o arquivo da execução a partir da curva anterior
+    ///
+    /// Uma execução retomada copia de `previous` as amostras até `until` (o horário da
+    /// última amostra do estado salvo); as gravadas depois dele são refeitas na retomada.
+    /// `seed` traz a curva embutida em estados antigos.
+    pub fn create(
+        path: &Path,
+        previous: Option<&Path>,
+        until: Option<DateTime<Utc>>,
+        seed: &[T],
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut rows = seed.to_vec();
+        if let Some(previous) = previous.filter(|p| p.exists()) {
+            rows.extend(
+                read_curve_csv::<T>(previous)?
+                    .into_iter()
+                    .filter(|row| until.is_some_and(|until| row.time() <= until)),
+            );
+        }
+
+        let mut log = Self {
+            path: path.to_path_buf(),
+            writer: csv::Writer::from_path(path)?,
+            rows: PhantomData,
+        };
+        for row in &rows {
+            log.append(row)?;
+        }
+        log.flush()?;
+        Ok(log)
+    }
+
+    pub fn append(&mut self, row: &T) -> Result<(), Box<dyn std::error::Error>> {
+        self.writer.serialize(row)?;
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+/// Lê uma curva exportada
+pub fn read_curve_csv<T: CurveRow>(path: &Path) -> Result<Vec<T>, Box<dyn std::error::Error>> {
+    let mut reader = csv::Reader::from_path(path)?;
+    let mut rows = Vec::new();
+    for record in reader.deserialize() {
+        rows.push(record?);
+    }
+    Ok(rows)
+}
+
+/// Lê uma curva de patrimônio exportada
+pub fn read_equity_csv(path: &Path) -> Result<Vec<EquityPoint>, Box<dyn std::error::Error>> {
+    read_curve_csv(path)
+}
+
+/// Exporta o histórico de transações em CSV
+pub fn write_trades_csv(
+    path: &Path,
+    transactions: &[Transaction],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut writer = csv::Writer::from_path(path)?;
+    for transaction in transactions {
+        writer.serialize(transaction)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Exporta a agregação por mês, trimestre e ano em CSV
+pub fn write_periods_csv(
+    path: &Path,
+    breakdown: &[PeriodBreakdown],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut writer = csv::Writer::from_path(path)?;
+    for period in breakdown {
+        writer.serialize(period)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn point(hour: u32) -> EquityPoint {
+        EquityPoint {
+            time: Utc.with_ymd_and_hms(2024, 1, 1, hour, 0, 0).unwrap(),
+            price: 50_000.0 + hour as f64,
+            saldo_fiat: 1000.0,
+            saldo_btc: 0.0,
+            total_value: 1000.0,
+            drawdown: 0.0,
+        }
+    }
+
+    fn hours(points: &[EquityPoint]) -> Vec<u32> {
+        points
+            .iter()
+            .map(|p| chrono::Timelike::hour(&p.time))
+            .collect()
+    }
+
+    #[test]
+    fn resumed_log_continues_previous_curve_up_to_saved_sample() {
+        let dir = std::env::temp_dir().join(format!("btc-equity-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let first = dir.join("first.csv");
+        let second = dir.join("second.csv");
+
+        let mut log = EquityLog::create(&first, None, None, &[point(0)]).unwrap();
+        for hour in 1..4 {
+            log.append(&point(hour)).unwrap();
+        }
+        log.flush().unwrap();
+
+        // Estado salvo na amostra das 2h: a das 3h é refeita pela execução retomada
+        let mut resumed =
+            EquityLog::create(&second, Some(&first), Some(point(2).time), &[]).unwrap();
+        resumed.append(&point(3)).unwrap();
+        resumed.append(&point(4)).unwrap();
+        resumed.flush().unwrap();
+
+        let copied = read_equity_csv(&second).unwrap();
+        let original = read_equity_csv(&first).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(hours(&original), vec![0, 1, 2, 3]);
+        assert_eq!(hours(&copied), vec![0, 1, 2, 3, 4]);
+        assert_eq!(copied[4].price, 50_004.0);
+    }
+
+    #[test]
+    fn legacy_curve_seeds_the_log() {
+        let path = std::env::temp_dir().join(format!("btc-equity-seed-{}.csv", std::process::id()));
+        let mut log = EquityLog::create(&path, None, None, &[point(0), point(1)]).unwrap();
+        log.append(&point(2)).unwrap();
+        log.flush().unwrap();
+        let points = read_equity_csv(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(hours(&points), vec![0, 1, 2]);
+    }
+}
@@ -0,0 +1,233 @@
+use chrono::{DateTime, TimeZone, Utc};
+use serde::Deserialize;
+use std::env;
+use tracing::info;
+
+use crate::reader_csv::parse_timestamp;
+
+/// Intervalo entre pagamentos de funding em contratos perpétuos (8 horas)
+pub const FUNDING_INTERVAL_HOURS: i64 = 8;
+
+/// Linha do arquivo CSV de funding rates (`timestamp,rate`)
+///
+/// O `timestamp` aceita os mesmos formatos dos candles (epoch ou ISO).
+#[derive(Debug, Deserialize)]
+struct CsvFundingRate {
+    timestamp: String,
+    rate: f64,
+}
+
+/// Taxa de funding válida a partir de um instante
+#[derive(Debug, Clone)]
+pub struct FundingRate {
+    pub time: DateTime<Utc>,
+    pub rate: f64,
+}
+
+/// Fonte das taxas de funding aplicadas às posições abertas
+#[derive(Debug, Clone)]
+pub enum FundingSchedule {
+    /// Mesma taxa em todos os períodos de 8h
+    Constant(f64),
+    /// Série histórica ordenada por tempo (a última taxa conhecida vale até a próxima)
+    Series(Vec<FundingRate>),
+}
+
+impl FundingSchedule {
+    /// Carrega uma série de funding de um CSV com colunas `timestamp,rate`
+    pub fn from_csv(file_path: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let file = std::fs::File::open(file_path)?;
+        let reader = std::io::BufReader::new(file);
+        let mut csv_reader = csv::Reader::from_reader(reader);
+
+        let mut rates = Vec::new();
+        for result in csv_reader.deserialize() {
+            let record: CsvFundingRate = result?;
+            let time = parse_timestamp(&record.timestamp)
+                .ok_or_else(|| format!("Timestamp de funding inválido: {}", record.timestamp))?;
+            rates.push(FundingRate {
+                time,
+                rate: record.rate,
+            });
+        }
+
+        rates.sort_by_key(|r| r.time);
+
+        info!(
+            "📈 {} taxas de funding carregadas de {}",
+            rates.len(),
+            file_path
+        );
+        println!(
+            "📈 {} taxas de funding carregadas de {}",
+            rates.len(),
+            file_path
+        );
+
+        Ok(FundingSchedule::Series(rates))
+    }
+
+    /// Cria a agenda de funding a partir de variáveis de ambiente
+    ///
+    /// `FUNDING_RATE_FILE` tem prioridade sobre `FUNDING_RATE_CONSTANT`.
+    /// Sem nenhuma das duas, a simulação roda sem funding (mercado spot).
+    pub fn from_env() -> Result<Option<Self>, Box<dyn std::error::Error>> {
+        if let Ok(path) = env::var("FUNDING_RATE_FILE") {
+            return Ok(Some(Self::from_csv(&path)?));
+        }
+
+        if let Ok(rate) = env::var("FUNDING_RATE_CONSTANT") {
+            let rate: f64 = rate
+                .parse()
+                .map_err(|_| format!("FUNDING_RATE_CONSTANT inválido: {}", rate))?;
+            info!(
+                "📈 Funding constante de {:.4}% a cada {}h",
+                rate * 100.0,
+                FUNDING_INTERVAL_HOURS
+            );
+            println!(
+                "📈 Funding constante de {:.4}% a cada {}h",
+                rate * 100.0,
+                FUNDING_INTERVAL_HOURS
+            );
+            return Ok(Some(FundingSchedule::Constant(rate)));
+        }
+
+        Ok(None)
+    }
+
+    /// Taxa vigente no instante informado
+    pub fn rate_at(&self, time: DateTime<Utc>) -> Option<f64> {
+        match self {
+            FundingSchedule::Constant(rate) => Some(*rate),
+            FundingSchedule::Series(rates) => {
+                let pos = rates.partition_point(|r| r.time <= time);
+                if pos == 0 {
+                    None
+                } else {
+                    Some(rates[pos - 1].rate)
+                }
+            }
+        }
+    }
+
+    /// Descrição reproduzível da agenda, registrada no manifesto da execução
+    ///
+    /// A série é identificada pela quantidade, pelo intervalo e por um CRC32 das taxas.
+    pub fn describe(&self) -> String {
+        match self {
+            FundingSchedule::Constant(rate) => {
+                format!("constante {} a cada {}h", rate, FUNDING_INTERVAL_HOURS)
+            }
+            FundingSchedule::Series(rates) => {
+                let mut hasher = crc32fast::Hasher::new();
+                for rate in rates {
+                    hasher.update(&rate.time.timestamp_millis().to_le_bytes());
+                    hasher.update(&rate.rate.to_bits().to_le_bytes());
+                }
+                match (rates.first(), rates.last()) {
+                    (Some(first), Some(last)) => format!(
+                        "série de {} taxas de {} a {} (crc32 {:08x})",
+                        rates.len(),
+                        first.time.format("%Y-%m-%d %H:%M"),
+                        last.time.format("%Y-%m-%d %H:%M"),
+                        hasher.finalize()
+                    ),
+                    _ => "série vazia".to_string(),
+                }
+            }
+        }
+    }
+
+    /// Próximo instante de funding (00:00, 08:00, 16:00 UTC) estritamente após `time`
+    pub fn next_funding_time(time: DateTime<Utc>) -> DateTime<Utc> {
+        let interval = FUNDING_INTERVAL_HOURS * 3600;
+        let next = (time.timestamp().div_euclid(interval) + 1) * interval;
+        Utc.timestamp_opt(next, 0).single().unwrap_or(time)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(text: &str) -> DateTime<Utc> {
+        parse_timestamp(text).unwrap()
+    }
+
+    #[test]
+    fn funding_times_are_every_eight_hours_from_midnight() {
+        let next = FundingSchedule::next_funding_time;
+        assert_eq!(next(at("2024-01-01 00:00:00")), at("2024-01-01 08:00:00"));
+        assert_eq!(next(at("2024-01-01 07:59:00")), at("2024-01-01 08:00:00"));
+        assert_eq!(next(at("2024-01-01 08:00:00")), at("2024-01-01 16:00:00"));
+        assert_eq!(next(at("2024-01-01 23:30:00")), at("2024-01-02 00:00:00"));
+    }
+
+    #[test]
+    fn series_uses_last_known_rate() {
+        let schedule = FundingSchedule::Series(vec![
+            FundingRate {
+                time: at("2024-01-01 00:00:00"),
+                rate: 0.0001,
+            },
+            FundingRate {
+                time: at("2024-01-01 08:00:00"),
+                rate: -0.0002,
+            },
+        ]);
+        assert_eq!(schedule.rate_at(at("2023-12-31 16:00:00")), None);
+        assert_eq!(schedule.rate_at(at("2024-01-01 00:00:00")), Some(0.0001));
+        assert_eq!(schedule.rate_at(at("2024-01-01 07:59:00")), Some(0.0001));
+        assert_eq!(schedule.rate_at(at("2024-01-01 08:00:00")), Some(-0.0002));
+        assert_eq!(schedule.rate_at(at("2024-03-01 00:00:00")), Some(-0.0002));
+        assert_eq!(
+            FundingSchedule::Constant(0.0003).rate_at(at("2024-01-01 00:00:00")),
+            Some(0.0003)
+        );
+    }
+
+    #[test]
+    fn describe_identifies_the_series() {
+        let series = |rate| {
+            FundingSchedule::Series(vec![
+                FundingRate {
+                    time: at("2024-01-01 00:00:00"),
+                    rate: 0.0001,
+                },
+                FundingRate {
+                    time: at("2024-01-01 08:00:00"),
+                    rate,
+                },
+            ])
+        };
+        let description = series(0.0002).describe();
+        assert!(description.starts_with("série de 2 taxas de 2024-01-01 00:00 a 2024-01-01 08:00"));
+        assert_eq!(description, series(0.0002).describe());
+        assert_ne!(description, series(0.0003).describe());
+        assert_eq!(
+            FundingSchedule::Constant(0.0001).describe(),
+            "constante 0.0001 a cada 8h"
+        );
+    }
+
+    #[test]
+    fn csv_series_is_sorted_by_time() {
+        let path = std::env::temp_dir().join(format!("funding_test_{}.csv", std::process::id()));
+        std::fs::write(
+            &path,
+            "timestamp,rate\n1704096000,-0.0002\n2024-01-01 00:00:00,0.0001\n",
+        )
+        .unwrap();
+        let schedule = FundingSchedule::from_csv(path.to_str().unwrap()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let FundingSchedule::Series(rates) = schedule else {
+            panic!("CSV deveria gerar uma série");
+        };
+        assert_eq!(rates.len(), 2);
+        assert_eq!(rates[0].time, at("2024-01-01 00:00:00"));
+        assert_eq!(rates[1].time, at("2024-01-01 08:00:00"));
+        assert_eq!(rates[1].rate, -0.0002);
+    }
+}
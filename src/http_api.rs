@@ -0,0 +1,309 @@
+use serde::Serialize;
+use serde_json::json;
+use std::env;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::Arc;
+use std::thread;
+use tiny_http::{Header, Method, Request, Response, Server};
+use tracing::{info, warn};
+
+use crate::control::{ControlCommand, ControlHandle, ControlReply};
+use crate::metrics::{self, TickRate};
+
+/// Transações por página quando `limit` não é informado
+const DEFAULT_PAGE_SIZE: usize = 100;
+
+/// Maior página aceita em `/api/transactions`
+const MAX_PAGE_SIZE: usize = 1000;
+
+/// Endereço da API lido de `HTTP_API` (`porta` ou `127.0.0.1:porta`); sem a variável
+/// a API fica desligada
+pub fn address_from_env() -> Result<Option<SocketAddr>, Box<dyn std::error::Error>> {
+    match env::var("HTTP_API") {
+        Ok(spec) if !spec.trim().is_empty() => Ok(Some(parse_address(spec.trim())?)),
+        _ => Ok(None),
+    }
+}
+
+/// Aceita só endereços locais: a API altera a simulação e não tem autenticação
+pub fn parse_address(spec: &str) -> Result<SocketAddr, Box<dyn std::error::Error>> {
+    let address = match spec.parse::<u16>() {
+        Ok(port) => SocketAddr::from((Ipv4Addr::LOCALHOST, port)),
+        Err(_) => spec.parse::<SocketAddr>().map_err(|_| {
+            format!(
+                "Endereço da API HTTP inválido: {} (use porta ou ip:porta)",
+                spec
+            )
+        })?,
+    };
+    if !address.ip().is_loopback() {
+        return Err(format!(
+            "A API HTTP só pode escutar em localhost (127.0.0.1 ou ::1), não em {}",
+            address.ip()
+        )
+        .into());
+    }
+    Ok(address)
+}
+
+/// API HTTP embutida no processo da simulação (JSON e `/metrics`, só localhost)
+///
+/// As consultas passam pelo mesmo canal do `control`, então refletem o laço em execução
+/// e não o último estado salvo em disco. O servidor para quando a simulação termina.
+pub struct HttpServer {
+    server: Arc<Server>,
+}
+
+impl HttpServer {
+    pub fn start(
+        address: SocketAddr,
+        handle: ControlHandle,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let server = Arc::new(
+            Server::http(address)
+                .map_err(|e| format!("Erro ao abrir a API HTTP em {}: {}", address, e))?,
+        );
+
+        let worker = Arc::clone(&server);
+        thread::Builder::new()
+            .name("http-api".to_string())
+            .spawn(move || {
+                let mut rate = TickRate::default();
+                for request in worker.incoming_requests() {
+                    if let Err(e) = handle_request(request, &handle, &mut rate) {
+                        warn!("⚠️  Erro ao responder requisição HTTP: {}", e);
+                    }
+                }
+            })?;
+
+        info!("🌐 API HTTP em http://{}/api/status", address);
+        println!("🌐 API HTTP em http://{}/api/status", address);
+        Ok(Self { server })
+    }
+}
+
+impl Drop for HttpServer {
+    fn drop(&mut self) {
+        self.server.unblock();
+    }
+}
+
+fn handle_request(
+    request: Request,
+    handle: &ControlHandle,
+    rate: &mut TickRate,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let url = request.url().to_string();
+    let (path, query) = url.split_once('?').unwrap_or((&url, ""));
+
+    if path == "/metrics" && *request.method() == Method::Get {
+        let reply = handle.request(ControlCommand::Status);
+        return match reply.status {
+            Some(status) if reply.ok => respond(
+                request,
+                200,
+                metrics::render(&status, rate),
+                "text/plain; version=0.0.4; charset=utf-8",
+            ),
+            _ => respond_json(request, 503, &json!({ "error": reply.message })),
+        };
+    }
+
+    let (status, body) = match (request.method(), path) {
+        (Method::Get, "/api/status") => {
+            reply_body(handle.request(ControlCommand::Status), |reply| {
+                serde_json::to_value(reply.status)
+            })
+        }
+        (Method::Get, "/api/portfolio") => query_data(handle, ControlCommand::Portfolio),
+        (Method::Get, "/api/orders") => query_data(handle, ControlCommand::Orders),
+        (Method::Get, "/api/transactions") => match transactions_command(query) {
+            Ok(command) => query_data(handle, command),
+            Err(e) => (400, json!({ "error": e })),
+        },
+        (Method::Get, "/api/equity") => query_data(handle, ControlCommand::EquityCurve),
+        (Method::Get, "/api/stats") => query_data(handle, ControlCommand::Stats),
+        (Method::Post, "/api/control/pause") => control(handle, ControlCommand::Pause),
+        (Method::Post, "/api/control/resume") => control(handle, ControlCommand::Resume),
+        (Method::Post, "/api/control/snapshot") => control(handle, ControlCommand::Snapshot),
+        (_, path) if is_route(path) => (405, json!({ "error": "Método não permitido" })),
+        (_, path) => (
+            404,
+            json!({ "error": format!("Rota não encontrada: {}", path) }),
+        ),
+    };
+
+    respond_json(request, status, &body)
+}
+
+fn is_route(path: &str) -> bool {
+    matches!(
+        path,
+        "/metrics"
+            | "/api/status"
+            | "/api/portfolio"
+            | "/api/orders"
+            | "/api/transactions"
+            | "/api/equity"
+            | "/api/stats"
+            | "/api/control/pause"
+            | "/api/control/resume"
+            | "/api/control/snapshot"
+    )
+}
+
+/// `GET /api/transactions?offset=0&limit=100&order=asc|desc`
+fn transactions_command(query: &str) -> Result<ControlCommand, String> {
+    let mut offset = 0;
+    let mut limit = DEFAULT_PAGE_SIZE;
+    let mut newest_first = false;
+    for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+        let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+        match key {
+            "offset" => {
+                offset = value
+                    .parse()
+                    .map_err(|_| format!("offset inválido: {}", value))?
+            }
+            "limit" => {
+                limit = value
+                    .parse()
+                    .map_err(|_| format!("limit inválido: {}", value))?
+            }
+            "order" => {
+                newest_first = match value {
+                    "asc" => false,
+                    "desc" => true,
+                    _ => return Err(format!("order inválido: {} (use asc ou desc)", value)),
+                }
+            }
+            _ => return Err(format!("Parâmetro desconhecido: {}", key)),
+        }
+    }
+    if limit == 0 || limit > MAX_PAGE_SIZE {
+        return Err(format!("limit deve estar entre 1 e {}", MAX_PAGE_SIZE));
+    }
+    Ok(ControlCommand::Transactions {
+        offset,
+        limit,
+        newest_first,
+    })
+}
+
+fn query_data(handle: &ControlHandle, command: ControlCommand) -> (u16, serde_json::Value) {
+    reply_body(handle.request(command), |reply| {
+        Ok(reply.data.unwrap_or_default())
+    })
+}
+
+fn control(handle: &ControlHandle, command: ControlCommand) -> (u16, serde_json::Value) {
+    reply_body(handle.request(command), |reply| {
+        Ok(json!({ "ok": true, "message": reply.message }))
+    })
+}
+
+/// Corpo da resposta: o conteúdo pedido ou `{"error": ...}` quando a simulação recusou
+fn reply_body(
+    reply: ControlReply,
+    body: impl FnOnce(ControlReply) -> serde_json::Result<serde_json::Value>,
+) -> (u16, serde_json::Value) {
+    if !reply.ok {
+        return (503, json!({ "error": reply.message }));
+    }
+    match body(reply) {
+        Ok(value) => (200, value),
+        Err(e) => (500, json!({ "error": e.to_string() })),
+    }
+}
+
+fn respond_json(
+    request: Request,
+    status: u16,
+    body: &impl Serialize,
+) -> Result<(), Box<dyn std::error::Error>> {
+    respond(
+        request,
+        status,
+        serde_json::to_string(body)?,
+        "application/json; charset=utf-8",
+    )
+}
+
+fn respond(
+    request: Request,
+    status: u16,
+    body: String,
+    content_type: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let content_type = Header::from_bytes(&b"Content-Type"[..], content_type.as_bytes())
+        .map_err(|_| "cabeçalho Content-Type inválido")?;
+    let response = Response::from_string(body)
+        .with_status_code(status)
+        .with_header(content_type);
+    request.respond(response)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `(offset, limit, newest_first)` do comando gerado pela query
+    fn page(query: &str) -> Result<(usize, usize, bool), String> {
+        match transactions_command(query)? {
+            ControlCommand::Transactions {
+                offset,
+                limit,
+                newest_first,
+            } => Ok((offset, limit, newest_first)),
+            other => panic!("comando inesperado: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn transactions_query_defaults_and_parameters() {
+        assert_eq!(page(""), Ok((0, DEFAULT_PAGE_SIZE, false)));
+        assert_eq!(page("offset=200&limit=50"), Ok((200, 50, false)));
+        assert_eq!(page("order=desc&limit=1"), Ok((0, 1, true)));
+        assert_eq!(page("order=asc&"), Ok((0, DEFAULT_PAGE_SIZE, false)));
+        assert_eq!(
+            page(&format!("limit={}", MAX_PAGE_SIZE)),
+            Ok((0, MAX_PAGE_SIZE, false))
+        );
+    }
+
+    #[test]
+    fn transactions_query_rejects_invalid_values() {
+        for query in [
+            "limit=0",
+            "limit=1001",
+            "offset=-1",
+            "offset=abc",
+            "limit",
+            "order=newest",
+            "page=2",
+        ] {
+            assert!(page(query).is_err(), "{} deveria ser rejeitada", query);
+        }
+    }
+
+    #[test]
+    fn address_accepts_port_and_loopback_only() {
+        assert_eq!(
+            parse_address("8080").unwrap(),
+            "127.0.0.1:8080".parse::<SocketAddr>().unwrap()
+        );
+        assert_eq!(
+            parse_address("127.0.0.1:9000").unwrap(),
+            "127.0.0.1:9000".parse::<SocketAddr>().unwrap()
+        );
+        assert_eq!(
+            parse_address("[::1]:9000").unwrap(),
+            "[::1]:9000".parse::<SocketAddr>().unwrap()
+        );
+        assert!(parse_address("0.0.0.0:8080").is_err());
+        assert!(parse_address("192.168.0.10:8080").is_err());
+        assert!(parse_address("localhost:8080").is_err());
+        assert!(parse_address("70000").is_err());
+    }
+}
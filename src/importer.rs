@@ -0,0 +1,474 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+use crate::reader_csv::{
+    CsvReadOptions, ReadPosition, ReaderBtcFile, RowIssue, TimestampRun, ValidationReport,
+    DEFAULT_CHUNK_SIZE,
+};
+use crate::redis_client::RedisClient;
+
+/// Opções da importação incremental do CSV para o Redis
+#[derive(Debug, Clone)]
+pub struct ImportOptions {
+    /// Candles válidos por bloco gravado (limita a memória usada)
+    pub chunk_size: usize,
+    /// Retomar do último bloco confirmado quando houver checkpoint compatível
+    pub resume: bool,
+}
+
+impl Default for ImportOptions {
+    fn default() -> Self {
+        Self {
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            resume: true,
+        }
+    }
+}
+
+impl ImportOptions {
+    /// Lê `IMPORT_CHUNK_SIZE` (padrão 20000)
+    pub fn from_env() -> Self {
+        let mut options = Self::default();
+        if let Some(size) = env::var("IMPORT_CHUNK_SIZE")
+            .ok()
+            .and_then(|v| v.parse().ok())
+        {
+            options.chunk_size = size;
+        }
+        options
+    }
+}
+
+/// Progresso confirmado de uma importação, gravado após cada bloco salvo no Redis
+#[derive(Debug, Serialize, Deserialize)]
+struct ImportCheckpoint {
+    source: String,
+    file_size: u64,
+    modified_secs: Option<u64>,
+    position: ReadPosition,
+    next_index: usize,
+    /// Contadores do relatório; os problemas detalhados ficam no `IssueLog`
+    report: ValidationReport,
+    /// Bytes confirmados do `IssueLog` (o que vier depois é refeito na retomada)
+    #[serde(default)]
+    issues_bytes: u64,
+    /// Janela de duplicados do leitor, para detectar repetições através do checkpoint
+    #[serde(default)]
+    dedupe_runs: Vec<TimestampRun>,
+    /// Janela gravada timestamp a timestamp por checkpoints antigos
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    dedupe_window: Vec<i64>,
+    updated_at: DateTime<Utc>,
+}
+
+impl ImportCheckpoint {
+    fn path(file_path: &str) -> String {
+        ReaderBtcFile::artifact_path(file_path, ".import.json")
+    }
+
+    /// Carrega o checkpoint se ele ainda corresponde ao arquivo (mesmo tamanho e data)
+    fn load(file_path: &str) -> Option<Self> {
+        let data = fs::read_to_string(Self::path(file_path)).ok()?;
+        let checkpoint: Self = serde_json::from_str(&data).ok()?;
+        let (size, modified) = ReaderBtcFile::input_signature(file_path).ok()?;
+        if checkpoint.file_size == size && checkpoint.modified_secs == modified {
+            Some(checkpoint)
+        } else {
+            warn!(
+                "⚠️  Checkpoint de importação ignorado: {} foi alterado",
+                file_path
+            );
+            None
+        }
+    }
+
+    /// Grava de forma atômica (arquivo temporário + rename)
+    fn save(&self, file_path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let path = Self::path(file_path);
+        let tmp = format!("{}.tmp", path);
+        fs::write(&tmp, serde_json::to_string_pretty(self)?)?;
+        fs::rename(&tmp, &path)?;
+        Ok(())
+    }
+
+    fn remove(file_path: &str) {
+        let path = Self::path(file_path);
+        if Path::new(&path).exists() {
+            if let Err(e) = fs::remove_file(&path) {
+                warn!("⚠️  Não foi possível remover {}: {}", path, e);
+            }
+        }
+    }
+}
+
+/// Problemas de validação da importação, acrescentados a `<arquivo>.import-issues.jsonl`
+///
+/// O checkpoint guarda só os contadores e quantos bytes deste arquivo estão confirmados,
+/// então cada bloco grava apenas os problemas novos em vez de reescrever a lista inteira.
+struct IssueLog {
+    file: File,
+    written: usize,
+    bytes: u64,
+}
+
+impl IssueLog {
+    fn path(file_path: &str) -> String {
+        ReaderBtcFile::artifact_path(file_path, ".import-issues.jsonl")
+    }
+
+    /// Abre o arquivo mantendo os `bytes` confirmados e devolve os problemas gravados neles
+    fn open(
+        file_path: &str,
+        bytes: u64,
+    ) -> Result<(Self, Vec<RowIssue>), Box<dyn std::error::Error>> {
+        let file = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(Self::path(file_path))?;
+        let bytes = bytes.min(file.metadata()?.len());
+        file.set_len(bytes)?;
+
+        let mut issues = Vec::new();
+        for line in BufReader::new(&file).lines() {
+            issues.push(serde_json::from_str(&line?)?);
+        }
+        let log = Self {
+            file,
+            written: issues.len(),
+            bytes,
+        };
+        Ok((log, issues))
+    }
+
+    fn append(&mut self, issues: &[RowIssue]) -> Result<(), Box<dyn std::error::Error>> {
+        if issues.is_empty() {
+            return Ok(());
+        }
+        let mut data = String::new();
+        for issue in issues {
+            data.push_str(&serde_json::to_string(issue)?);
+            data.push('\n');
+        }
+        self.file.write_all(data.as_bytes())?;
+        self.file.flush()?;
+        self.written += issues.len();
+        self.bytes += data.len() as u64;
+        Ok(())
+    }
+
+    fn remove(file_path: &str) {
+        let path = Self::path(file_path);
+        if Path::new(&path).exists() {
+            if let Err(e) = fs::remove_file(&path) {
+                warn!("⚠️  Não foi possível remover {}: {}", path, e);
+            }
+        }
+    }
+}
+
+/// Importa o CSV para o Redis em blocos, sem carregar o arquivo inteiro na memória
+///
+/// Cada bloco é validado, gravado com um único pipeline e confirmado num checkpoint
+/// (`<arquivo>.import.json`, com os problemas de validação em `<arquivo>.import-issues.jsonl`).
+/// Se o processo for interrompido, a próxima importação do mesmo arquivo continua a partir
+/// da última linha confirmada. Retorna o total de
+/// registros no Redis (`btc_0` a `btc_{total - 1}`).
+pub fn import_csv(
+    file_path: &str,
+    redis: &RedisClient,
+    csv_options: &CsvReadOptions,
+    options: &ImportOptions,
+) -> Result<usize, Box<dyn std::error::Error>> {
+    let checkpoint = if options.resume {
+        ImportCheckpoint::load(file_path)
+    } else {
+        ImportCheckpoint::remove(file_path);
+        None
+    };
+
+    let mut reader = ReaderBtcFile::open_stream(file_path, csv_options)?;
+    let (mut issue_log, saved_issues) =
+        IssueLog::open(file_path, checkpoint.as_ref().map_or(0, |c| c.issues_bytes))?;
+    let mut next_index = 0;
+    if let Some(checkpoint) = checkpoint {
+        info!(
+            "⏯️  Retomando importação de {} após {} candles (btc_{})",
+            file_path, checkpoint.position.emitted, checkpoint.next_index
+        );
+        println!(
+            "⏯️  Retomando importação de {} após {} candles (btc_{})",
+            file_path, checkpoint.position.emitted, checkpoint.next_index
+        );
+        // Checkpoints antigos traziam os problemas e a janela dentro do próprio JSON
+        let mut report = checkpoint.report;
+        if report.issues.is_empty() {
+            report.issues = saved_issues;
+        } else {
+            issue_log.append(&report.issues)?;
+        }
+        let dedupe_window = if checkpoint.dedupe_window.is_empty() {
+            checkpoint.dedupe_runs
+        } else {
+            TimestampRun::compress(checkpoint.dedupe_window)
+        };
+        reader.resume(&checkpoint.position, report, &dedupe_window)?;
+        next_index = checkpoint.next_index;
+    }
+
+    let (file_size, modified_secs) = ReaderBtcFile::input_signature(file_path)?;
+    let start_byte = reader.position().byte;
+    let start_index = next_index;
+    let start_time = Instant::now();
+    let mut last_progress = Instant::now();
+    let mut saved = 0;
+    let mut skipped = 0;
+    let mut chunk_num = 0;
+
+    println!(
+        "🚀 Importação em blocos de {} registros ({:.1} MB)",
+        options.chunk_size,
+        file_size as f64 / 1_048_576.0
+    );
+
+    loop {
+        let chunk = reader.next_chunk(options.chunk_size);
+        // Arquivo da entrada que não abriu: parar antes de gravar a mesclagem incompleta
+        // (o checkpoint anterior continua válido para retomar depois de corrigi-lo)
+        reader.ensure_complete()?;
+        if chunk.is_empty() {
+            break;
+        }
+        chunk_num += 1;
+
+        let (chunk_saved, chunk_skipped) = redis.store_chunk(next_index, &chunk)?;
+        next_index += chunk.len();
+        saved += chunk_saved;
+        skipped += chunk_skipped;
+
+        let position = reader.position();
+        // Sem os problemas gravados o checkpoint ficaria incoerente: manter o anterior
+        match issue_log.append(reader.issues_from(issue_log.written)) {
+            Ok(()) => {
+                let checkpoint = ImportCheckpoint {
+                    source: file_path.to_string(),
+                    file_size,
+                    modified_secs,
+                    position: position.clone(),
+                    next_index,
+                    report: reader.report_counters(),
+                    issues_bytes: issue_log.bytes,
+                    dedupe_runs: reader.dedupe_window(),
+                    dedupe_window: Vec::new(),
+                    updated_at: Utc::now(),
+                };
+                if let Err(e) = checkpoint.save(file_path) {
+                    warn!(
+                        "⚠️  Não foi possível salvar checkpoint de importação: {}",
+                        e
+                    );
+                }
+            }
+            Err(e) => warn!(
+                "⚠️  Não foi possível gravar problemas de validação da importação: {}",
+                e
+            ),
+        }
+
+        if last_progress.elapsed() >= Duration::from_secs(2) {
+            display_progress(
+                chunk_num,
+                next_index,
+                position.byte,
+                start_byte,
+                file_size,
+                next_index - start_index,
+                start_time.elapsed(),
+            );
+            last_progress = Instant::now();
+        }
+    }
+
+    let report = reader.into_report();
+    ImportCheckpoint::remove(file_path);
+    drop(issue_log);
+    IssueLog::remove(file_path);
+
+    report.display();
+    let report_path = ReaderBtcFile::artifact_path(file_path, ".validation.json");
+    if let Err(e) = report.save(&report_path) {
+        warn!("⚠️  Não foi possível salvar relatório de validação: {}", e);
+    } else if report.skipped_rows() > 0 {
+        println!("📝 Relatório de validação completo em {}", report_path);
+    }
+
+    let elapsed = start_time.elapsed();
+    let rows_per_sec = (next_index - start_index) as f64 / elapsed.as_secs_f64().max(0.001);
+    info!(
+        "✅ Importação concluída: {} registros (btc_0 a btc_{}) | {} salvos | {} ignorados | {:.2?} ({:.0} registros/s)",
+        next_index,
+        next_index.saturating_sub(1),
+        saved,
+        skipped,
+        elapsed,
+        rows_per_sec
+    );
+    println!(
+        "✅ Importação concluída: {} registros (btc_0 a btc_{}) | {} salvos | {} ignorados | {:.2?} ({:.0} registros/s)",
+        next_index,
+        next_index.saturating_sub(1),
+        saved,
+        skipped,
+        elapsed,
+        rows_per_sec
+    );
+
+    Ok(next_index)
+}
+
+fn display_progress(
+    chunk_num: usize,
+    records: usize,
+    byte: u64,
+    start_byte: u64,
+    file_size: u64,
+    session_records: usize,
+    elapsed: Duration,
+) {
+    let secs = elapsed.as_secs_f64().max(0.001);
+    let percent = if file_size > 0 {
+        byte as f64 / file_size as f64 * 100.0
+    } else {
+        100.0
+    };
+    let rows_per_sec = session_records as f64 / secs;
+    let bytes_per_sec = byte.saturating_sub(start_byte) as f64 / secs;
+    let eta = if bytes_per_sec > 0.0 {
+        file_size.saturating_sub(byte) as f64 / bytes_per_sec
+    } else {
+        0.0
+    };
+
+    info!(
+        "📦 Bloco {}: {} registros | {:.1}% | {:.0} registros/s | {:.1} MB/s | ETA {:.0}s",
+        chunk_num,
+        records,
+        percent,
+        rows_per_sec,
+        bytes_per_sec / 1_048_576.0,
+        eta
+    );
+    println!(
+        "📦 Bloco {}: {} registros | {:.1}% | {:.0} registros/s | {:.1} MB/s | ETA {:.0}s",
+        chunk_num,
+        records,
+        percent,
+        rows_per_sec,
+        bytes_per_sec / 1_048_576.0,
+        eta
+    );
+}
+
+/// Comando `import [arquivo.csv|diretório|glob] [--chunk N] [--restart]`
+pub fn run_import_command(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let mut file_path = "data/btc_historical_data.csv".to_string();
+    let mut options = ImportOptions::from_env();
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--chunk" => {
+                options.chunk_size = iter.next().ok_or("--chunk requer um valor")?.parse()?;
+            }
+            "--restart" => options.resume = false,
+            path => file_path = path.to_string(),
+        }
+    }
+    if options.chunk_size == 0 {
+        return Err("--chunk deve ser maior que zero".into());
+    }
+
+    let redis = RedisClient::from_env()?;
+    import_csv(&file_path, &redis, &CsvReadOptions::from_env()?, &options)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reader_csv::RowIssueKind;
+
+    fn input(name: &str) -> String {
+        let path =
+            std::env::temp_dir().join(format!("btc-import-{}-{}.csv", name, std::process::id()));
+        path.to_str().unwrap().to_string()
+    }
+
+    fn issue(line: u64) -> RowIssue {
+        RowIssue {
+            file: None,
+            line,
+            kind: RowIssueKind::Invalid,
+            message: format!("linha {}", line),
+        }
+    }
+
+    fn lines(issues: &[RowIssue]) -> Vec<u64> {
+        issues.iter().map(|issue| issue.line).collect()
+    }
+
+    #[test]
+    fn issue_log_keeps_only_confirmed_bytes_on_reopen() {
+        let file_path = input("issues");
+        let (mut log, issues) = IssueLog::open(&file_path, 0).unwrap();
+        assert!(issues.is_empty());
+        log.append(&[issue(2), issue(5)]).unwrap();
+        let confirmed = log.bytes;
+        // Gravado depois do último checkpoint: refeito pela retomada
+        log.append(&[issue(9)]).unwrap();
+        drop(log);
+
+        let (mut log, issues) = IssueLog::open(&file_path, confirmed).unwrap();
+        assert_eq!(lines(&issues), vec![2, 5]);
+        assert_eq!(log.written, 2);
+        log.append(&[issue(11)]).unwrap();
+        drop(log);
+
+        let (_, issues) = IssueLog::open(&file_path, u64::MAX).unwrap();
+        IssueLog::remove(&file_path);
+        assert_eq!(lines(&issues), vec![2, 5, 11]);
+        assert!(!Path::new(&IssueLog::path(&file_path)).exists());
+    }
+
+    #[test]
+    fn legacy_checkpoint_with_inline_window_still_loads() {
+        let json = r#"{
+            "source": "data/btc.csv",
+            "file_size": 10,
+            "modified_secs": null,
+            "position": {"byte": 5, "line": 2, "record": 1},
+            "next_index": 1,
+            "report": {"total_rows": 1, "valid_rows": 1, "invalid_rows": 0,
+                       "inconsistent_rows": 0, "duplicate_rows": 0, "issues": []},
+            "dedupe_window": [60000, 120000],
+            "updated_at": "2024-01-01T00:00:00Z"
+        }"#;
+        let checkpoint: ImportCheckpoint = serde_json::from_str(json).unwrap();
+        assert_eq!(checkpoint.issues_bytes, 0);
+        assert!(checkpoint.dedupe_runs.is_empty());
+        assert_eq!(
+            TimestampRun::compress(checkpoint.dedupe_window),
+            vec![TimestampRun {
+                first: 60_000,
+                step: 60_000,
+                count: 2
+            }]
+        );
+    }
+}
@@ -0,0 +1,560 @@
+use std::env;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::thread;
+use std::time::Duration;
+use tracing::{info, warn};
+use tungstenite::Message;
+
+use crate::reader_csv::{check_ohlc, CsvBtcFile};
+use crate::redis_client::RedisClient;
+use crate::trade_btc;
+
+/// Espera antes de reconectar uma fonte que caiu
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+/// Candles recebidos e ainda não processados; com a fila cheia a leitura da fonte espera
+const FEED_CAPACITY: usize = 1000;
+
+/// Origem dos candles em tempo real
+///
+/// Todas as fontes recebem um candle por mensagem, no mesmo JSON gravado no Redis
+/// (`{"open":..,"high":..,"low":..,"close":..,"volume":..,"timestamp":..}`).
+#[derive(Debug, Clone)]
+pub enum LiveSource {
+    /// Canal Redis pub/sub (`pubsub:<canal>`)
+    PubSub(String),
+    /// Redis Stream com o candle no campo `data` (`stream:<chave>`), opcionalmente
+    /// lido por um grupo de consumidores
+    Stream {
+        key: String,
+        group: Option<StreamGroup>,
+    },
+    /// Named pipe com um candle por linha (`pipe:<caminho>`)
+    Pipe(PathBuf),
+    /// WebSocket com um candle por mensagem de texto (`ws://host:porta/caminho`)
+    WebSocket(String),
+}
+
+impl LiveSource {
+    pub fn parse(spec: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let spec = spec.trim();
+        if spec.starts_with("ws://") || spec.starts_with("wss://") {
+            return Ok(LiveSource::WebSocket(spec.to_string()));
+        }
+        match spec.split_once(':') {
+            Some(("pubsub", channel)) if !channel.is_empty() => {
+                Ok(LiveSource::PubSub(channel.to_string()))
+            }
+            Some(("stream", key)) if !key.is_empty() => Ok(LiveSource::Stream {
+                key: key.to_string(),
+                group: None,
+            }),
+            Some(("pipe", path)) if !path.is_empty() => Ok(LiveSource::Pipe(PathBuf::from(path))),
+            _ => Err(format!(
+                "Fonte inválida: {} (use pubsub:<canal>, stream:<chave>, pipe:<caminho> ou ws://...)",
+                spec
+            )
+            .into()),
+        }
+    }
+
+    /// Lê `LIVE_SOURCE` (padrão `pubsub:btc_candles`)
+    pub fn from_env() -> Result<Self, Box<dyn std::error::Error>> {
+        Self::parse(&env::var("LIVE_SOURCE").unwrap_or_else(|_| "pubsub:btc_candles".to_string()))
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            LiveSource::PubSub(channel) => format!("pubsub:{}", channel),
+            LiveSource::Stream { key, group: None } => format!("stream:{}", key),
+            LiveSource::Stream {
+                key,
+                group: Some(group),
+            } => format!("stream:{} (grupo {}/{})", key, group.group, group.consumer),
+            LiveSource::Pipe(path) => format!("pipe:{}", path.display()),
+            LiveSource::WebSocket(url) => url.clone(),
+        }
+    }
+
+    fn uses_redis(&self) -> bool {
+        matches!(self, LiveSource::PubSub(_) | LiveSource::Stream { .. })
+    }
+}
+
+/// Grupo de consumidores de um Redis Stream (XREADGROUP/XACK)
+#[derive(Debug, Clone)]
+pub struct StreamGroup {
+    pub group: String,
+    pub consumer: String,
+}
+
+/// Candle recebido da fonte
+///
+/// `entry_id` é o id da entrada nas leituras por grupo de consumidores: ela só deve ser
+/// confirmada (XACK) depois de processada e com o estado salvo, via [`StreamAcks`].
+#[derive(Debug, Clone)]
+pub struct LiveCandle {
+    pub candle: CsvBtcFile,
+    pub entry_id: Option<String>,
+}
+
+/// Candles recebidos da fonte e as confirmações pendentes das entradas já processadas
+pub struct LiveFeed {
+    pub candles: Receiver<LiveCandle>,
+    pub acks: StreamAcks,
+}
+
+/// Confirmações pendentes das entradas lidas por um grupo de consumidores
+///
+/// Para as demais fontes não há o que confirmar e tudo vira no-op.
+pub struct StreamAcks {
+    target: Option<(RedisClient, String, String)>,
+    pending: Vec<String>,
+}
+
+impl StreamAcks {
+    fn none() -> Self {
+        Self {
+            target: None,
+            pending: Vec::new(),
+        }
+    }
+
+    fn for_source(source: &LiveSource, redis: &RedisClient) -> Self {
+        let target = match source {
+            LiveSource::Stream {
+                key,
+                group: Some(group),
+            } => Some((redis.clone(), key.clone(), group.group.clone())),
+            _ => None,
+        };
+        Self {
+            target,
+            pending: Vec::new(),
+        }
+    }
+
+    /// Marca o candle como processado; a confirmação sai no próximo `flush`
+    pub fn push(&mut self, candle: &LiveCandle) {
+        if let (Some(_), Some(id)) = (&self.target, &candle.entry_id) {
+            self.pending.push(id.clone());
+        }
+    }
+
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Confirma de uma vez as entradas processadas; chamar só depois de salvar o estado
+    pub fn flush(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let Some((redis, key, group)) = &self.target else {
+            return Ok(());
+        };
+        redis.ack(key, group, &self.pending)?;
+        self.pending.clear();
+        Ok(())
+    }
+}
+
+/// Interpreta uma mensagem da fonte como candle validado
+pub fn parse_candle_message(text: &str) -> Result<CsvBtcFile, String> {
+    let candle: CsvBtcFile =
+        serde_json::from_str(text.trim()).map_err(|e| format!("JSON inválido: {}", e))?;
+    check_ohlc(&candle)?;
+    Ok(candle)
+}
+
+/// Inicia a leitura da fonte numa thread e devolve a fila com os candles recebidos
+///
+/// Quedas de conexão são registradas e a fonte é reaberta após alguns segundos; a
+/// thread só termina quando o receptor é descartado.
+pub fn spawn_feed(
+    source: LiveSource,
+    redis: Option<RedisClient>,
+) -> Result<LiveFeed, Box<dyn std::error::Error>> {
+    if source.uses_redis() && redis.is_none() {
+        return Err(format!("A fonte {} requer conexão Redis", source.describe()).into());
+    }
+
+    let acks = match &redis {
+        Some(redis) => StreamAcks::for_source(&source, redis),
+        None => StreamAcks::none(),
+    };
+    let (tx, candles) = mpsc::sync_channel(FEED_CAPACITY);
+    // Última entrada entregue de um stream sem grupo; fica fora do laço de reconexão para
+    // que a leitura retome dela e não perca o que foi publicado durante a queda.
+    // "$": na primeira conexão, apenas entradas publicadas a partir de agora
+    let mut stream_last_id = "$".to_string();
+    thread::Builder::new()
+        .name("live-feed".to_string())
+        .spawn(move || loop {
+            let result = match &source {
+                LiveSource::PubSub(channel) => {
+                    read_pubsub(redis.as_ref().expect("Redis verificado"), channel, &tx)
+                }
+                LiveSource::Stream { key, group: None } => read_stream(
+                    redis.as_ref().expect("Redis verificado"),
+                    key,
+                    &mut stream_last_id,
+                    &tx,
+                ),
+                LiveSource::Stream {
+                    key,
+                    group: Some(group),
+                } => read_stream_group(redis.as_ref().expect("Redis verificado"), key, group, &tx),
+                LiveSource::Pipe(path) => read_pipe(path, &tx),
+                LiveSource::WebSocket(url) => read_websocket(url, &tx),
+            };
+            match result {
+                Ok(()) => return,
+                Err(e) => {
+                    warn!(
+                        "⚠️  Fonte {} indisponível: {} - reconectando em {:?}",
+                        source.describe(),
+                        e,
+                        RECONNECT_DELAY
+                    );
+                    eprintln!(
+                        "⚠️  Fonte {} indisponível: {} - reconectando em {:?}",
+                        source.describe(),
+                        e,
+                        RECONNECT_DELAY
+                    );
+                    thread::sleep(RECONNECT_DELAY);
+                }
+            }
+        })?;
+
+    Ok(LiveFeed { candles, acks })
+}
+
+/// Resultado da entrega de uma mensagem ao simulador
+#[derive(Debug, PartialEq)]
+enum Delivery {
+    Sent,
+    /// Mensagem vazia ou inválida, descartada
+    Ignored,
+    /// O simulador já encerrou
+    Closed,
+}
+
+/// Envia a mensagem ao simulador, esperando se a fila estiver cheia
+fn deliver(tx: &SyncSender<LiveCandle>, text: &str, entry_id: Option<String>) -> Delivery {
+    if text.trim().is_empty() {
+        return Delivery::Ignored;
+    }
+    match parse_candle_message(text) {
+        Ok(candle) => match tx.send(LiveCandle { candle, entry_id }) {
+            Ok(()) => Delivery::Sent,
+            Err(_) => Delivery::Closed,
+        },
+        Err(e) => {
+            let preview: String = text.chars().take(120).collect();
+            warn!("⚠️  Mensagem ignorada ({}): {}", e, preview);
+            Delivery::Ignored
+        }
+    }
+}
+
+fn read_pubsub(
+    redis: &RedisClient,
+    channel: &str,
+    tx: &SyncSender<LiveCandle>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    redis.subscribe(channel, |payload| {
+        deliver(tx, &payload, None) != Delivery::Closed
+    })
+}
+
+/// Lê as entradas posteriores a `last_id`, atualizando-o a cada entrega
+fn read_stream(
+    redis: &RedisClient,
+    key: &str,
+    last_id: &mut String,
+    tx: &SyncSender<LiveCandle>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if last_id == "$" {
+        info!("📡 Lendo novas entradas do stream Redis {}", key);
+    } else {
+        info!(
+            "📡 Retomando o stream Redis {} após a entrada {}",
+            key, last_id
+        );
+    }
+    loop {
+        for (id, data) in redis.read_stream(key, last_id, 100, Duration::from_secs(5))? {
+            if deliver(tx, &data, None) == Delivery::Closed {
+                return Ok(());
+            }
+            *last_id = id;
+        }
+    }
+}
+
+fn read_stream_group(
+    redis: &RedisClient,
+    key: &str,
+    group: &StreamGroup,
+    tx: &SyncSender<LiveCandle>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    redis.ensure_group(key, &group.group)?;
+    info!(
+        "📡 Lendo stream Redis {} como {}/{}",
+        key, group.group, group.consumer
+    );
+
+    // Primeiro as entradas entregues antes de uma queda e não confirmadas, avançando pelo
+    // id porque continuam pendentes até o simulador salvar o estado; depois as novas (">").
+    // As já processadas voltam como repetidas e são ignoradas pelo simulador.
+    let mut id = "0".to_string();
+    loop {
+        let entries = redis.read_group(
+            key,
+            &group.group,
+            &group.consumer,
+            &id,
+            100,
+            Duration::from_secs(5),
+        )?;
+        if id != ">" {
+            match entries.last() {
+                Some((last, _)) => id = last.clone(),
+                None => {
+                    id = ">".to_string();
+                    continue;
+                }
+            }
+        }
+        // Mensagens inválidas são confirmadas aqui mesmo para não voltarem a cada leitura
+        let mut ignored = Vec::new();
+        for (entry_id, data) in entries {
+            match deliver(tx, &data, Some(entry_id.clone())) {
+                Delivery::Sent => {}
+                Delivery::Ignored => ignored.push(entry_id),
+                Delivery::Closed => return Ok(()),
+            }
+        }
+        redis.ack(key, &group.group, &ignored)?;
+    }
+}
+
+fn read_pipe(path: &Path, tx: &SyncSender<LiveCandle>) -> Result<(), Box<dyn std::error::Error>> {
+    if !path.exists() {
+        let status = Command::new("mkfifo").arg(path).status()?;
+        if !status.success() {
+            return Err(format!("Não foi possível criar o named pipe {}", path.display()).into());
+        }
+        info!("🪈 Named pipe criado em {}", path.display());
+        println!("🪈 Named pipe criado em {}", path.display());
+    }
+
+    loop {
+        // Bloqueia até um escritor abrir o pipe; EOF significa que ele fechou
+        let reader = BufReader::new(File::open(path)?);
+        for line in reader.lines() {
+            if deliver(tx, &line?, None) == Delivery::Closed {
+                return Ok(());
+            }
+        }
+    }
+}
+
+fn read_websocket(
+    url: &str,
+    tx: &SyncSender<LiveCandle>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (mut socket, _) = tungstenite::connect(url)?;
+    info!("🔌 WebSocket conectado em {}", url);
+    println!("🔌 WebSocket conectado em {}", url);
+
+    loop {
+        let text = match socket.read()? {
+            Message::Text(text) => text,
+            Message::Binary(data) => String::from_utf8_lossy(&data).into_owned(),
+            Message::Close(_) => return Err("conexão fechada pelo servidor".into()),
+            _ => continue,
+        };
+        if deliver(tx, &text, None) == Delivery::Closed {
+            let _ = socket.close(None);
+            return Ok(());
+        }
+    }
+}
+
+/// Comando `live [--source fonte] [--group nome [--consumer nome]] [--fresh] [--idle-timeout segundos]`
+pub fn run_live_command(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let mut source: Option<LiveSource> = None;
+    let mut group: Option<String> = None;
+    let mut consumer: Option<String> = None;
+    let mut fresh = false;
+    let mut idle_timeout = None;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--source" => {
+                source = Some(LiveSource::parse(
+                    iter.next().ok_or("--source requer um valor")?,
+                )?)
+            }
+            "--group" => group = Some(iter.next().ok_or("--group requer um nome")?.clone()),
+            "--consumer" => {
+                consumer = Some(iter.next().ok_or("--consumer requer um nome")?.clone())
+            }
+            "--fresh" => fresh = true,
+            "--idle-timeout" => {
+                let secs: u64 = iter
+                    .next()
+                    .ok_or("--idle-timeout requer um valor")?
+                    .parse()?;
+                idle_timeout = Some(Duration::from_secs(secs));
+            }
+            other => return Err(format!("Opção desconhecida: {}", other).into()),
+        }
+    }
+    let mut source = match source {
+        Some(source) => source,
+        None => LiveSource::from_env()?,
+    };
+    if let Some(group) = group {
+        let LiveSource::Stream { group: slot, .. } = &mut source else {
+            return Err("--group só pode ser usado com fontes stream:<chave>".into());
+        };
+        *slot = Some(StreamGroup {
+            group,
+            consumer: consumer.unwrap_or_else(|| "simulator".to_string()),
+        });
+    }
+
+    trade_btc::run_live_simulation(source, fresh, idle_timeout)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CANDLE: &str = r#"{"open":100.0,"high":110.0,"low":95.0,"close":105.0,"volume":2.5,"timestamp":"2024-01-01T00:00:00Z"}"#;
+
+    #[test]
+    fn parses_every_source_kind() {
+        assert!(matches!(
+            LiveSource::parse("pubsub:btc_candles").unwrap(),
+            LiveSource::PubSub(channel) if channel == "btc_candles"
+        ));
+        assert!(matches!(
+            LiveSource::parse(" stream:btc_stream ").unwrap(),
+            LiveSource::Stream { key, group: None } if key == "btc_stream"
+        ));
+        assert!(matches!(
+            LiveSource::parse("pipe:/tmp/btc.fifo").unwrap(),
+            LiveSource::Pipe(path) if path == Path::new("/tmp/btc.fifo")
+        ));
+        assert!(matches!(
+            LiveSource::parse("wss://exchange/candles").unwrap(),
+            LiveSource::WebSocket(url) if url == "wss://exchange/candles"
+        ));
+    }
+
+    #[test]
+    fn rejects_unknown_or_empty_sources() {
+        for spec in [
+            "",
+            "pubsub:",
+            "stream:",
+            "pipe:",
+            "http://host",
+            "kafka:topic",
+        ] {
+            assert!(LiveSource::parse(spec).is_err(), "{} deveria falhar", spec);
+        }
+    }
+
+    #[test]
+    fn describes_sources_in_the_parse_format() {
+        for spec in [
+            "pubsub:btc_candles",
+            "stream:btc_stream",
+            "pipe:/tmp/btc.fifo",
+        ] {
+            assert_eq!(LiveSource::parse(spec).unwrap().describe(), spec);
+        }
+        let grouped = LiveSource::Stream {
+            key: "btc_stream".to_string(),
+            group: Some(StreamGroup {
+                group: "sims".to_string(),
+                consumer: "a".to_string(),
+            }),
+        };
+        assert_eq!(grouped.describe(), "stream:btc_stream (grupo sims/a)");
+        assert!(grouped.uses_redis());
+        assert!(!LiveSource::Pipe(PathBuf::from("/tmp/btc.fifo")).uses_redis());
+    }
+
+    #[test]
+    fn parses_valid_candle_messages() {
+        let candle = parse_candle_message(&format!("  {}\n", CANDLE)).unwrap();
+        assert_eq!(candle.close, 105.0);
+        assert_eq!(candle.timestamp.to_rfc3339(), "2024-01-01T00:00:00+00:00");
+    }
+
+    #[test]
+    fn rejects_malformed_or_inconsistent_candles() {
+        assert!(parse_candle_message("not json")
+            .unwrap_err()
+            .starts_with("JSON inválido"));
+        let inverted = CANDLE.replace("\"low\":95.0", "\"low\":120.0");
+        assert!(parse_candle_message(&inverted).is_err());
+    }
+
+    fn received(entry_id: Option<&str>) -> LiveCandle {
+        LiveCandle {
+            candle: parse_candle_message(CANDLE).unwrap(),
+            entry_id: entry_id.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn acks_are_only_kept_for_group_reads() {
+        let redis = RedisClient::offline();
+        let mut acks = StreamAcks::for_source(&LiveSource::parse("stream:btc").unwrap(), &redis);
+        acks.push(&received(Some("1-0")));
+        assert_eq!(acks.pending(), 0);
+        acks.flush().unwrap();
+
+        let group = LiveSource::Stream {
+            key: "btc".to_string(),
+            group: Some(StreamGroup {
+                group: "sims".to_string(),
+                consumer: "a".to_string(),
+            }),
+        };
+        let mut acks = StreamAcks::for_source(&group, &redis);
+        acks.push(&received(Some("1-0")));
+        acks.push(&received(None));
+        acks.push(&received(Some("2-0")));
+        assert_eq!(acks.pending, vec!["1-0".to_string(), "2-0".to_string()]);
+        // Sem Redis a confirmação falha e as entradas continuam pendentes
+        assert!(acks.flush().is_err());
+        assert_eq!(acks.pending(), 2);
+    }
+
+    #[test]
+    fn deliver_skips_bad_messages_and_stops_when_the_simulator_is_gone() {
+        let (tx, rx) = mpsc::sync_channel(FEED_CAPACITY);
+        assert_eq!(deliver(&tx, "", None), Delivery::Ignored);
+        assert_eq!(deliver(&tx, "lixo", None), Delivery::Ignored);
+        assert_eq!(
+            deliver(&tx, CANDLE, Some("1-0".to_string())),
+            Delivery::Sent
+        );
+        let received: Vec<LiveCandle> = rx.try_iter().collect();
+        assert_eq!(received.len(), 1);
+        assert_eq!(received[0].entry_id.as_deref(), Some("1-0"));
+
+        drop(rx);
+        assert_eq!(deliver(&tx, CANDLE, None), Delivery::Closed);
+    }
+}
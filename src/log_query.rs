@@ -0,0 +1,832 @@
+use chrono::{DateTime, Duration as ChronoDuration, NaiveDate, Utc};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Seek, SeekFrom};
+use std::path::PathBuf;
+use std::thread;
+use std::time::Duration;
+
+use crate::reader_csv::parse_timestamp;
+use crate::simulations::NamedSimulation;
+
+/// Intervalo entre leituras dos logs no `--follow`
+const FOLLOW_POLL: Duration = Duration::from_millis(250);
+
+/// Prefixo do target dos eventos do próprio simulador, omitido na saída
+const CRATE_TARGET: &str = "btc_trading_simulator::";
+
+/// Como imprimir o resultado da consulta
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Output {
+    Lines,
+    Json,
+    Summary,
+}
+
+/// Comparação do `--field`: `=`, `!=`, `>`, `<` ou `~` (contém)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FieldOp {
+    Eq,
+    Ne,
+    Gt,
+    Lt,
+    Contains,
+}
+
+#[derive(Debug, Clone)]
+struct FieldMatch {
+    name: String,
+    op: FieldOp,
+    value: String,
+}
+
+impl FieldMatch {
+    fn parse(spec: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        // `!=` antes de `=`; o primeiro operador encontrado separa nome e valor
+        for (token, op) in [
+            ("!=", FieldOp::Ne),
+            ("~", FieldOp::Contains),
+            (">", FieldOp::Gt),
+            ("<", FieldOp::Lt),
+            ("=", FieldOp::Eq),
+        ] {
+            if let Some((name, value)) = spec.split_once(token) {
+                if name.is_empty() || name.contains(['=', '!', '~', '>', '<']) {
+                    continue;
+                }
+                return Ok(Self {
+                    name: name.to_string(),
+                    op,
+                    value: value.to_string(),
+                });
+            }
+        }
+        Err(format!(
+            "Filtro de campo inválido: {} (use campo=valor, !=, >, < ou ~)",
+            spec
+        )
+        .into())
+    }
+
+    fn matches(&self, entry: &Value) -> bool {
+        let Some(actual) = field(entry, &self.name) else {
+            return self.op == FieldOp::Ne;
+        };
+        let text = match actual {
+            Value::String(text) => text.clone(),
+            other => other.to_string(),
+        };
+        let numbers = actual
+            .as_f64()
+            .or_else(|| text.parse().ok())
+            .zip(self.value.parse::<f64>().ok());
+        match self.op {
+            FieldOp::Eq => match numbers {
+                Some((actual, expected)) => actual == expected,
+                None => text.eq_ignore_ascii_case(&self.value),
+            },
+            FieldOp::Ne => match numbers {
+                Some((actual, expected)) => actual != expected,
+                None => !text.eq_ignore_ascii_case(&self.value),
+            },
+            FieldOp::Gt => numbers.is_some_and(|(actual, expected)| actual > expected),
+            FieldOp::Lt => numbers.is_some_and(|(actual, expected)| actual < expected),
+            FieldOp::Contains => text.to_lowercase().contains(&self.value.to_lowercase()),
+        }
+    }
+}
+
+/// Filtros do `logs query`; todos precisam casar
+#[derive(Debug, Default)]
+struct LogFilter {
+    min_level: Option<u8>,
+    since: Option<DateTime<Utc>>,
+    until: Option<DateTime<Utc>>,
+    target: Option<String>,
+    text: Option<String>,
+    fields: Vec<FieldMatch>,
+}
+
+impl LogFilter {
+    fn matches(&self, entry: &Value) -> bool {
+        if let Some(min_level) = self.min_level {
+            if entry["level"].as_str().and_then(level_rank).unwrap_or(0) < min_level {
+                return false;
+            }
+        }
+        if self.since.is_some() || self.until.is_some() {
+            let Some(time) = timestamp(entry) else {
+                return false;
+            };
+            if self.since.is_some_and(|since| time < since)
+                || self.until.is_some_and(|until| time >= until)
+            {
+                return false;
+            }
+        }
+        if let Some(target) = &self.target {
+            if !entry["target"]
+                .as_str()
+                .unwrap_or("")
+                .contains(target.as_str())
+            {
+                return false;
+            }
+        }
+        if let Some(text) = &self.text {
+            if !message(entry).to_lowercase().contains(text.as_str()) {
+                return false;
+            }
+        }
+        self.fields.iter().all(|field| field.matches(entry))
+    }
+
+    /// Pula arquivos diários inteiros fora do período pedido
+    fn covers_day(&self, day: NaiveDate) -> bool {
+        let start = day.and_hms_opt(0, 0, 0).map(|naive| naive.and_utc());
+        let end = day
+            .succ_opt()
+            .and_then(|next| next.and_hms_opt(0, 0, 0))
+            .map(|naive| naive.and_utc());
+        !(self.since.zip(end).is_some_and(|(since, end)| since >= end)
+            || self
+                .until
+                .zip(start)
+                .is_some_and(|(until, start)| until <= start))
+    }
+}
+
+struct QueryOptions {
+    simulations: Vec<NamedSimulation>,
+    filter: LogFilter,
+    output: Output,
+    follow: bool,
+    limit: Option<usize>,
+}
+
+fn parse_args(args: &[String]) -> Result<QueryOptions, Box<dyn std::error::Error>> {
+    let mut simulations = None;
+    let mut filter = LogFilter::default();
+    let mut output = Output::Lines;
+    let mut follow = false;
+    let mut limit = None;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--all" => simulations = Some(NamedSimulation::all()?),
+            "--name" => {
+                simulations = Some(vec![NamedSimulation::new(
+                    iter.next().ok_or("--name requer um nome")?,
+                )?])
+            }
+            "--level" => {
+                let level = iter.next().ok_or("--level requer um nível")?;
+                filter.min_level = Some(level_rank(level).ok_or_else(|| {
+                    format!(
+                        "Nível inválido: {} (use error, warn, info, debug ou trace)",
+                        level
+                    )
+                })?);
+            }
+            "--since" => {
+                filter.since = Some(parse_time(
+                    iter.next().ok_or("--since requer um horário")?,
+                    false,
+                )?)
+            }
+            "--until" => {
+                filter.until = Some(parse_time(
+                    iter.next().ok_or("--until requer um horário")?,
+                    true,
+                )?)
+            }
+            "--target" => {
+                filter.target = Some(iter.next().ok_or("--target requer um módulo")?.clone())
+            }
+            "--grep" => {
+                filter.text = Some(iter.next().ok_or("--grep requer um texto")?.to_lowercase())
+            }
+            "--field" => filter.fields.push(FieldMatch::parse(
+                iter.next().ok_or("--field requer campo=valor")?,
+            )?),
+            "--follow" | "-f" => follow = true,
+            "--summary" => output = Output::Summary,
+            "--json" => output = Output::Json,
+            "--limit" => {
+                let value: usize = iter.next().ok_or("--limit requer um número")?.parse()?;
+                if value == 0 {
+                    return Err("--limit deve ser maior que zero".into());
+                }
+                limit = Some(value);
+            }
+            name if !name.starts_with('-') && simulations.is_none() => {
+                simulations = Some(vec![NamedSimulation::new(name)?])
+            }
+            other => return Err(format!("Argumento desconhecido: {}", other).into()),
+        }
+    }
+
+    if follow && output == Output::Summary {
+        return Err("--summary não pode ser usado com --follow".into());
+    }
+    if filter
+        .since
+        .zip(filter.until)
+        .is_some_and(|(since, until)| since >= until)
+    {
+        return Err("--since precisa ser anterior a --until".into());
+    }
+
+    Ok(QueryOptions {
+        simulations: match simulations {
+            Some(simulations) => simulations,
+            None => vec![NamedSimulation::from_env()?],
+        },
+        filter,
+        output,
+        follow,
+        limit,
+    })
+}
+
+/// Horário absoluto (mesmos formatos do CSV, em UTC) ou relativo a agora (`30m`, `2h`, `7d`)
+///
+/// No `--until`, uma data sem hora inclui o dia inteiro.
+fn parse_time(value: &str, end_of_day: bool) -> Result<DateTime<Utc>, Box<dyn std::error::Error>> {
+    let relative = value
+        .char_indices()
+        .last()
+        .and_then(|(index, unit)| Some((value[..index].parse::<i64>().ok()?, unit)))
+        .and_then(|(amount, unit)| match unit {
+            's' => Some(ChronoDuration::seconds(amount)),
+            'm' => Some(ChronoDuration::minutes(amount)),
+            'h' => Some(ChronoDuration::hours(amount)),
+            'd' => Some(ChronoDuration::days(amount)),
+            _ => None,
+        });
+    if let Some(ago) = relative {
+        return Ok(Utc::now() - ago);
+    }
+
+    if end_of_day {
+        if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+            if let Some(next) = date.succ_opt().and_then(|next| next.and_hms_opt(0, 0, 0)) {
+                return Ok(next.and_utc());
+            }
+        }
+    }
+    parse_timestamp(value).ok_or_else(|| {
+        format!(
+            "Horário inválido: {} (use AAAA-MM-DD, AAAA-MM-DD HH:MM[:SS], RFC 3339 ou 30m/2h/7d)",
+            value
+        )
+        .into()
+    })
+}
+
+fn level_rank(level: &str) -> Option<u8> {
+    match level.to_ascii_uppercase().as_str() {
+        "TRACE" => Some(0),
+        "DEBUG" => Some(1),
+        "INFO" => Some(2),
+        "WARN" | "WARNING" => Some(3),
+        "ERROR" => Some(4),
+        _ => None,
+    }
+}
+
+fn timestamp(entry: &Value) -> Option<DateTime<Utc>> {
+    entry["timestamp"]
+        .as_str()
+        .and_then(|time| DateTime::parse_from_rfc3339(time).ok())
+        .map(|time| time.with_timezone(&Utc))
+}
+
+fn message(entry: &Value) -> &str {
+    entry["fields"]["message"]
+        .as_str()
+        .or_else(|| entry["message"].as_str())
+        .unwrap_or("")
+}
+
+/// Campo do evento, do span atual ou de um span pai (o mais próximo primeiro)
+fn field<'a>(entry: &'a Value, name: &str) -> Option<&'a Value> {
+    entry["fields"]
+        .get(name)
+        .or_else(|| entry["span"].get(name))
+        .or_else(|| {
+            entry["spans"]
+                .as_array()?
+                .iter()
+                .rev()
+                .find_map(|span| span.get(name))
+        })
+        .or_else(|| entry.get(name))
+}
+
+/// Tipo do evento no `--summary`: o campo `event` quando existe; `span run`/`span batch`
+/// no fechamento de um span; senão as primeiras palavras da mensagem, sem emoji e antes
+/// de números ou valores (`💚 VENDA COM LUCRO - Ordem #3` → `VENDA COM LUCRO`)
+fn event_type(entry: &Value) -> String {
+    if let Some(event) = field(entry, "event").and_then(Value::as_str) {
+        return event.to_string();
+    }
+    if entry["fields"].get("time.busy").is_some() {
+        if let Some(span) = entry["span"]["name"].as_str() {
+            return format!("span {}", span);
+        }
+    }
+    let text = message(entry).trim_start_matches(|c: char| !c.is_alphanumeric());
+    let end = text
+        .find(|c: char| c.is_ascii_digit() || ":-#$('!.,=".contains(c))
+        .unwrap_or(text.len());
+    let words: Vec<&str> = text[..end].split_whitespace().take(4).collect();
+    if words.is_empty() {
+        entry["level"].as_str().unwrap_or("?").to_string()
+    } else {
+        words.join(" ")
+    }
+}
+
+fn print_entry(label: Option<&str>, entry: &Value, output: Output, raw: &str) {
+    if output == Output::Json {
+        println!("{}", raw.trim_end());
+        return;
+    }
+
+    let level = entry["level"].as_str().unwrap_or("?");
+    let level_color = match level {
+        "INFO" => "32",  // Verde
+        "WARN" => "33",  // Amarelo
+        "ERROR" => "31", // Vermelho
+        "DEBUG" => "36", // Ciano
+        _ => "37",       // Branco
+    };
+    let time = timestamp(entry)
+        .map(|time| time.format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_else(|| "----------  --:--:--".to_string());
+    let target = entry["target"].as_str().unwrap_or("");
+    let target = target.strip_prefix(CRATE_TARGET).unwrap_or(target);
+
+    let mut extra = String::new();
+    if let Some(fields) = entry["fields"].as_object() {
+        for (name, value) in fields.iter().filter(|(name, _)| *name != "message") {
+            match value {
+                Value::String(text) => extra.push_str(&format!(" {}={}", name, text)),
+                other => extra.push_str(&format!(" {}={}", name, other)),
+            }
+        }
+    }
+
+    let prefix = label.map(|name| format!("[{}] ", name)).unwrap_or_default();
+    println!(
+        "\x1b[{}m{}[{}] {:5} {}: {}\x1b[0m{}",
+        level_color,
+        prefix,
+        time,
+        level,
+        target,
+        message(entry),
+        extra
+    );
+}
+
+/// Eventos de um dia: total, por nível e por tipo
+#[derive(Default)]
+struct DaySummary {
+    total: usize,
+    levels: BTreeMap<String, usize>,
+    types: HashMap<String, usize>,
+}
+
+/// Contagem de eventos por dia e tipo
+#[derive(Default)]
+struct Summary {
+    days: BTreeMap<NaiveDate, DaySummary>,
+}
+
+impl Summary {
+    fn add(&mut self, entry: &Value) {
+        let Some(day) = timestamp(entry).map(|time| time.date_naive()) else {
+            return;
+        };
+        let summary = self.days.entry(day).or_default();
+        summary.total += 1;
+        *summary
+            .levels
+            .entry(entry["level"].as_str().unwrap_or("?").to_string())
+            .or_default() += 1;
+        *summary.types.entry(event_type(entry)).or_default() += 1;
+    }
+
+    fn print(&self) {
+        if self.days.is_empty() {
+            println!("📭 Nenhum evento encontrado com esses filtros");
+            return;
+        }
+        let mut total = 0;
+        for (day, summary) in &self.days {
+            total += summary.total;
+            let levels: Vec<String> = summary
+                .levels
+                .iter()
+                .map(|(level, count)| format!("{} {}", count, level))
+                .collect();
+            println!(
+                "\n📅 {} - {} eventos ({})",
+                day,
+                summary.total,
+                levels.join(", ")
+            );
+            println!("{}", "-".repeat(60));
+            let mut types: Vec<(&String, &usize)> = summary.types.iter().collect();
+            types.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+            for (event, count) in types {
+                println!("  {:>8}  {}", count, event);
+            }
+        }
+        println!("\n📊 Total: {} eventos em {} dias", total, self.days.len());
+    }
+}
+
+/// Arquivo acompanhado pelo `--follow` a partir de `offset`
+struct Tail {
+    label: Option<String>,
+    simulation: NamedSimulation,
+    path: Option<PathBuf>,
+    offset: u64,
+    pending: String,
+}
+
+impl Tail {
+    /// Lê as linhas completas novas; ao virar o dia passa para o arquivo novo
+    fn poll(&mut self, options: &QueryOptions) -> Result<bool, Box<dyn std::error::Error>> {
+        let mut found = self.read_new(options)?;
+        let newest = self.simulation.log_files()?.pop();
+        if newest.is_some() && newest != self.path {
+            self.path = newest;
+            self.offset = 0;
+            self.pending.clear();
+            found |= self.read_new(options)?;
+        }
+        Ok(found)
+    }
+
+    fn read_new(&mut self, options: &QueryOptions) -> Result<bool, Box<dyn std::error::Error>> {
+        let Some(path) = &self.path else {
+            return Ok(false);
+        };
+        let mut file = File::open(path)?;
+        if file.metadata()?.len() < self.offset {
+            // Arquivo truncado ou recriado
+            self.offset = 0;
+            self.pending.clear();
+        }
+        file.seek(SeekFrom::Start(self.offset))?;
+        let mut reader = BufReader::new(file);
+        let mut found = false;
+        loop {
+            let read = reader.read_line(&mut self.pending)?;
+            if read == 0 {
+                break;
+            }
+            self.offset += read as u64;
+            if !self.pending.ends_with('\n') {
+                // Linha ainda sendo escrita: termina na próxima leitura
+                break;
+            }
+            if let Ok(entry) = serde_json::from_str::<Value>(self.pending.trim()) {
+                if options.filter.matches(&entry) {
+                    print_entry(self.label.as_deref(), &entry, options.output, &self.pending);
+                    found = true;
+                }
+            }
+            self.pending.clear();
+        }
+        Ok(found)
+    }
+}
+
+/// Comando `logs query`: filtra os logs JSON de todos os dias, com resumo ou `--follow`
+///
+/// Uma simulação sem `--limit` é impressa conforme é lida; com `--limit` só os últimos
+/// eventos ficam em memória. Várias simulações são guardadas para intercalar por horário.
+pub fn run_logs_query(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let options = parse_args(args)?;
+    let labeled = options.simulations.len() > 1;
+    let stream = !labeled && options.limit.is_none();
+
+    let mut summary = Summary::default();
+    let mut matches: Vec<(DateTime<Utc>, Option<String>, Value, String)> = Vec::new();
+    let mut found = 0;
+    let mut tails = Vec::new();
+
+    for simulation in &options.simulations {
+        let label = labeled.then(|| simulation.name().to_string());
+        let files = simulation.log_files()?;
+        let mut end_offset = 0;
+        // Últimos eventos da simulação (limitados a `--limit`), em ordem de leitura
+        let mut recent: VecDeque<(DateTime<Utc>, Value, String)> = VecDeque::new();
+
+        for path in &files {
+            let day = path
+                .extension()
+                .and_then(|date| date.to_str())
+                .and_then(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d").ok());
+            let newest = Some(path) == files.last();
+            if !newest && day.is_some_and(|day| !options.filter.covers_day(day)) {
+                continue;
+            }
+
+            let mut reader = BufReader::new(File::open(path)?);
+            let mut line = String::new();
+            let mut offset = 0;
+            loop {
+                line.clear();
+                let read = reader.read_line(&mut line)?;
+                if read == 0 || !line.ends_with('\n') {
+                    break;
+                }
+                offset += read as u64;
+                let Ok(entry) = serde_json::from_str::<Value>(line.trim()) else {
+                    continue;
+                };
+                if !options.filter.matches(&entry) {
+                    continue;
+                }
+                found += 1;
+                if options.output == Output::Summary {
+                    summary.add(&entry);
+                } else if stream {
+                    print_entry(None, &entry, options.output, &line);
+                } else {
+                    let time = timestamp(&entry).unwrap_or_default();
+                    recent.push_back((time, entry, line.clone()));
+                    if options.limit.is_some_and(|limit| recent.len() > limit) {
+                        recent.pop_front();
+                    }
+                }
+            }
+            if newest {
+                end_offset = offset;
+            }
+        }
+        matches.extend(
+            recent
+                .into_iter()
+                .map(|(time, entry, raw)| (time, label.clone(), entry, raw)),
+        );
+
+        tails.push(Tail {
+            label,
+            simulation: simulation.clone(),
+            path: files.last().cloned(),
+            offset: end_offset,
+            pending: String::new(),
+        });
+    }
+
+    if options.output == Output::Summary {
+        summary.print();
+        return Ok(());
+    }
+
+    if labeled {
+        matches.sort_by_key(|(time, ..)| *time);
+    }
+    let skip = options
+        .limit
+        .map_or(0, |limit| matches.len().saturating_sub(limit));
+    for (_, label, entry, raw) in &matches[skip..] {
+        print_entry(label.as_deref(), entry, options.output, raw);
+    }
+    if found == 0 && !options.follow {
+        println!("📭 Nenhum evento encontrado com esses filtros");
+    }
+    if !options.follow {
+        return Ok(());
+    }
+
+    if options.output == Output::Lines {
+        println!("🔄 Aguardando novos eventos (Ctrl+C para sair)...");
+    }
+    loop {
+        let mut idle = true;
+        for tail in tails.iter_mut() {
+            if tail.poll(&options)? {
+                idle = false;
+            }
+        }
+        if idle {
+            thread::sleep(FOLLOW_POLL);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use serde_json::json;
+
+    fn entry(level: &str, time: &str, message: &str) -> Value {
+        json!({
+            "timestamp": time,
+            "level": level,
+            "target": "btc_trading_simulator::trade_btc",
+            "fields": { "message": message, "order_id": 3, "price": 42000.5, "reason": "drop" },
+            "span": { "name": "batch", "index": 120 },
+            "spans": [{ "name": "run", "run_id": "abc", "index": 0 }, { "name": "batch", "index": 120 }],
+        })
+    }
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn parses_field_operators() {
+        for (spec, op, name, value) in [
+            ("reason=drop", FieldOp::Eq, "reason", "drop"),
+            ("reason!=drop", FieldOp::Ne, "reason", "drop"),
+            ("price>100", FieldOp::Gt, "price", "100"),
+            ("price<100", FieldOp::Lt, "price", "100"),
+            ("message~venda", FieldOp::Contains, "message", "venda"),
+            ("note=a=b", FieldOp::Eq, "note", "a=b"),
+        ] {
+            let field = FieldMatch::parse(spec).unwrap();
+            assert_eq!(
+                (field.op, field.name.as_str(), field.value.as_str()),
+                (op, name, value)
+            );
+        }
+        for spec in ["reason", "=drop", "!=x"] {
+            assert!(FieldMatch::parse(spec).is_err(), "{}", spec);
+        }
+    }
+
+    #[test]
+    fn field_filters_compare_numbers_and_text() {
+        let entry = entry("INFO", "2024-01-01T10:00:00Z", "💰 COMPRA");
+        let matches = |spec: &str| FieldMatch::parse(spec).unwrap().matches(&entry);
+        assert!(matches("order_id=3"));
+        assert!(matches("order_id=3.0"));
+        assert!(matches("price>42000"));
+        assert!(!matches("price<42000"));
+        assert!(matches("reason=DROP"));
+        assert!(matches("reason!=first"));
+        assert!(matches("message~compra"));
+        // Campo ausente só casa com !=
+        assert!(!matches("missing=1"));
+        assert!(matches("missing!=1"));
+        assert!(!matches("reason>1"));
+    }
+
+    #[test]
+    fn fields_come_from_the_event_then_the_closest_span() {
+        let entry = entry("INFO", "2024-01-01T10:00:00Z", "x");
+        assert_eq!(field(&entry, "order_id"), Some(&json!(3)));
+        assert_eq!(field(&entry, "index"), Some(&json!(120)));
+        assert_eq!(field(&entry, "run_id"), Some(&json!("abc")));
+        assert_eq!(field(&entry, "level"), Some(&json!("INFO")));
+        assert_eq!(field(&entry, "nada"), None);
+    }
+
+    #[test]
+    fn filter_combines_level_period_target_and_text() {
+        let info = entry("INFO", "2024-01-01T10:00:00Z", "💚 VENDA COM LUCRO");
+        let warn = entry("WARN", "2024-01-02T10:00:00Z", "⚠️ Gap");
+        let at = |day, hour| Utc.with_ymd_and_hms(2024, 1, day, hour, 0, 0).unwrap();
+
+        let filter = LogFilter {
+            min_level: level_rank("warn"),
+            ..LogFilter::default()
+        };
+        assert!(!filter.matches(&info) && filter.matches(&warn));
+
+        let filter = LogFilter {
+            since: Some(at(1, 10)),
+            until: Some(at(2, 10)),
+            ..LogFilter::default()
+        };
+        assert!(filter.matches(&info) && !filter.matches(&warn));
+        assert!(!filter.matches(&json!({ "level": "INFO" })));
+
+        let filter = LogFilter {
+            target: Some("supervisor".to_string()),
+            ..LogFilter::default()
+        };
+        assert!(!filter.matches(&info));
+
+        let filter = LogFilter {
+            text: Some("venda".to_string()),
+            fields: vec![FieldMatch::parse("reason=drop").unwrap()],
+            ..LogFilter::default()
+        };
+        assert!(filter.matches(&info) && !filter.matches(&warn));
+    }
+
+    #[test]
+    fn daily_files_outside_the_period_are_skipped() {
+        let day = NaiveDate::from_ymd_opt(2024, 1, 2).unwrap();
+        let at = |day, hour| Utc.with_ymd_and_hms(2024, 1, day, hour, 0, 0).unwrap();
+        assert!(LogFilter::default().covers_day(day));
+        let since = |time| LogFilter {
+            since: Some(time),
+            ..LogFilter::default()
+        };
+        assert!(since(at(2, 23)).covers_day(day));
+        assert!(!since(at(3, 0)).covers_day(day));
+        let until = |time| LogFilter {
+            until: Some(time),
+            ..LogFilter::default()
+        };
+        assert!(until(at(2, 1)).covers_day(day));
+        assert!(!until(at(2, 0)).covers_day(day));
+    }
+
+    #[test]
+    fn parses_absolute_and_relative_times() {
+        assert_eq!(
+            parse_time("2024-01-02", false).unwrap(),
+            Utc.with_ymd_and_hms(2024, 1, 2, 0, 0, 0).unwrap()
+        );
+        // No --until a data sem hora inclui o dia inteiro
+        assert_eq!(
+            parse_time("2024-01-02", true).unwrap(),
+            Utc.with_ymd_and_hms(2024, 1, 3, 0, 0, 0).unwrap()
+        );
+        assert_eq!(
+            parse_time("2024-01-02 13:45", true).unwrap(),
+            Utc.with_ymd_and_hms(2024, 1, 2, 13, 45, 0).unwrap()
+        );
+        let ago = Utc::now() - parse_time("2h", false).unwrap();
+        assert!((ago - ChronoDuration::hours(2)).num_seconds().abs() <= 5);
+        assert!(parse_time("ontem", false).is_err());
+        assert!(parse_time("2x", false).is_err());
+    }
+
+    #[test]
+    fn rejects_inconsistent_query_options() {
+        for line in [
+            "--summary --follow",
+            "--since 2024-01-02 --until 2024-01-01",
+            "--limit 0",
+            "--level verbose",
+            "--field reason",
+            "--name ../x",
+            "--since",
+            "--verbose",
+        ] {
+            assert!(
+                parse_args(&args(line)).is_err(),
+                "'{}' deveria falhar",
+                line
+            );
+        }
+        let options = parse_args(&args("papel --level warn --json --limit 5")).unwrap();
+        assert_eq!(options.simulations[0].name(), "papel");
+        assert_eq!(options.filter.min_level, level_rank("WARNING"));
+        assert_eq!(options.output, Output::Json);
+        assert_eq!(options.limit, Some(5));
+        assert!(!options.follow);
+    }
+
+    #[test]
+    fn summary_groups_events_by_day_and_type() {
+        let mut structured = entry("INFO", "2024-01-01T10:00:00Z", "💰 COMPRA");
+        structured["fields"]["event"] = json!("buy");
+        let span_close = json!({
+            "timestamp": "2024-01-01T11:00:00Z",
+            "level": "INFO",
+            "fields": { "message": "close", "time.busy": "1ms" },
+            "span": { "name": "run" },
+        });
+        let legacy = entry(
+            "INFO",
+            "2024-01-02T10:00:00Z",
+            "💚 VENDA COM LUCRO - Ordem #3",
+        );
+        assert_eq!(event_type(&structured), "buy");
+        assert_eq!(event_type(&span_close), "span run");
+        assert_eq!(event_type(&legacy), "VENDA COM LUCRO");
+        assert_eq!(event_type(&json!({ "level": "WARN" })), "WARN");
+
+        let mut summary = Summary::default();
+        for entry in [
+            &structured,
+            &span_close,
+            &legacy,
+            &json!({ "level": "INFO" }),
+        ] {
+            summary.add(entry);
+        }
+        assert_eq!(summary.days.len(), 2);
+        let first = &summary.days[&NaiveDate::from_ymd_opt(2024, 1, 1).unwrap()];
+        assert_eq!(first.total, 2);
+        assert_eq!(first.levels["INFO"], 2);
+        assert_eq!(first.types["buy"], 1);
+    }
+}
@@ -1,35 +1,72 @@
+mod benchmark;
+mod candle_cache;
+mod chart;
+mod compare;
+mod control;
+mod data_quality;
+mod events;
+mod export;
+mod funding;
+mod http_api;
+mod importer;
+mod live;
+mod log_query;
+mod metrics;
+mod periods;
 mod reader_csv;
 mod redis_client;
+mod replay;
+mod report;
+mod run_manifest;
+mod simulations;
+mod state_store;
+mod supervisor;
+mod ticks;
 mod trade_btc;
+mod tui;
 
-use crate::{reader_csv::ReaderBtcFile, redis_client::RedisClient, trade_btc::TradeSimulator};
+use crate::{
+    importer::ImportOptions, reader_csv::CsvReadOptions, redis_client::RedisClient,
+    simulations::{NamedSimulation, LOG_DIR}, state_store::StateStore, trade_btc::TradeSimulator,
+};
 use std::env;
 use std::fs;
 use std::io::{BufRead, BufReader, Seek, SeekFrom};
-use std::path::Path;
 use std::process::{Command, Stdio};
 use std::thread;
 use std::time::{Duration, Instant};
 use tracing::{info, error};
-use tracing_subscriber::{EnvFilter, fmt, layer::SubscriberExt, util::SubscriberInitExt};
-use tracing_appender::{rolling, non_blocking};
+use tracing_subscriber::{EnvFilter, Layer, fmt, filter::filter_fn, layer::SubscriberExt, util::SubscriberInitExt};
+use tracing_subscriber::fmt::format::{self, FmtSpan};
+use tracing_appender::{rolling, non_blocking, non_blocking::WorkerGuard};
 
-fn init_logging() {
+/// Tempo máximo que o `stop` espera a simulação salvar o estado e sair
+const STOP_TIMEOUT: Duration = Duration::from_secs(60);
+
+fn init_logging() -> WorkerGuard {
     // Criar diretório de logs se não existir
-    std::fs::create_dir_all("logs").expect("Failed to create logs directory");
+    std::fs::create_dir_all(LOG_DIR).expect("Failed to create logs directory");
     
-    // Configurar appender para rotação diária
-    let file_appender = rolling::daily("logs", "btc_trading.log");
+    // Configurar appender para rotação diária (um arquivo por simulação nomeada)
+    let simulation = NamedSimulation::from_env().unwrap_or_default();
+    let file_appender = rolling::daily(LOG_DIR, simulation.log_prefix());
     let (file_writer, guard) = non_blocking(file_appender);
     
-    // IMPORTANTE: Manter o guard vivo para garantir que os logs sejam escritos
-    std::mem::forget(guard);
+    // IMPORTANTE: o guard precisa viver até o fim do main para gravar os logs pendentes
     
     // Configurar filtros - aceitar variável de ambiente RUST_LOG ou usar padrão
     let env_filter = EnvFilter::try_from_default_env()
         .unwrap_or_else(|_| EnvFilter::new("info,btc_trading_simulator=debug"));
     
     // Configurar subscriber com múltiplas camadas
+    // Terminal: só a mensagem; campos (order_id, price, pnl...) e spans (run, batch) ficam no JSON
+    let message_only = format::debug_fn(|writer, field, value| {
+        if field.name() == "message" {
+            write!(writer, "{:?}", value)
+        } else {
+            Ok(())
+        }
+    });
     tracing_subscriber::registry()
         .with(env_filter)
         .with(
@@ -39,6 +76,8 @@ fn init_logging() {
                 .with_target(true)
                 .with_thread_ids(true)
                 .with_level(true)
+                .fmt_fields(message_only)
+                .with_filter(filter_fn(|metadata| metadata.is_event()))
         )
         .with(
             fmt::layer()
@@ -47,269 +86,370 @@ fn init_logging() {
                 .with_target(true)
                 .with_thread_ids(true)
                 .with_level(true)
+                .with_span_events(FmtSpan::CLOSE)
                 .json()
         )
         .init();
     
     info!("🚀 Sistema de logging inicializado");
-    info!("📁 Logs salvos em: logs/btc_trading.log.YYYY-MM-DD");
+    info!("📁 Logs salvos em: {}/{}.YYYY-MM-DD", LOG_DIR, simulation.log_prefix());
+    guard
+}
+
+/// Simulações escolhidas por `[nome|--all]`; sem argumento, a de `STATE_RUN_ID`
+fn select_simulations(args: &[String]) -> Result<Vec<NamedSimulation>, Box<dyn std::error::Error>> {
+    match args.first().map(String::as_str) {
+        Some("--all") => NamedSimulation::all(),
+        Some(name) => Ok(vec![NamedSimulation::new(name)?]),
+        None => Ok(vec![NamedSimulation::from_env()?]),
+    }
+}
+
+fn print_log_line(label: Option<&str>, line: &str) {
+    if let Ok(log_entry) = serde_json::from_str::<serde_json::Value>(line.trim()) {
+        if let (Some(timestamp), Some(level), Some(message)) = (
+            log_entry["timestamp"].as_str(),
+            log_entry["level"].as_str(),
+            log_entry["fields"]["message"].as_str().or_else(|| log_entry["message"].as_str()),
+        ) {
+            let level_color = match level {
+                "INFO" => "32", // Verde
+                "WARN" => "33", // Amarelo
+                "ERROR" => "31", // Vermelho
+                "DEBUG" => "36", // Ciano
+                _ => "37", // Branco
+            };
+            
+            let prefix = label.map(|name| format!("[{}] ", name)).unwrap_or_default();
+            println!("\x1b[{}m{}[{}] {}: {}\x1b[0m", 
+                    level_color, 
+                    prefix,
+                    timestamp.get(11..19).unwrap_or("--:--:--"), 
+                    level, 
+                    message);
+        }
+    }
 }
 
-fn follow_logs() -> Result<(), Box<dyn std::error::Error>> {
+fn follow_logs(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
     println!("📊 Acompanhando logs da simulação em tempo real...");
     println!("💡 Use Ctrl+C para parar de acompanhar\n");
 
-    let today = chrono::Utc::now().format("%Y-%m-%d");
-    let log_file_path = format!("logs/btc_trading.log.{}", today);
-    
-    if !Path::new(&log_file_path).exists() {
-        println!("❌ Arquivo de log não encontrado: {}", log_file_path);
-        println!("💡 Certifique-se de que a simulação está rodando");
-        return Ok(());
-    }
+    let simulations = select_simulations(args)?;
+    // Com várias simulações, cada linha recebe o nome da simulação
+    let labeled = simulations.len() > 1;
+    let mut readers = Vec::new();
 
-    println!("📂 Lendo arquivo: {}", log_file_path);
-    println!("{}", "=".repeat(80));
+    for simulation in &simulations {
+        let log_file_path = simulation.today_log();
+        
+        if !log_file_path.exists() {
+            println!("❌ Arquivo de log não encontrado: {}", log_file_path.display());
+            continue;
+        }
 
-    // Primeiro, mostrar as últimas 20 linhas do arquivo
-    println!("📜 Últimas entradas do log:");
-    println!("{}", "-".repeat(80));
-    
-    let content = fs::read_to_string(&log_file_path)?;
-    let lines: Vec<&str> = content.lines().collect();
-    let start_idx = if lines.len() > 20 { lines.len() - 20 } else { 0 };
-    
-    for line in &lines[start_idx..] {
-        if !line.trim().is_empty() {
-            if let Ok(log_entry) = serde_json::from_str::<serde_json::Value>(line.trim()) {
-                if let (Some(timestamp), Some(level), Some(message)) = (
-                    log_entry["timestamp"].as_str(),
-                    log_entry["level"].as_str(),
-                    log_entry["fields"]["message"].as_str().or_else(|| log_entry["message"].as_str()),
-                ) {
-                    let level_color = match level {
-                        "INFO" => "32", // Verde
-                        "WARN" => "33", // Amarelo
-                        "ERROR" => "31", // Vermelho
-                        "DEBUG" => "36", // Ciano
-                        _ => "37", // Branco
-                    };
-                    
-                    println!("\x1b[{}m[{}] {}: {}\x1b[0m", 
-                            level_color, 
-                            timestamp.get(11..19).unwrap_or("--:--:--"), 
-                            level, 
-                            message);
-                }
+        println!("📂 Lendo arquivo: {}", log_file_path.display());
+        println!("{}", "=".repeat(80));
+
+        // Primeiro, mostrar as últimas 20 linhas do arquivo
+        println!("📜 Últimas entradas do log:");
+        println!("{}", "-".repeat(80));
+        
+        let label = labeled.then(|| simulation.name().to_string());
+        let content = fs::read_to_string(&log_file_path)?;
+        let lines: Vec<&str> = content.lines().collect();
+        let start_idx = if lines.len() > 20 { lines.len() - 20 } else { 0 };
+        
+        for line in &lines[start_idx..] {
+            if !line.trim().is_empty() {
+                print_log_line(label.as_deref(), line);
             }
         }
+        
+        println!("{}", "-".repeat(80));
+
+        let mut file = fs::File::open(&log_file_path)?;
+        file.seek(SeekFrom::End(0))?; // Começar do final do arquivo
+        readers.push((label, BufReader::new(file)));
+    }
+
+    if readers.is_empty() {
+        println!("💡 Certifique-se de que a simulação está rodando");
+        return Ok(());
     }
-    
-    println!("{}", "-".repeat(80));
     println!("🔄 Aguardando novos logs...\n");
 
     // Agora monitorar novas entradas
-    let mut file = fs::File::open(&log_file_path)?;
-    file.seek(SeekFrom::End(0))?; // Começar do final do arquivo
-    
-    let mut reader = BufReader::new(file);
     let mut line = String::new();
 
     loop {
-        line.clear();
-        match reader.read_line(&mut line) {
-            Ok(0) => {
-                // Arquivo não cresceu, aguardar um pouco
-                thread::sleep(Duration::from_millis(100));
-            }
-            Ok(_) => {
-                // Nova linha encontrada, processar
-                if let Ok(log_entry) = serde_json::from_str::<serde_json::Value>(&line.trim()) {
-                    if let (Some(timestamp), Some(level), Some(message)) = (
-                        log_entry["timestamp"].as_str(),
-                        log_entry["level"].as_str(),
-                        log_entry["fields"]["message"].as_str().or_else(|| log_entry["message"].as_str()),
-                    ) {
-                        let level_color = match level {
-                            "INFO" => "32", // Verde
-                            "WARN" => "33", // Amarelo
-                            "ERROR" => "31", // Vermelho
-                            "DEBUG" => "36", // Ciano
-                            _ => "37", // Branco
-                        };
-                        
-                        println!("\x1b[{}m[{}] {}: {}\x1b[0m", 
-                                level_color, 
-                                timestamp.get(11..19).unwrap_or("--:--:--"), 
-                                level, 
-                                message);
-                    }
+        let mut idle = true;
+        for (label, reader) in readers.iter_mut() {
+            line.clear();
+            match reader.read_line(&mut line) {
+                Ok(0) => {}
+                Ok(_) => {
+                    // Nova linha encontrada, processar
+                    idle = false;
+                    print_log_line(label.as_deref(), &line);
+                }
+                Err(_) => {
+                    // Erro na leitura, aguardar e tentar novamente
+                    thread::sleep(Duration::from_millis(500));
                 }
-            }
-            Err(_) => {
-                // Erro na leitura, aguardar e tentar novamente
-                thread::sleep(Duration::from_millis(500));
             }
         }
+        if idle {
+            // Arquivos não cresceram, aguardar um pouco
+            thread::sleep(Duration::from_millis(100));
+        }
     }
 }
 
-fn start_daemon() -> Result<(), Box<dyn std::error::Error>> {
-    println!("🚀 Iniciando simulação em modo daemon...");
+/// `daemon [--name nome] [--env-file arquivo] [--restart [--max-restarts N]] [-- comando args...]`
+/// (comando padrão: simulate)
+fn start_daemon(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let mut simulation = NamedSimulation::from_env()?;
+    let mut env_file: Option<String> = None;
+    let mut command = vec!["simulate".to_string()];
+    let mut restart = false;
+    let mut max_restarts: Option<String> = None;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--name" => simulation = NamedSimulation::new(iter.next().ok_or("--name requer um nome")?)?,
+            "--env-file" => env_file = Some(iter.next().ok_or("--env-file requer um arquivo")?.clone()),
+            "--restart" => restart = true,
+            "--max-restarts" => {
+                let value = iter.next().ok_or("--max-restarts requer um valor")?;
+                value.parse::<u32>()?;
+                max_restarts = Some(value.clone());
+                restart = true;
+            }
+            "--" => {
+                command = iter.by_ref().cloned().collect();
+                if command.is_empty() {
+                    return Err("-- requer um comando".into());
+                }
+            }
+            other => return Err(format!("Opção desconhecida: {}", other).into()),
+        }
+    }
+
+    println!("🚀 Iniciando simulação '{}' em modo daemon...", simulation.name());
     
     // Verificar se já existe um processo rodando
-    if is_simulation_running() {
-        println!("⚠️  Uma simulação já está rodando!");
-        println!("💡 Use 'cargo run logs' para acompanhar os logs");
-        println!("💡 Use 'cargo run status' para verificar o status");
+    if simulation.is_running() {
+        println!("⚠️  A simulação '{}' já está rodando!", simulation.name());
+        println!("💡 Use 'cargo run logs {}' para acompanhar os logs", simulation.name());
+        println!("💡 Use 'cargo run status {}' para verificar o status", simulation.name());
+        return Ok(());
+    }
+    if let Some(pid) = simulation.stale_pid() {
+        println!("🧹 PID obsoleto {} removido ({})", pid, simulation.pid_path().display());
+        fs::remove_file(simulation.pid_path()).ok();
+    } else if let Some(pid) = simulation.pid() {
+        // Processo vivo sem o lock: supervisor entre dois reinícios ou PID reaproveitado
+        println!("⚠️  O PID {} de '{}' ainda está ativo", pid, simulation.name());
+        println!("💡 Se não for a simulação, remova {} e tente de novo", simulation.pid_path().display());
         return Ok(());
     }
     
     let exe_path = env::current_exe()?;
     let current_dir = env::current_dir()?;
     
-    let child = Command::new(&exe_path)
-        .arg("simulate")
+    // Com --restart o processo iniciado é o supervisor, que roda o comando e o reinicia se cair
+    let mut process_args = Vec::new();
+    if restart {
+        process_args.push("supervise".to_string());
+        if let Some(max) = &max_restarts {
+            process_args.extend(["--max-restarts".to_string(), max.clone()]);
+        }
+        process_args.push("--".to_string());
+    }
+    process_args.extend(command.iter().cloned());
+    
+    let mut child = Command::new(&exe_path);
+    child
+        .args(&process_args)
         .current_dir(&current_dir)
+        .env("STATE_RUN_ID", simulation.name())
         .stdin(Stdio::null())
         .stdout(Stdio::null())
-        .stderr(Stdio::null())
-        .spawn()?;
+        .stderr(Stdio::null());
+    // Variáveis próprias desta simulação (ex.: outra fonte ao vivo ou outro funding)
+    if let Some(path) = &env_file {
+        child.envs(simulations::read_env_file(path)?);
+    }
+    let mut child = child.spawn()?;
     
     // Salvar PID do processo
     let pid = child.id();
-    fs::write("simulation.pid", pid.to_string())?;
+    fs::write(simulation.pid_path(), pid.to_string())?;
+    
+    // Falhas imediatas (ex.: lock já tomado por um `simulate` no terminal) aparecem aqui
+    thread::sleep(Duration::from_millis(500));
+    if let Some(status) = child.try_wait()? {
+        if !status.success() {
+            fs::remove_file(simulation.pid_path()).ok();
+            return Err(format!(
+                "a simulação '{}' encerrou ao iniciar ({}); veja {}",
+                simulation.name(), status, simulation.today_log().display()
+            ).into());
+        }
+    }
     
-    println!("✅ Simulação iniciada em background (PID: {})", pid);
-    println!("📂 Arquivo de estado salvo a cada 30 segundos");
-    println!("📊 Use 'cargo run logs' para acompanhar em tempo real");
-    println!("🛑 Use 'cargo run stop' para parar a simulação");
+    println!("✅ Simulação '{}' iniciada em background (PID: {})", simulation.name(), pid);
+    println!("⚙️  Comando: {}", command.join(" "));
+    if restart {
+        println!("🔁 Reinício automático em caso de queda (supervisor PID {})", pid);
+    }
+    println!("📂 Estado salvo a cada 30 segundos em {}", simulation.state_store()?.describe());
+    println!("📊 Use 'cargo run logs {}' para acompanhar em tempo real", simulation.name());
+    println!("🛑 Use 'cargo run stop {}' para parar a simulação", simulation.name());
     
     Ok(())
 }
 
-fn stop_daemon() -> Result<(), Box<dyn std::error::Error>> {
-    if let Ok(pid_str) = fs::read_to_string("simulation.pid") {
-        if let Ok(pid) = pid_str.trim().parse::<u32>() {
-            println!("🛑 Parando simulação (PID: {})...", pid);
-            
-            #[cfg(unix)]
-            {
-                Command::new("kill")
-                    .arg(pid.to_string())
-                    .output()?;
-            }
-            
-            #[cfg(windows)]
-            {
-                Command::new("taskkill")
-                    .args(["/PID", &pid.to_string(), "/F"])
-                    .output()?;
-            }
-            
-            fs::remove_file("simulation.pid").ok();
-            println!("✅ Simulação parada");
-            println!("💾 Estado foi salvo automaticamente");
-        } else {
-            println!("❌ PID inválido no arquivo");
+/// `stop [nome|--all]`: envia SIGTERM e espera a simulação salvar o estado e sair
+fn stop_daemon(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let simulations = select_simulations(args)?;
+    let mut stopping = Vec::new();
+
+    for simulation in simulations {
+        if let Some(pid) = simulation.stale_pid() {
+            println!("🧹 PID obsoleto {} de '{}' removido", pid, simulation.name());
+            fs::remove_file(simulation.pid_path()).ok();
         }
-    } else {
+        let Some(pid) = simulation.running_pid() else {
+            continue;
+        };
+        println!("🛑 Parando simulação '{}' (PID: {})...", simulation.name(), pid);
+        simulations::terminate(pid)?;
+        stopping.push((simulation, pid));
+    }
+
+    if stopping.is_empty() {
         println!("❌ Nenhuma simulação em execução encontrada");
+        return Ok(());
+    }
+
+    // Aguardar o salvamento do estado e do relatório final
+    let started = Instant::now();
+    while started.elapsed() < STOP_TIMEOUT
+        && stopping.iter().any(|(simulation, pid)| simulations::process_alive(*pid) || simulation.is_running())
+    {
+        thread::sleep(Duration::from_millis(200));
+    }
+
+    for (simulation, pid) in stopping {
+        if simulations::process_alive(pid) || simulation.is_running() {
+            println!("⚠️  '{}' (PID {}) não encerrou em {:?}", simulation.name(), pid, STOP_TIMEOUT);
+            println!("💡 Verifique com 'cargo run logs {}' ou force com 'kill -9 {}'", simulation.name(), pid);
+        } else {
+            fs::remove_file(simulation.pid_path()).ok();
+            println!("✅ Simulação '{}' parada - estado e relatório final salvos", simulation.name());
+        }
     }
     
     Ok(())
 }
 
-fn show_status() -> Result<(), Box<dyn std::error::Error>> {
-    println!("📊 Status da Simulação");
+/// `status` lista todas as simulações; `status <nome>` mostra os detalhes de uma
+fn show_status(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    match args.first() {
+        Some(name) => show_simulation_status(&NamedSimulation::new(name)?),
+        None => list_simulations(),
+    }
+}
+
+fn list_simulations() -> Result<(), Box<dyn std::error::Error>> {
+    let simulations = NamedSimulation::all()?;
+
+    println!("📊 Simulações");
+    println!("{}", "=".repeat(80));
+    if simulations.is_empty() {
+        println!("Nenhuma simulação encontrada");
+    } else {
+        println!("{:<20} {:<10} {:>8} {:<18} {:>12}", "Nome", "Status", "PID", "Data simulada", "Fiat");
+        println!("{}", "-".repeat(80));
+        for simulation in simulations {
+            let running_pid = simulation.running_pid();
+            let status = match (running_pid, simulation.stale_pid()) {
+                (Some(_), _) => "🟢 RODANDO",
+                (None, Some(_)) => "🟠 CAIU",
+                (None, None) => "🔴 PARADO",
+            };
+            let pid = running_pid.map(|pid| pid.to_string()).unwrap_or_else(|| "-".to_string());
+            let state = simulation.state_store()?.load().ok().flatten();
+            println!(
+                "{:<20} {:<10} {:>8} {:<18} {:>12}",
+                simulation.name(),
+                status,
+                pid,
+                state.as_ref().map(|s| s.current_time.format("%Y-%m-%d %H:%M").to_string()).unwrap_or_else(|| "-".to_string()),
+                state.as_ref().map(|s| format!("{:.2}", s.saldo_fiat)).unwrap_or_else(|| "-".to_string()),
+            );
+        }
+    }
+
+    println!("\n💡 Comandos disponíveis:");
+    println!("  cargo run status <nome>         - Detalhes de uma simulação");
+    println!("  cargo run daemon --name <nome>  - Iniciar em background");
+    println!("  cargo run logs <nome|--all>     - Acompanhar logs");
+    println!("  cargo run logs query <nome>     - Filtrar logs de todos os dias");
+    println!("  cargo run stop <nome|--all>     - Parar daemon");
+
+    Ok(())
+}
+
+fn show_simulation_status(simulation: &NamedSimulation) -> Result<(), Box<dyn std::error::Error>> {
+    println!("📊 Status da Simulação '{}'", simulation.name());
     println!("{}", "=".repeat(50));
     
     // Verificar se existe processo rodando
-    if is_simulation_running() {
-        if let Ok(pid_str) = fs::read_to_string("simulation.pid") {
-            if let Ok(pid) = pid_str.trim().parse::<u32>() {
-                println!("🟢 Status: RODANDO (PID: {})", pid);
-            }
-        } else {
-            println!("🟢 Status: RODANDO");
-        }
-    } else {
-        println!("🔴 Status: PARADO");
+    match (simulation.running_pid(), simulation.stale_pid()) {
+        (Some(pid), _) => println!("🟢 Status: RODANDO (PID: {})", pid),
+        (None, Some(pid)) => println!("🟠 Status: PARADO - o processo {} terminou sem 'stop' (PID obsoleto)", pid),
+        (None, None) => println!("🔴 Status: PARADO"),
     }
     
-    // Verificar se existe arquivo de estado
-    if Path::new("simulation_state.json").exists() {
-        if let Ok(state_data) = fs::read_to_string("simulation_state.json") {
-            if let Ok(state) = serde_json::from_str::<serde_json::Value>(&state_data) {
-                if let (Some(current_time), Some(data_index), Some(saldo_fiat), Some(saldo_btc)) = (
-                    state["current_time"].as_str(),
-                    state["data_index"].as_u64(),
-                    state["saldo_fiat"].as_f64(),
-                    state["saldo_btc"].as_f64(),
-                ) {
-                    println!("💾 Estado salvo: SIM");
-                    println!("📅 Última data: {}", current_time.get(..19).unwrap_or("--"));
-                    println!("📊 Índice atual: {}", data_index);
-                    println!("💰 Saldo Fiat: ${:.2}", saldo_fiat);
-                    println!("₿  Saldo BTC: {:.6} BTC", saldo_btc);
-                } else {
-                    println!("💾 Estado salvo: SIM (formato inválido)");
-                }
-            }
+    // Verificar se existe estado salvo
+    let store = simulation.state_store()?;
+    match store.load() {
+        Ok(Some(state)) => {
+            println!("💾 Estado salvo: SIM ({})", store.describe());
+            println!("📅 Última data: {}", state.current_time.format("%Y-%m-%d %H:%M:%S"));
+            println!("📊 Índice atual: {}", state.data_index);
+            println!("💰 Saldo Fiat: ${:.2}", state.saldo_fiat);
+            println!("₿  Saldo BTC: {:.6} BTC", state.saldo_btc);
         }
-    } else {
-        println!("💾 Estado salvo: NÃO");
+        Ok(None) => println!("💾 Estado salvo: NÃO"),
+        Err(e) => println!("💾 Estado salvo: SIM ({})", e),
     }
     
     // Verificar logs
-    let today = chrono::Utc::now().format("%Y-%m-%d");
-    let log_file_path = format!("logs/btc_trading.log.{}", today);
-    if Path::new(&log_file_path).exists() {
+    let log_file_path = simulation.today_log();
+    if log_file_path.exists() {
         if let Ok(metadata) = fs::metadata(&log_file_path) {
-            println!("📄 Log de hoje: {} ({} bytes)", log_file_path, metadata.len());
+            println!("📄 Log de hoje: {} ({} bytes)", log_file_path.display(), metadata.len());
         }
     } else {
         println!("📄 Log de hoje: Não encontrado");
     }
     
     println!("\n💡 Comandos disponíveis:");
-    println!("  cargo run daemon  - Iniciar em background");
-    println!("  cargo run logs    - Acompanhar logs");
-    println!("  cargo run stop    - Parar daemon");
+    println!("  cargo run daemon --name {}  - Iniciar em background", simulation.name());
+    println!("  cargo run logs {}    - Acompanhar logs", simulation.name());
+    println!("  cargo run stop {}    - Parar daemon", simulation.name());
     
     Ok(())
 }
 
-fn is_simulation_running() -> bool {
-    if let Ok(pid_str) = fs::read_to_string("simulation.pid") {
-        if let Ok(pid) = pid_str.trim().parse::<u32>() {
-            #[cfg(unix)]
-            {
-                return Command::new("kill")
-                    .args(["-0", &pid.to_string()])
-                    .output()
-                    .map(|output| output.status.success())
-                    .unwrap_or(false);
-            }
-            
-            #[cfg(windows)]
-            {
-                return Command::new("tasklist")
-                    .args(["/FI", &format!("PID eq {}", pid)])
-                    .output()
-                    .map(|output| {
-                        String::from_utf8_lossy(&output.stdout).contains(&pid.to_string())
-                    })
-                    .unwrap_or(false);
-            }
-        }
-    }
-    false
-}
-
 fn main() {
     dotenv::dotenv().ok();
-    init_logging();
+    let _log_guard = init_logging();
     
     let args: Vec<String> = env::args().collect();
 
@@ -334,27 +474,39 @@ fn main() {
                 return;
             }
             "clear" => {
-                // Limpar apenas o arquivo de estado
-                info!("🗑️  Limpando arquivo de estado...");
-                if let Err(e) = TradeSimulator::clear_state_file() {
+                // Limpar apenas o estado salvo
+                info!("🗑️  Limpando estado salvo...");
+                if let Err(e) = StateStore::from_env().and_then(|store| TradeSimulator::clear_saved_state(&store)) {
                     error!("❌ Erro ao limpar estado: {}", e);
                     std::process::exit(1);
                 } else {
-                    println!("✅ Arquivo de estado limpo com sucesso!");
+                    println!("✅ Estado salvo limpo com sucesso!");
                 }
                 return;
             }
             "daemon" => {
                 // Iniciar simulação em background
-                if let Err(e) = start_daemon() {
+                if let Err(e) = start_daemon(&args[2..]) {
                     error!("❌ Erro ao iniciar daemon: {}", e);
+                    println!("❌ Erro ao iniciar daemon: {}", e);
+                    println!("💡 Uso: cargo run daemon [--name nome] [--env-file arquivo] [--restart [--max-restarts N]] [-- comando args...]");
+                    std::process::exit(1);
+                }
+                return;
+            }
+            "logs" if args.get(2).map(String::as_str) == Some("query") => {
+                // Filtrar os logs de todos os dias (nível, período, módulo, texto, campos)
+                if let Err(e) = log_query::run_logs_query(&args[3..]) {
+                    error!("❌ Erro ao consultar logs: {}", e);
+                    println!("❌ Erro ao consultar logs: {}", e);
+                    println!("💡 Uso: cargo run logs query [nome|--all] [--level nível] [--since horário] [--until horário] [--target módulo] [--grep texto] [--field campo=valor] [--limit N] [--json|--summary] [--follow]");
                     std::process::exit(1);
                 }
                 return;
             }
             "logs" => {
                 // Acompanhar logs em tempo real
-                if let Err(e) = follow_logs() {
+                if let Err(e) = follow_logs(&args[2..]) {
                     error!("❌ Erro ao acompanhar logs: {}", e);
                     std::process::exit(1);
                 }
@@ -362,15 +514,147 @@ fn main() {
             }
             "stop" => {
                 // Parar daemon
-                if let Err(e) = stop_daemon() {
+                if let Err(e) = stop_daemon(&args[2..]) {
                     error!("❌ Erro ao parar daemon: {}", e);
                     std::process::exit(1);
                 }
                 return;
             }
+            "compare" => {
+                // Comparar execuções salvas lado a lado
+                if let Err(e) = compare::compare_runs(&args[2..]) {
+                    error!("❌ Erro ao comparar execuções: {}", e);
+                    println!("❌ Erro ao comparar execuções: {}", e);
+                    println!("💡 Uso: cargo run compare <run-id|dir|estado.json> <run-id|dir|estado.json> [...] [--output relatorio.html]");
+                    std::process::exit(1);
+                }
+                return;
+            }
+            "report" => {
+                // Gerar relatório HTML de uma execução salva
+                if let Err(e) = report::generate_report(&args[2..]) {
+                    error!("❌ Erro ao gerar relatório: {}", e);
+                    println!("❌ Erro ao gerar relatório: {}", e);
+                    println!("💡 Uso: cargo run report <run-id|dir|estado.json> [--output relatorio.html]");
+                    std::process::exit(1);
+                }
+                return;
+            }
+            "import" => {
+                // Importar CSV para o Redis em blocos (retomável)
+                if let Err(e) = importer::run_import_command(&args[2..]) {
+                    error!("❌ Erro ao importar dados: {}", e);
+                    println!("❌ Erro ao importar dados: {}", e);
+                    println!("💡 Uso: cargo run import [arquivo.csv] [--chunk registros] [--restart]");
+                    std::process::exit(1);
+                }
+                return;
+            }
+            "cache" => {
+                // Cache binário colunar de candles (mmap)
+                if let Err(e) = candle_cache::run_cache_command(&args[2..]) {
+                    error!("❌ Erro no cache de candles: {}", e);
+                    println!("❌ Erro no cache de candles: {}", e);
+                    println!("💡 Uso: cargo run cache build [entrada|--redis] [--output arquivo] | cargo run cache info [arquivo] [--at timestamp]");
+                    std::process::exit(1);
+                }
+                return;
+            }
+            "live" => {
+                // Paper trading com candles recebidos em tempo real
+                if let Err(e) = live::run_live_command(&args[2..]) {
+                    error!("❌ Erro no paper trading: {}", e);
+                    println!("❌ Erro no paper trading: {}", e);
+                    println!("💡 Uso: cargo run live [--source pubsub:<canal>|stream:<chave>|pipe:<caminho>|ws://host:porta] [--fresh] [--idle-timeout segundos]");
+                    std::process::exit(1);
+                }
+                return;
+            }
+            "replay" => {
+                // Reproduzir o histórico num Redis Stream como se fosse mercado ao vivo
+                if let Err(e) = replay::run_replay_command(&args[2..]) {
+                    error!("❌ Erro no replay: {}", e);
+                    println!("❌ Erro no replay: {}", e);
+                    println!("💡 Uso: cargo run replay [--redis|--input arquivo.csv|--cache arquivo] [--stream chave] [--speed 60|max] [--from timestamp] [--limit N] [--maxlen N]");
+                    std::process::exit(1);
+                }
+                return;
+            }
+            "supervise" => {
+                // Interno: usado por 'daemon --restart' para reiniciar a simulação se ela cair
+                if let Err(e) = supervisor::run_supervisor(&args[2..]) {
+                    error!("❌ Erro no supervisor: {}", e);
+                    std::process::exit(1);
+                }
+                return;
+            }
+            "restore" => {
+                // Voltar o estado salvo para um snapshot anterior
+                if NamedSimulation::from_env().is_ok_and(|simulation| simulation.is_running()) {
+                    error!("❌ Simulação rodando; pare-a antes de restaurar um snapshot");
+                    println!("❌ Simulação rodando; pare-a antes de restaurar um snapshot");
+                    std::process::exit(1);
+                }
+                if let Err(e) = state_store::run_restore_command(&args[2..]) {
+                    error!("❌ Erro ao restaurar estado: {}", e);
+                    println!("❌ Erro ao restaurar estado: {}", e);
+                    println!("💡 Uso: cargo run restore [--list] [--snapshot <Nº ou id>]");
+                    std::process::exit(1);
+                }
+                return;
+            }
+            "control" => {
+                // Pausar, avançar, ajustar ou consultar uma simulação rodando
+                if let Err(e) = control::run_control_command(&args[2..]) {
+                    error!("❌ Erro no controle da simulação: {}", e);
+                    println!("❌ Erro no controle da simulação: {}", e);
+                    println!("💡 Uso: cargo run control [--name nome] pause|resume|step [N]|speed <normal|max|candles/s>|snapshot|set <campo> <valor>|status");
+                    std::process::exit(1);
+                }
+                return;
+            }
+            "tui" => {
+                // Painel em tela cheia de uma simulação rodando
+                if let Err(e) = tui::run_tui_command(&args[2..]) {
+                    error!("❌ Erro no painel da simulação: {}", e);
+                    println!("❌ Erro no painel da simulação: {}", e);
+                    println!("💡 Uso: cargo run tui [nome]");
+                    std::process::exit(1);
+                }
+                return;
+            }
+            "states" => {
+                // Listar simulações com estado salvo (arquivo ou Redis)
+                if let Err(e) = state_store::run_states_command() {
+                    error!("❌ Erro ao listar estados: {}", e);
+                    println!("❌ Erro ao listar estados: {}", e);
+                    std::process::exit(1);
+                }
+                return;
+            }
+            "ticks" => {
+                // Trades da exchange: agregar em candles ou simular tick a tick
+                if let Err(e) = ticks::run_ticks_command(&args[2..]) {
+                    error!("❌ Erro ao processar trades: {}", e);
+                    println!("❌ Erro ao processar trades: {}", e);
+                    println!("💡 Uso: cargo run ticks aggregate <trades.csv|diretório|glob> [--interval 1m] [--output candles.csv] [--redis] | cargo run ticks simulate <trades> [--fresh]");
+                    std::process::exit(1);
+                }
+                return;
+            }
+            "data" => {
+                // Verificar qualidade do dataset (gaps, duplicados, outliers)
+                if let Err(e) = data_quality::run_data_command(&args[2..]) {
+                    error!("❌ Erro ao verificar dados: {}", e);
+                    println!("❌ Erro ao verificar dados: {}", e);
+                    println!("💡 Uso: cargo run data check [arquivo.csv|--redis] [--interval segundos] [--spike percentual] [--fill skip|forward|interpolate --output corrigido.csv]");
+                    std::process::exit(1);
+                }
+                return;
+            }
             "status" => {
                 // Mostrar status da simulação
-                if let Err(e) = show_status() {
+                if let Err(e) = show_status(&args[2..]) {
                     error!("❌ Erro ao mostrar status: {}", e);
                     std::process::exit(1);
                 }
@@ -381,19 +665,45 @@ fn main() {
                 error!("Comandos disponíveis:");
                 error!("  cargo run simulate  - Continuar simulação do estado salvo (ou iniciar nova)");
                 error!("  cargo run fresh     - Iniciar simulação nova (limpa estado anterior)");
-                error!("  cargo run daemon    - Iniciar simulação em background");
-                error!("  cargo run logs      - Acompanhar logs em tempo real");
-                error!("  cargo run stop      - Parar simulação em background");
-                error!("  cargo run status    - Verificar status da simulação");
+                error!("  cargo run daemon    - Iniciar simulação em background (--name para várias)");
+                error!("  cargo run logs      - Acompanhar logs em tempo real (nome ou --all)");
+                error!("  cargo run logs query - Filtrar logs de todos os dias por nível, período, módulo, texto ou campo");
+                error!("  cargo run stop      - Parar simulação em background (nome ou --all)");
+                error!("  cargo run status    - Listar simulações ou ver uma pelo nome");
                 error!("  cargo run clear     - Limpar apenas o arquivo de estado");
+                error!("  cargo run compare   - Comparar execuções salvas (runs/<run-id>)");
+                error!("  cargo run report    - Gerar relatório HTML de uma execução");
+                error!("  cargo run data      - Verificar qualidade dos dados (data check)");
+                error!("  cargo run import    - Importar CSV para o Redis em blocos (retomável)");
+                error!("  cargo run cache     - Criar/inspecionar cache binário de candles");
+                error!("  cargo run ticks     - Agregar trades em candles ou simular tick a tick");
+                error!("  cargo run live      - Paper trading com candles em tempo real");
+                error!("  cargo run replay    - Publicar o histórico num Redis Stream");
+                error!("  cargo run states    - Listar simulações com estado salvo");
+                error!("  cargo run restore   - Listar snapshots do estado ou restaurar um deles");
+                error!("  cargo run control   - Pausar, retomar, avançar, ajustar ou consultar a simulação rodando");
+                error!("  cargo run tui       - Painel em tela cheia da simulação rodando (gráfico, carteira, ordens)");
                 println!("❌ Comando não reconhecido. Use:");
                 println!("  cargo run simulate  - Continuar simulação do estado salvo (ou iniciar nova)");
                 println!("  cargo run fresh     - Iniciar simulação nova (limpa estado anterior)");
-                println!("  cargo run daemon    - Iniciar simulação em background");
-                println!("  cargo run logs      - Acompanhar logs em tempo real");
-                println!("  cargo run stop      - Parar simulação em background");
-                println!("  cargo run status    - Verificar status da simulação");
+                println!("  cargo run daemon    - Iniciar simulação em background (--name para várias)");
+                println!("  cargo run logs      - Acompanhar logs em tempo real (nome ou --all)");
+                println!("  cargo run logs query - Filtrar logs de todos os dias por nível, período, módulo, texto ou campo");
+                println!("  cargo run stop      - Parar simulação em background (nome ou --all)");
+                println!("  cargo run status    - Listar simulações ou ver uma pelo nome");
                 println!("  cargo run clear     - Limpar apenas o arquivo de estado");
+                println!("  cargo run compare   - Comparar execuções salvas (runs/<run-id>)");
+                println!("  cargo run report    - Gerar relatório HTML de uma execução");
+                println!("  cargo run data      - Verificar qualidade dos dados (data check)");
+                println!("  cargo run import    - Importar CSV para o Redis em blocos (retomável)");
+                println!("  cargo run cache     - Criar/inspecionar cache binário de candles");
+                println!("  cargo run ticks     - Agregar trades em candles ou simular tick a tick");
+                println!("  cargo run live      - Paper trading com candles em tempo real");
+                println!("  cargo run replay    - Publicar o histórico num Redis Stream");
+                println!("  cargo run states    - Listar simulações com estado salvo");
+                println!("  cargo run restore   - Listar snapshots do estado ou restaurar um deles");
+                println!("  cargo run control   - Pausar, retomar, avançar, ajustar ou consultar a simulação rodando");
+                println!("  cargo run tui       - Painel em tela cheia da simulação rodando (gráfico, carteira, ordens)");
                 std::process::exit(1);
             }
         }
@@ -416,40 +726,65 @@ fn main() {
         }
     };
 
-    match ReaderBtcFile::read_btc_csv_file(csv_path) {
-        Ok(data) => {
-            let duration = start_time.elapsed();
-            info!("✅ Dados CSV carregados: {} registros em {:.2?}", data.len(), duration);
-            println!("✅ Dados carregados com sucesso: {} registros", data.len());
-            println!("⏱️  Tempo de carregamento: {:.2?}", duration);
-
-            let start_time = Instant::now();
-            if let Err(e) = redis.set_all_btc(&data) {
-                error!("❌ Erro ao salvar dados no Redis: {}", e);
-                eprintln!("❌ Erro ao salvar no Redis: {}", e);
-                std::process::exit(1);
-            }
+    let csv_options = match CsvReadOptions::from_env() {
+        Ok(options) => options,
+        Err(e) => {
+            error!("❌ Configuração de CSV inválida: {}", e);
+            eprintln!("❌ Configuração de CSV inválida: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    match importer::import_csv(csv_path, &redis, &csv_options, &ImportOptions::from_env()) {
+        Ok(records) => {
             let duration = start_time.elapsed();
-            info!("✅ Dados salvos no Redis em {:.2?}", duration);
-            println!("⏱️  Tempo de salvamento no Redis: {:.2?}", duration);
+            info!("✅ Dados CSV importados: {} registros em {:.2?}", records, duration);
+            println!("✅ Dados carregados com sucesso: {} registros", records);
+            println!("⏱️  Tempo de importação: {:.2?}", duration);
 
             info!("💡 Sistema pronto para uso");
             info!("💡 Comandos disponíveis:");
             info!("  cargo run simulate  - Continuar simulação do estado salvo (ou iniciar nova)");
             info!("  cargo run fresh     - Iniciar simulação nova (limpa estado anterior)");
-            info!("  cargo run daemon    - Iniciar simulação em background");
-            info!("  cargo run logs      - Acompanhar logs em tempo real");
-            info!("  cargo run stop      - Parar simulação em background");
-            info!("  cargo run status    - Verificar status da simulação");
+            info!("  cargo run daemon    - Iniciar simulação em background (--name para várias)");
+            info!("  cargo run logs      - Acompanhar logs em tempo real (nome ou --all)");
+            info!("  cargo run logs query - Filtrar logs de todos os dias por nível, período, módulo, texto ou campo");
+            info!("  cargo run stop      - Parar simulação em background (nome ou --all)");
+            info!("  cargo run status    - Listar simulações ou ver uma pelo nome");
             info!("  cargo run clear     - Limpar apenas o arquivo de estado");
+            info!("  cargo run compare   - Comparar execuções salvas (runs/<run-id>)");
+            info!("  cargo run report    - Gerar relatório HTML de uma execução");
+            info!("  cargo run data      - Verificar qualidade dos dados (data check)");
+            info!("  cargo run import    - Importar CSV para o Redis em blocos (retomável)");
+            info!("  cargo run cache     - Criar/inspecionar cache binário de candles");
+            info!("  cargo run ticks     - Agregar trades em candles ou simular tick a tick");
+            info!("  cargo run live      - Paper trading com candles em tempo real");
+            info!("  cargo run replay    - Publicar o histórico num Redis Stream");
+            info!("  cargo run states    - Listar simulações com estado salvo");
+            info!("  cargo run restore   - Listar snapshots do estado ou restaurar um deles");
+            info!("  cargo run control   - Pausar, retomar, avançar, ajustar ou consultar a simulação rodando");
+            info!("  cargo run tui       - Painel em tela cheia da simulação rodando (gráfico, carteira, ordens)");
             println!("\n💡 Comandos disponíveis:");
             println!("  cargo run simulate  - Continuar simulação do estado salvo (ou iniciar nova)");
             println!("  cargo run fresh     - Iniciar simulação nova (limpa estado anterior)");
-            println!("  cargo run daemon    - Iniciar simulação em background");
-            println!("  cargo run logs      - Acompanhar logs em tempo real");
-            println!("  cargo run stop      - Parar simulação em background");
-            println!("  cargo run status    - Verificar status da simulação");
+            println!("  cargo run daemon    - Iniciar simulação em background (--name para várias)");
+            println!("  cargo run logs      - Acompanhar logs em tempo real (nome ou --all)");
+            println!("  cargo run logs query - Filtrar logs de todos os dias por nível, período, módulo, texto ou campo");
+            println!("  cargo run stop      - Parar simulação em background (nome ou --all)");
+            println!("  cargo run status    - Listar simulações ou ver uma pelo nome");
             println!("  cargo run clear     - Limpar apenas o arquivo de estado");
+            println!("  cargo run compare   - Comparar execuções salvas (runs/<run-id>)");
+            println!("  cargo run report    - Gerar relatório HTML de uma execução");
+            println!("  cargo run data      - Verificar qualidade dos dados (data check)");
+            println!("  cargo run import    - Importar CSV para o Redis em blocos (retomável)");
+            println!("  cargo run cache     - Criar/inspecionar cache binário de candles");
+            println!("  cargo run ticks     - Agregar trades em candles ou simular tick a tick");
+            println!("  cargo run live      - Paper trading com candles em tempo real");
+            println!("  cargo run replay    - Publicar o histórico num Redis Stream");
+            println!("  cargo run states    - Listar simulações com estado salvo");
+            println!("  cargo run restore   - Listar snapshots do estado ou restaurar um deles");
+            println!("  cargo run control   - Pausar, retomar, avançar, ajustar ou consultar a simulação rodando");
+            println!("  cargo run tui       - Painel em tela cheia da simulação rodando (gráfico, carteira, ordens)");
         }
         Err(err) => {
             error!("❌ Erro ao carregar dados CSV: {}", err);
@@ -0,0 +1,277 @@
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::control::{LiveStatus, RunState};
+
+/// Limites (em segundos) dos buckets do histograma de latência do Redis
+const REDIS_BUCKETS: [f64; 8] = [0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.1, 1.0];
+
+/// Contadores do Redis do processo, alimentados pelo `RedisClient`
+static REDIS_COMMANDS: AtomicU64 = AtomicU64::new(0);
+static REDIS_ERRORS: AtomicU64 = AtomicU64::new(0);
+static REDIS_LATENCY_MICROS: AtomicU64 = AtomicU64::new(0);
+static REDIS_BUCKET_COUNTS: [AtomicU64; REDIS_BUCKETS.len()] =
+    [const { AtomicU64::new(0) }; REDIS_BUCKETS.len()];
+
+/// Registra um comando enviado ao Redis (latência inclui abrir a conexão)
+pub fn record_redis(elapsed: Duration, ok: bool) {
+    REDIS_COMMANDS.fetch_add(1, Ordering::Relaxed);
+    if !ok {
+        REDIS_ERRORS.fetch_add(1, Ordering::Relaxed);
+    }
+    REDIS_LATENCY_MICROS.fetch_add(elapsed.as_micros() as u64, Ordering::Relaxed);
+    let secs = elapsed.as_secs_f64();
+    if let Some(bucket) = REDIS_BUCKETS.iter().position(|limit| secs <= *limit) {
+        REDIS_BUCKET_COUNTS[bucket].fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Ritmo de processamento entre duas leituras do status (coletas do `/metrics`, TUI)
+///
+/// Na primeira coleta (ou depois de um reinício do contador) usa a média da execução.
+#[derive(Default)]
+pub struct TickRate {
+    last: Option<(Instant, usize)>,
+}
+
+impl TickRate {
+    pub fn update(&mut self, status: &LiveStatus) -> f64 {
+        let now = Instant::now();
+        let rate = match self.last {
+            Some((at, processed)) if status.processed >= processed && now > at => {
+                (status.processed - processed) as f64 / (now - at).as_secs_f64()
+            }
+            _ => status.processed_per_sec,
+        };
+        self.last = Some((now, status.processed));
+        rate
+    }
+}
+
+/// Métricas da simulação no formato texto do Prometheus
+pub fn render(status: &LiveStatus, rate: &mut TickRate) -> String {
+    let mut out = String::new();
+    let labels = format!("simulation=\"{}\"", status.simulation);
+    let portfolio = &status.portfolio;
+
+    let mut gauge = |name: &str, help: &str, value: f64| {
+        let _ = writeln!(out, "# HELP btc_sim_{} {}", name, help);
+        let _ = writeln!(out, "# TYPE btc_sim_{} gauge", name);
+        let _ = writeln!(out, "btc_sim_{}{{{}}} {}", name, labels, value);
+    };
+    gauge(
+        "portfolio_value_usd",
+        "Patrimônio (fiat + BTC ao último preço)",
+        portfolio.equity,
+    );
+    gauge("fiat_balance_usd", "Saldo em fiat", portfolio.fiat_balance);
+    gauge("btc_balance", "Saldo em BTC", portfolio.btc_balance);
+    gauge(
+        "btc_price_usd",
+        "Último preço processado",
+        portfolio.last_price.unwrap_or(0.0),
+    );
+    gauge(
+        "open_orders",
+        "Ordens de compra abertas",
+        portfolio.open_orders as f64,
+    );
+    gauge(
+        "invested_usd",
+        "Total investido nas ordens abertas",
+        portfolio.total_invested,
+    );
+    gauge(
+        "realized_pnl_usd",
+        "P&L realizado (lucros menos perdas das vendas)",
+        status.realized_pnl,
+    );
+    gauge(
+        "unrealized_pnl_usd",
+        "P&L não realizado das ordens abertas",
+        portfolio.unrealized_pnl,
+    );
+    gauge(
+        "drawdown_percent",
+        "Drawdown atual",
+        status.current_drawdown,
+    );
+    gauge(
+        "max_drawdown_percent",
+        "Maior drawdown da simulação",
+        status.max_drawdown,
+    );
+    gauge(
+        "ticks_per_second",
+        "Dados processados por segundo desde a última coleta",
+        rate.update(status),
+    );
+    gauge(
+        "progress_percent",
+        "Progresso no período",
+        status.progress_pct,
+    );
+    gauge(
+        "sim_time_seconds",
+        "Horário simulado (epoch)",
+        portfolio.sim_time.timestamp() as f64,
+    );
+    gauge(
+        "paused",
+        "1 quando pausada pelo controle",
+        if status.state == RunState::Running {
+            0.0
+        } else {
+            1.0
+        },
+    );
+
+    let mut counter = |name: &str, help: &str, value: u64| {
+        let _ = writeln!(out, "# HELP btc_sim_{} {}", name, help);
+        let _ = writeln!(out, "# TYPE btc_sim_{} counter", name);
+        let _ = writeln!(out, "btc_sim_{}{{{}}} {}", name, labels, value);
+    };
+    counter(
+        "ticks_processed_total",
+        "Dados processados nesta execução",
+        status.processed as u64,
+    );
+    counter(
+        "trades_total",
+        "Vendas realizadas",
+        status.total_trades as u64,
+    );
+    counter(
+        "candles_missing_total",
+        "Índices sem candle no Redis",
+        status.candles_missing,
+    );
+    counter(
+        "candles_filled_total",
+        "Candles sintéticos gerados para gaps",
+        status.candles_filled,
+    );
+    counter(
+        "redis_commands_total",
+        "Comandos enviados ao Redis",
+        REDIS_COMMANDS.load(Ordering::Relaxed),
+    );
+    counter(
+        "redis_errors_total",
+        "Comandos do Redis que falharam",
+        REDIS_ERRORS.load(Ordering::Relaxed),
+    );
+
+    let _ = writeln!(
+        out,
+        "# HELP btc_sim_redis_latency_seconds Latência dos comandos do Redis"
+    );
+    let _ = writeln!(out, "# TYPE btc_sim_redis_latency_seconds histogram");
+    let mut cumulative = 0;
+    for (limit, count) in REDIS_BUCKETS.iter().zip(&REDIS_BUCKET_COUNTS) {
+        cumulative += count.load(Ordering::Relaxed);
+        let _ = writeln!(
+            out,
+            "btc_sim_redis_latency_seconds_bucket{{{},le=\"{}\"}} {}",
+            labels, limit, cumulative
+        );
+    }
+    let total = REDIS_COMMANDS.load(Ordering::Relaxed);
+    let _ = writeln!(
+        out,
+        "btc_sim_redis_latency_seconds_bucket{{{},le=\"+Inf\"}} {}",
+        labels, total
+    );
+    let _ = writeln!(
+        out,
+        "btc_sim_redis_latency_seconds_sum{{{}}} {}",
+        labels,
+        REDIS_LATENCY_MICROS.load(Ordering::Relaxed) as f64 / 1_000_000.0
+    );
+    let _ = writeln!(
+        out,
+        "btc_sim_redis_latency_seconds_count{{{}}} {}",
+        labels, total
+    );
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status(processed: usize, state: RunState) -> LiveStatus {
+        LiveStatus {
+            state,
+            ..LiveStatus::sample(processed)
+        }
+    }
+
+    fn value(text: &str, metric: &str) -> f64 {
+        text.lines()
+            .find(|line| line.starts_with(&format!("{}{{", metric)))
+            .and_then(|line| line.rsplit_once(' '))
+            .map(|(_, value)| value.parse().unwrap())
+            .unwrap_or_else(|| panic!("métrica {} ausente", metric))
+    }
+
+    #[test]
+    fn renders_labelled_gauges_and_counters() {
+        let text = render(&status(120, RunState::Running), &mut TickRate::default());
+        assert!(text.contains("# TYPE btc_sim_portfolio_value_usd gauge"));
+        assert!(text.contains("# TYPE btc_sim_trades_total counter"));
+        assert!(text.contains("btc_sim_portfolio_value_usd{simulation=\"papel\"} 996"));
+        assert_eq!(value(&text, "btc_sim_btc_price_usd"), 48_000.0);
+        assert_eq!(value(&text, "btc_sim_ticks_processed_total"), 120.0);
+        assert_eq!(value(&text, "btc_sim_candles_missing_total"), 7.0);
+        assert_eq!(value(&text, "btc_sim_paused"), 0.0);
+
+        let paused = render(&status(120, RunState::Paused), &mut TickRate::default());
+        assert_eq!(value(&paused, "btc_sim_paused"), 1.0);
+        let stepping = RunState::Stepping { remaining: 3 };
+        let stepping = render(&status(120, stepping), &mut TickRate::default());
+        assert_eq!(value(&stepping, "btc_sim_paused"), 1.0);
+    }
+
+    #[test]
+    fn every_sample_line_has_a_numeric_value() {
+        record_redis(Duration::from_millis(2), true);
+        let text = render(&status(1, RunState::Running), &mut TickRate::default());
+        for line in text.lines().filter(|line| !line.starts_with('#')) {
+            let (_, value) = line.rsplit_once(' ').unwrap();
+            assert!(value.parse::<f64>().is_ok(), "{}", line);
+        }
+    }
+
+    #[test]
+    fn latency_buckets_are_cumulative() {
+        record_redis(Duration::from_micros(100), true);
+        record_redis(Duration::from_secs(5), false);
+        let text = render(&status(1, RunState::Running), &mut TickRate::default());
+        let buckets: Vec<f64> = text
+            .lines()
+            .filter(|line| line.starts_with("btc_sim_redis_latency_seconds_bucket"))
+            .map(|line| line.rsplit_once(' ').unwrap().1.parse().unwrap())
+            .collect();
+        assert_eq!(buckets.len(), REDIS_BUCKETS.len() + 1);
+        assert!(buckets.windows(2).all(|pair| pair[0] <= pair[1]));
+        assert_eq!(
+            buckets.last().copied(),
+            Some(value(&text, "btc_sim_redis_latency_seconds_count"))
+        );
+        // A chamada de 5s fica só no +Inf
+        assert!(buckets[buckets.len() - 2] < buckets[buckets.len() - 1]);
+    }
+
+    #[test]
+    fn tick_rate_uses_the_run_average_until_it_has_a_previous_sample() {
+        let mut rate = TickRate::default();
+        assert_eq!(rate.update(&status(100, RunState::Running)), 42.0);
+        std::thread::sleep(Duration::from_millis(20));
+        let between = rate.update(&status(200, RunState::Running));
+        assert!(between > 0.0 && between <= 100.0 / 0.02, "{}", between);
+        // Contador reiniciado (nova execução): volta à média
+        assert_eq!(rate.update(&status(10, RunState::Running)), 42.0);
+    }
+}
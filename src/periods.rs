@@ -0,0 +1,204 @@
+use chrono::{DateTime, Datelike, Utc};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::trade_btc::{EquityPoint, Transaction};
+
+/// Granularidade da agregação por período
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PeriodKind {
+    Month,
+    Quarter,
+    Year,
+}
+
+impl PeriodKind {
+    pub const ALL: [PeriodKind; 3] = [PeriodKind::Month, PeriodKind::Quarter, PeriodKind::Year];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            PeriodKind::Month => "MONTH",
+            PeriodKind::Quarter => "QUARTER",
+            PeriodKind::Year => "YEAR",
+        }
+    }
+
+    /// Chave ordenável (ano, sub-período) do instante
+    fn key(&self, time: DateTime<Utc>) -> (i32, u32) {
+        match self {
+            PeriodKind::Month => (time.year(), time.month()),
+            PeriodKind::Quarter => (time.year(), (time.month() - 1) / 3 + 1),
+            PeriodKind::Year => (time.year(), 0),
+        }
+    }
+
+    fn label(&self, (year, sub): (i32, u32)) -> String {
+        match self {
+            PeriodKind::Month => format!("{}-{:02}", year, sub),
+            PeriodKind::Quarter => format!("{}-Q{}", year, sub),
+            PeriodKind::Year => year.to_string(),
+        }
+    }
+}
+
+/// Resultado consolidado de um mês, trimestre ou ano
+#[derive(Debug, Clone, Serialize)]
+pub struct PeriodBreakdown {
+    pub kind: &'static str,
+    pub period: String,
+    pub year: i32,
+    pub sub_period: u32,
+    pub start_value: f64,
+    pub end_value: f64,
+    pub realised_pnl: f64,
+    pub funding_pnl: f64,
+    pub unrealised_change: f64,
+    pub trade_count: u32,
+    pub return_percentage: f64,
+}
+
+/// Agrega histórico de transações e curva de patrimônio por período
+///
+/// A variação não realizada é o que sobra da variação do patrimônio depois de
+/// descontar o P&L realizado das vendas e o funding do período.
+pub fn aggregate(
+    kind: PeriodKind,
+    equity_curve: &[EquityPoint],
+    transactions: &[Transaction],
+    initial_balance: f64,
+) -> Vec<PeriodBreakdown> {
+    let mut end_values: BTreeMap<(i32, u32), f64> = BTreeMap::new();
+    for point in equity_curve {
+        end_values.insert(kind.key(point.time), point.total_value);
+    }
+
+    let mut realised: BTreeMap<(i32, u32), f64> = BTreeMap::new();
+    let mut funding: BTreeMap<(i32, u32), f64> = BTreeMap::new();
+    let mut trades: BTreeMap<(i32, u32), u32> = BTreeMap::new();
+    for tx in transactions {
+        let key = kind.key(tx.time);
+        match tx.transaction_type.as_str() {
+            "SELL" => {
+                *realised.entry(key).or_default() += tx.profit_loss.unwrap_or(0.0);
+                *trades.entry(key).or_default() += 1;
+            }
+            "BUY" => *trades.entry(key).or_default() += 1,
+            "FUNDING" => *funding.entry(key).or_default() += tx.amount,
+            _ => {}
+        }
+    }
+
+    let mut breakdown = Vec::with_capacity(end_values.len());
+    let mut previous = initial_balance;
+    for (key, end_value) in end_values {
+        let realised_pnl = realised.get(&key).copied().unwrap_or(0.0);
+        let funding_pnl = funding.get(&key).copied().unwrap_or(0.0);
+        let change = end_value - previous;
+        breakdown.push(PeriodBreakdown {
+            kind: kind.name(),
+            period: kind.label(key),
+            year: key.0,
+            sub_period: key.1,
+            start_value: previous,
+            end_value,
+            realised_pnl,
+            funding_pnl,
+            unrealised_change: change - realised_pnl - funding_pnl,
+            trade_count: trades.get(&key).copied().unwrap_or(0),
+            return_percentage: if previous > 0.0 {
+                change / previous * 100.0
+            } else {
+                0.0
+            },
+        });
+        previous = end_value;
+    }
+    breakdown
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time(value: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(value)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    fn point(at: &str, total_value: f64) -> EquityPoint {
+        EquityPoint {
+            time: time(at),
+            price: 0.0,
+            saldo_fiat: 0.0,
+            saldo_btc: 0.0,
+            total_value,
+            drawdown: 0.0,
+        }
+    }
+
+    fn transaction(at: &str, kind: &str, amount: f64, profit_loss: Option<f64>) -> Transaction {
+        Transaction {
+            id: 0,
+            transaction_type: kind.to_string(),
+            btc_quantity: 0.0,
+            price: 0.0,
+            time: time(at),
+            amount,
+            profit_loss,
+            buy_order_id: None,
+        }
+    }
+
+    #[test]
+    fn monthly_breakdown_splits_realised_funding_and_unrealised() {
+        let equity = [
+            point("2024-01-10T00:00:00Z", 1_050.0),
+            point("2024-01-31T23:00:00Z", 1_100.0),
+            point("2024-02-15T00:00:00Z", 990.0),
+        ];
+        let transactions = [
+            transaction("2024-01-05T00:00:00Z", "BUY", 500.0, None),
+            transaction("2024-01-20T00:00:00Z", "SELL", 530.0, Some(30.0)),
+            transaction("2024-01-24T00:00:00Z", "FUNDING", -2.0, None),
+            transaction("2024-02-03T00:00:00Z", "FUNDING", 1.0, None),
+        ];
+
+        let months = aggregate(PeriodKind::Month, &equity, &transactions, 1_000.0);
+        assert_eq!(months.len(), 2);
+
+        let january = &months[0];
+        assert_eq!(january.period, "2024-01");
+        assert_eq!((january.start_value, january.end_value), (1_000.0, 1_100.0));
+        assert_eq!(january.realised_pnl, 30.0);
+        assert_eq!(january.funding_pnl, -2.0);
+        assert_eq!(january.unrealised_change, 72.0);
+        assert_eq!(january.trade_count, 2);
+        assert_eq!(january.return_percentage, 10.0);
+
+        let february = &months[1];
+        assert_eq!(february.period, "2024-02");
+        assert_eq!(february.start_value, 1_100.0);
+        assert_eq!(february.unrealised_change, -111.0);
+        assert_eq!(february.trade_count, 0);
+        assert_eq!(february.return_percentage, -10.0);
+    }
+
+    #[test]
+    fn quarters_and_years_group_months() {
+        let equity = [
+            point("2023-12-31T00:00:00Z", 900.0),
+            point("2024-03-31T00:00:00Z", 1_200.0),
+            point("2024-04-01T00:00:00Z", 1_300.0),
+        ];
+
+        let quarters = aggregate(PeriodKind::Quarter, &equity, &[], 1_000.0);
+        let labels: Vec<&str> = quarters.iter().map(|p| p.period.as_str()).collect();
+        assert_eq!(labels, vec!["2023-Q4", "2024-Q1", "2024-Q2"]);
+
+        let years = aggregate(PeriodKind::Year, &equity, &[], 1_000.0);
+        assert_eq!(years.len(), 2);
+        assert_eq!(years[1].period, "2024");
+        assert_eq!((years[1].start_value, years[1].end_value), (900.0, 1_300.0));
+    }
+}
@@ -1,30 +1,1473 @@
-#[derive(Debug, serde::Deserialize, serde::Serialize)]
+use chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use flate2::read::MultiGzDecoder;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::cell::Cell;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashSet, VecDeque};
+use std::env;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::UNIX_EPOCH;
+use tracing::{error, info, warn};
+
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
 pub struct CsvBtcFile {
     pub open: f64,
     pub high: f64,
     pub low: f64,
     pub close: f64,
     pub volume: f64,
-    pub timestamp: String,
+    #[serde(deserialize_with = "deserialize_timestamp")]
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Aceita tanto o formato novo (RFC3339) quanto os formatos brutos já gravados no Redis
+fn deserialize_timestamp<'de, D>(deserializer: D) -> Result<DateTime<Utc>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum RawTimestamp {
+        Text(String),
+        Number(f64),
+    }
+
+    let raw = match RawTimestamp::deserialize(deserializer)? {
+        RawTimestamp::Text(text) => text,
+        RawTimestamp::Number(number) => number.to_string(),
+    };
+    parse_timestamp(&raw)
+        .ok_or_else(|| serde::de::Error::custom(format!("timestamp inválido: {}", raw)))
+}
+
+/// Converte timestamps em epoch (s, ms, µs ou ns) ou formatos ISO para UTC
+///
+/// A unidade do epoch é deduzida pelo número de dígitos da parte inteira: até 10
+/// segundos, 11 a 13 milissegundos, 14 a 16 microssegundos e 17 a 19 nanossegundos.
+/// Datas fora de 1970-2100 são rejeitadas, o que pega unidades trocadas.
+pub fn parse_timestamp(value: &str) -> Option<DateTime<Utc>> {
+    let value = value.trim();
+    if value.is_empty() {
+        return None;
+    }
+
+    parse_epoch(value)
+        .or_else(|| parse_datetime(value))
+        .filter(|dt| (MIN_TIMESTAMP_YEAR..=MAX_TIMESTAMP_YEAR).contains(&dt.year()))
+}
+
+/// Faixa de anos aceita por `parse_timestamp`
+const MIN_TIMESTAMP_YEAR: i32 = 1970;
+const MAX_TIMESTAMP_YEAR: i32 = 2100;
+
+fn parse_epoch(value: &str) -> Option<DateTime<Utc>> {
+    let integer = value.split('.').next()?;
+    let digits = integer.trim_start_matches(['-', '+']);
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let nanos_per_unit: i64 = match digits.len() {
+        1..=10 => 1_000_000_000,
+        11..=13 => 1_000_000,
+        14..=16 => 1_000,
+        17..=19 => 1,
+        _ => return None,
+    };
+
+    // Inteiros em aritmética exata; com fração, f64 (precisão de sobra para s e ms)
+    let nanos = match value.parse::<i64>() {
+        Ok(epoch) => epoch.checked_mul(nanos_per_unit)?,
+        Err(_) => {
+            let epoch = value.parse::<f64>().ok()?;
+            if !epoch.is_finite() {
+                return None;
+            }
+            (epoch * nanos_per_unit as f64).round() as i64
+        }
+    };
+    Some(Utc.timestamp_nanos(nanos))
+}
+
+fn parse_datetime(value: &str) -> Option<DateTime<Utc>> {
+    if let Ok(dt) = DateTime::parse_from_rfc3339(value) {
+        return Some(dt.with_timezone(&Utc));
+    }
+
+    for format in [
+        "%Y-%m-%d %H:%M:%S%.f",
+        "%Y-%m-%dT%H:%M:%S%.f",
+        "%Y-%m-%d %H:%M",
+        "%Y-%m-%dT%H:%M",
+        "%d/%m/%Y %H:%M:%S",
+        "%d/%m/%Y %H:%M",
+    ] {
+        if let Ok(naive) = NaiveDateTime::parse_from_str(value, format) {
+            return Some(Utc.from_utc_datetime(&naive));
+        }
+    }
+
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .map(|naive| Utc.from_utc_datetime(&naive))
+}
+
+/// Coluna do CSV: pelo nome no cabeçalho ou pela posição (base 0)
+#[derive(Debug, Clone)]
+pub enum ColumnRef {
+    Name(String),
+    Index(usize),
+}
+
+impl ColumnRef {
+    fn parse(value: &str) -> Self {
+        match value.trim().parse::<usize>() {
+            Ok(index) => ColumnRef::Index(index),
+            Err(_) => ColumnRef::Name(value.trim().to_string()),
+        }
+    }
+
+    fn resolve(&self, headers: Option<&csv::StringRecord>) -> Result<usize, String> {
+        match self {
+            ColumnRef::Index(index) => Ok(*index),
+            ColumnRef::Name(name) => headers
+                .and_then(|h| {
+                    h.iter()
+                        .position(|col| col.trim().eq_ignore_ascii_case(name))
+                })
+                .ok_or_else(|| format!("Coluna '{}' não encontrada no cabeçalho", name)),
+        }
+    }
+}
+
+/// Mapeamento das colunas OHLCV + timestamp
+#[derive(Debug, Clone)]
+pub struct ColumnMapping {
+    pub open: ColumnRef,
+    pub high: ColumnRef,
+    pub low: ColumnRef,
+    pub close: ColumnRef,
+    pub volume: ColumnRef,
+    pub timestamp: ColumnRef,
+}
+
+impl Default for ColumnMapping {
+    fn default() -> Self {
+        Self {
+            open: ColumnRef::Name("open".to_string()),
+            high: ColumnRef::Name("high".to_string()),
+            low: ColumnRef::Name("low".to_string()),
+            close: ColumnRef::Name("close".to_string()),
+            volume: ColumnRef::Name("volume".to_string()),
+            timestamp: ColumnRef::Name("timestamp".to_string()),
+        }
+    }
+}
+
+/// Opções de leitura do CSV de candles
+#[derive(Debug, Clone)]
+pub struct CsvReadOptions {
+    pub delimiter: u8,
+    pub has_headers: bool,
+    pub columns: ColumnMapping,
+}
+
+impl Default for CsvReadOptions {
+    fn default() -> Self {
+        Self {
+            delimiter: b',',
+            has_headers: true,
+            columns: ColumnMapping::default(),
+        }
+    }
+}
+
+impl CsvReadOptions {
+    /// Cria opções a partir de variáveis de ambiente
+    ///
+    /// - `CSV_DELIMITER`: caractere separador (`,`, `;`, `tab`...)
+    /// - `CSV_HAS_HEADERS`: `true`/`false`
+    /// - `CSV_COLUMNS`: mapeamento `campo=coluna`, ex.: `timestamp=time,volume=vol` ou `open=1`
+    pub fn from_env() -> Result<Self, Box<dyn std::error::Error>> {
+        let mut options = Self::default();
+
+        if let Ok(delimiter) = env::var("CSV_DELIMITER") {
+            options.delimiter = match delimiter.as_str() {
+                "tab" | "\\t" => b'\t',
+                d if d.len() == 1 => d.as_bytes()[0],
+                d => return Err(format!("CSV_DELIMITER inválido: {}", d).into()),
+            };
+        }
+
+        if let Ok(has_headers) = env::var("CSV_HAS_HEADERS") {
+            options.has_headers = has_headers.parse().unwrap_or(true);
+        }
+
+        if let Ok(columns) = env::var("CSV_COLUMNS") {
+            for entry in columns.split(',').filter(|e| !e.trim().is_empty()) {
+                let (field, column) = entry
+                    .split_once('=')
+                    .ok_or_else(|| format!("Entrada inválida em CSV_COLUMNS: {}", entry))?;
+                let column = ColumnRef::parse(column);
+                match field.trim() {
+                    "open" => options.columns.open = column,
+                    "high" => options.columns.high = column,
+                    "low" => options.columns.low = column,
+                    "close" => options.columns.close = column,
+                    "volume" => options.columns.volume = column,
+                    "timestamp" => options.columns.timestamp = column,
+                    other => {
+                        return Err(format!("Campo desconhecido em CSV_COLUMNS: {}", other).into())
+                    }
+                }
+            }
+        }
+
+        Ok(options)
+    }
+}
+
+/// Motivo de uma linha não ter sido importada
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum RowIssueKind {
+    /// Linha ilegível ou com campo não numérico / timestamp inválido
+    Invalid,
+    /// Valores OHLC inconsistentes (ex.: high < low)
+    Inconsistent,
+    /// Timestamp repetido; a primeira ocorrência é mantida
+    Duplicate,
+    /// Timestamp anterior ao último candle já mesclado (entrada com vários arquivos)
+    OutOfOrder,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RowIssue {
+    /// Arquivo de origem quando a entrada tem mais de um arquivo
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+    pub line: u64,
+    pub kind: RowIssueKind,
+    pub message: String,
+}
+
+/// Relatório de validação de uma importação de CSV
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ValidationReport {
+    pub total_rows: usize,
+    pub valid_rows: usize,
+    pub invalid_rows: usize,
+    pub inconsistent_rows: usize,
+    pub duplicate_rows: usize,
+    #[serde(default)]
+    pub out_of_order_rows: usize,
+    pub issues: Vec<RowIssue>,
+    /// Arquivos da entrada que não puderam ser abertos, com o erro
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub failed_files: Vec<String>,
+}
+
+impl ValidationReport {
+    const MAX_DISPLAYED_ISSUES: usize = 20;
+    // Limite de problemas detalhados em memória; os contadores continuam exatos
+    const MAX_STORED_ISSUES: usize = 10_000;
+
+    fn push(&mut self, file: Option<&str>, line: u64, kind: RowIssueKind, message: String) {
+        match kind {
+            RowIssueKind::Invalid => self.invalid_rows += 1,
+            RowIssueKind::Inconsistent => self.inconsistent_rows += 1,
+            RowIssueKind::Duplicate => self.duplicate_rows += 1,
+            RowIssueKind::OutOfOrder => self.out_of_order_rows += 1,
+        }
+        if self.issues.len() >= Self::MAX_STORED_ISSUES {
+            return;
+        }
+        self.issues.push(RowIssue {
+            file: file.map(str::to_string),
+            line,
+            kind,
+            message,
+        });
+    }
+
+    /// Soma o relatório de outro arquivo a este
+    fn absorb(&mut self, other: ValidationReport) {
+        self.total_rows += other.total_rows;
+        self.valid_rows += other.valid_rows;
+        self.invalid_rows += other.invalid_rows;
+        self.inconsistent_rows += other.inconsistent_rows;
+        self.duplicate_rows += other.duplicate_rows;
+        self.out_of_order_rows += other.out_of_order_rows;
+        let room = Self::MAX_STORED_ISSUES.saturating_sub(self.issues.len());
+        self.issues.extend(other.issues.into_iter().take(room));
+        self.failed_files.extend(other.failed_files);
+    }
+
+    pub fn skipped_rows(&self) -> usize {
+        self.invalid_rows + self.inconsistent_rows + self.duplicate_rows + self.out_of_order_rows
+    }
+
+    pub fn display(&self) {
+        info!(
+            "🧪 Validação CSV: {} linhas | {} válidas | {} inválidas | {} inconsistentes | {} duplicadas | {} fora de ordem",
+            self.total_rows, self.valid_rows, self.invalid_rows, self.inconsistent_rows, self.duplicate_rows, self.out_of_order_rows
+        );
+        println!(
+            "🧪 Validação CSV: {} linhas | {} válidas | {} inválidas | {} inconsistentes | {} duplicadas | {} fora de ordem",
+            self.total_rows, self.valid_rows, self.invalid_rows, self.inconsistent_rows, self.duplicate_rows, self.out_of_order_rows
+        );
+
+        for issue in self.issues.iter().take(Self::MAX_DISPLAYED_ISSUES) {
+            let location = match &issue.file {
+                Some(file) => format!("{}:{}", file, issue.line),
+                None => format!("Linha {}", issue.line),
+            };
+            warn!("⚠️  {} ({:?}): {}", location, issue.kind, issue.message);
+            println!("⚠️  {} ({:?}): {}", location, issue.kind, issue.message);
+        }
+        if self.skipped_rows() > Self::MAX_DISPLAYED_ISSUES {
+            println!(
+                "... e mais {} linhas com problemas",
+                self.skipped_rows() - Self::MAX_DISPLAYED_ISSUES
+            );
+        }
+        for failure in &self.failed_files {
+            error!("❌ Arquivo não lido: {}", failure);
+            println!("❌ Arquivo não lido: {}", failure);
+        }
+    }
+
+    /// Falha se algum arquivo da entrada ficou de fora da leitura
+    pub fn ensure_complete(&self) -> Result<(), Box<dyn std::error::Error>> {
+        if self.failed_files.is_empty() {
+            return Ok(());
+        }
+        Err(format!(
+            "{} arquivo(s) da entrada não puderam ser lidos: {}",
+            self.failed_files.len(),
+            self.failed_files.join("; ")
+        )
+        .into())
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Cópia só com os contadores, sem a lista de problemas
+    pub fn counters(&self) -> Self {
+        Self {
+            total_rows: self.total_rows,
+            valid_rows: self.valid_rows,
+            invalid_rows: self.invalid_rows,
+            inconsistent_rows: self.inconsistent_rows,
+            duplicate_rows: self.duplicate_rows,
+            out_of_order_rows: self.out_of_order_rows,
+            issues: Vec::new(),
+            failed_files: self.failed_files.clone(),
+        }
+    }
 }
 
+/// Sequência de timestamps em passo constante: `first`, `first + step`, ... (`count` valores)
+///
+/// Forma compacta da janela de duplicados no checkpoint da importação: num CSV de
+/// candles de minuto os 100.000 timestamps da janela viram poucas sequências.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimestampRun {
+    pub first: i64,
+    pub step: i64,
+    pub count: u64,
+}
+
+impl TimestampRun {
+    /// Agrupa os timestamps, na ordem dada, em sequências de passo constante
+    pub fn compress(timestamps: impl IntoIterator<Item = i64>) -> Vec<Self> {
+        let mut runs: Vec<Self> = Vec::new();
+        for timestamp in timestamps {
+            match runs.last_mut() {
+                Some(run) if run.count == 1 && timestamp.checked_sub(run.first).is_some() => {
+                    run.step = timestamp - run.first;
+                    run.count = 2;
+                }
+                Some(run) if run.nth(run.count) == Some(timestamp) => run.count += 1,
+                _ => runs.push(Self {
+                    first: timestamp,
+                    step: 0,
+                    count: 1,
+                }),
+            }
+        }
+        runs
+    }
+
+    /// Timestamps das sequências, na ordem original
+    pub fn expand(runs: &[Self]) -> impl Iterator<Item = i64> + '_ {
+        runs.iter()
+            .flat_map(|run| (0..run.count).filter_map(move |n| run.nth(n)))
+    }
+
+    fn nth(&self, n: u64) -> Option<i64> {
+        i64::try_from(n)
+            .ok()
+            .and_then(|n| self.step.checked_mul(n))
+            .and_then(|offset| self.first.checked_add(offset))
+    }
+}
+
+/// Índices já resolvidos das colunas
+struct ResolvedColumns {
+    open: usize,
+    high: usize,
+    low: usize,
+    close: usize,
+    volume: usize,
+    timestamp: usize,
+}
+
+impl ResolvedColumns {
+    fn resolve(
+        mapping: &ColumnMapping,
+        headers: Option<&csv::StringRecord>,
+    ) -> Result<Self, String> {
+        Ok(Self {
+            open: mapping.open.resolve(headers)?,
+            high: mapping.high.resolve(headers)?,
+            low: mapping.low.resolve(headers)?,
+            close: mapping.close.resolve(headers)?,
+            volume: mapping.volume.resolve(headers)?,
+            timestamp: mapping.timestamp.resolve(headers)?,
+        })
+    }
+
+    fn parse(&self, record: &csv::StringRecord) -> Result<CsvBtcFile, String> {
+        let field = |index: usize, name: &str| {
+            record
+                .get(index)
+                .ok_or_else(|| format!("coluna {} ({}) ausente", index, name))
+        };
+        let number = |index: usize, name: &str| -> Result<f64, String> {
+            let raw = field(index, name)?;
+            raw.trim()
+                .parse::<f64>()
+                .map_err(|_| format!("{} não numérico: '{}'", name, raw))
+        };
+
+        let raw_timestamp = field(self.timestamp, "timestamp")?;
+        Ok(CsvBtcFile {
+            open: number(self.open, "open")?,
+            high: number(self.high, "high")?,
+            low: number(self.low, "low")?,
+            close: number(self.close, "close")?,
+            volume: number(self.volume, "volume")?,
+            timestamp: parse_timestamp(raw_timestamp)
+                .ok_or_else(|| format!("timestamp inválido: '{}'", raw_timestamp))?,
+        })
+    }
+}
+
+/// Verifica a consistência dos valores OHLCV de um candle
+pub fn check_ohlc(candle: &CsvBtcFile) -> Result<(), String> {
+    let values = [
+        candle.open,
+        candle.high,
+        candle.low,
+        candle.close,
+        candle.volume,
+    ];
+    if values.iter().any(|v| !v.is_finite()) {
+        return Err("valor não finito".to_string());
+    }
+    if candle.open <= 0.0 || candle.high <= 0.0 || candle.low <= 0.0 || candle.close <= 0.0 {
+        return Err("preço não positivo".to_string());
+    }
+    if candle.volume < 0.0 {
+        return Err(format!("volume negativo: {}", candle.volume));
+    }
+    if candle.high < candle.low {
+        return Err(format!("high {} < low {}", candle.high, candle.low));
+    }
+    if candle.high < candle.open.max(candle.close) {
+        return Err(format!("high {} abaixo de open/close", candle.high));
+    }
+    if candle.low > candle.open.min(candle.close) {
+        return Err(format!("low {} acima de open/close", candle.low));
+    }
+    Ok(())
+}
+
+/// Candles por bloco na leitura incremental
+pub const DEFAULT_CHUNK_SIZE: usize = 20_000;
+
 pub struct ReaderBtcFile {}
 
 impl ReaderBtcFile {
-    pub fn read_btc_csv_file(
-        file_path: &str,
-    ) -> Result<Vec<CsvBtcFile>, Box<dyn std::error::Error>> {
-        let file = std::fs::File::open(file_path)?;
-        let reader = std::io::BufReader::new(file);
-        let mut csv_reader = csv::Reader::from_reader(reader);
-
+    /// Lê o CSV com mapeamento de colunas configurável, validando cada linha
+    ///
+    /// `input` pode ser um arquivo (`.csv`, `.csv.gz`, `.csv.zst`), um diretório ou um
+    /// glob. Linhas inválidas, inconsistentes ou duplicadas são ignoradas e registradas
+    /// no relatório em vez de abortar a importação.
+    pub fn read_btc_csv_file_with(
+        input: &str,
+        options: &CsvReadOptions,
+    ) -> Result<(Vec<CsvBtcFile>, ValidationReport), Box<dyn std::error::Error>> {
+        let mut stream = Self::open_stream(input, options)?;
         let mut data = Vec::new();
-        for result in csv_reader.deserialize() {
-            let record: CsvBtcFile = result?;
+        loop {
+            let chunk = stream.next_chunk(DEFAULT_CHUNK_SIZE);
+            stream.ensure_complete()?;
+            if chunk.is_empty() {
+                break;
+            }
+            data.extend(chunk);
+        }
+        Ok((data, stream.into_report()))
+    }
+
+    /// Abre a entrada para leitura incremental em blocos
+    ///
+    /// Um único CSV sem compressão é lido diretamente (e pode ser retomado por posição).
+    /// Arquivos compactados ou múltiplos arquivos são descompactados durante a leitura e
+    /// mesclados em ordem de timestamp, descartando sobreposições entre arquivos.
+    pub fn open_stream(
+        input: &str,
+        options: &CsvReadOptions,
+    ) -> Result<CandleStream, Box<dyn std::error::Error>> {
+        let files = Self::resolve_inputs(input)?;
+
+        let inner = if files.len() == 1 && Compression::of(&files[0]) == Compression::None {
+            let file = File::open(&files[0])?;
+            StreamInner::Plain(CandleReader::from_reader(
+                BufReader::new(file),
+                options,
+                None,
+            )?)
+        } else {
+            info!(
+                "🗂️  Mesclando {} arquivo(s) de {} por timestamp",
+                files.len(),
+                input
+            );
+            println!(
+                "🗂️  Mesclando {} arquivo(s) de {} por timestamp",
+                files.len(),
+                input
+            );
+            StreamInner::Merged(MergedReader::open(&files, options)?)
+        };
+
+        Ok(CandleStream { inner, emitted: 0 })
+    }
+
+    /// Expande a entrada em arquivos: caminho simples, diretório ou glob (`data/*.csv.gz`)
+    pub fn resolve_inputs(input: &str) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+        let path = Path::new(input);
+        let mut files: Vec<PathBuf> = if path.is_dir() {
+            std::fs::read_dir(path)?
+                .filter_map(|entry| entry.ok().map(|e| e.path()))
+                .filter(|p| p.is_file() && Compression::is_supported(p))
+                .collect()
+        } else if input.contains(['*', '?', '[']) {
+            glob::glob(input)?
+                .filter_map(Result::ok)
+                .filter(|p| p.is_file())
+                .collect()
+        } else {
+            vec![path.to_path_buf()]
+        };
+
+        if files.is_empty() {
+            return Err(format!("Nenhum arquivo de candles encontrado em {}", input).into());
+        }
+        files.sort();
+        Ok(files)
+    }
+
+    /// Abre um arquivo da entrada descompactando `.gz`/`.zst` durante a leitura
+    pub fn open_decoded(path: &Path) -> Result<Box<dyn Read>, Box<dyn std::error::Error>> {
+        open_decoded(path).map(|(reader, _)| reader)
+    }
+
+    /// Caminho de um artefato derivado da entrada (`<entrada><sufixo>`), seguro para globs
+    pub fn artifact_path(input: &str, suffix: &str) -> String {
+        let base: String = input
+            .trim_end_matches('/')
+            .chars()
+            .map(|c| {
+                if matches!(c, '*' | '?' | '[' | ']') {
+                    '_'
+                } else {
+                    c
+                }
+            })
+            .collect();
+        format!("{}{}", base, suffix)
+    }
+
+    /// Tamanho total e data de modificação mais recente dos arquivos da entrada
+    pub fn input_signature(input: &str) -> Result<(u64, Option<u64>), Box<dyn std::error::Error>> {
+        let mut size = 0;
+        let mut modified = None;
+        for file in Self::resolve_inputs(input)? {
+            let metadata = std::fs::metadata(&file)?;
+            size += metadata.len();
+            let secs = metadata
+                .modified()
+                .ok()
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map(|d| d.as_secs());
+            modified = modified.max(secs);
+        }
+        Ok((size, modified))
+    }
+}
+
+/// Compressão detectada pela extensão do arquivo
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Compression {
+    None,
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    fn of(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("gz") => Compression::Gzip,
+            Some("zst") | Some("zstd") => Compression::Zstd,
+            _ => Compression::None,
+        }
+    }
+
+    fn is_supported(path: &Path) -> bool {
+        matches!(
+            path.extension().and_then(|e| e.to_str()),
+            Some("csv" | "tsv" | "gz" | "zst" | "zstd")
+        )
+    }
+}
+
+/// Bytes já lidos de um arquivo (compactado), compartilhado com a mesclagem
+type ByteCounter = Rc<Cell<u64>>;
 
-            data.push(record);
+/// Conta os bytes lidos do arquivo para o progresso da importação
+struct CountingReader<R> {
+    inner: R,
+    count: ByteCounter,
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.count.set(self.count.get() + n as u64);
+        Ok(n)
+    }
+}
+
+/// Abre um arquivo descompactando conforme a extensão
+fn open_decoded(path: &Path) -> Result<(Box<dyn Read>, ByteCounter), Box<dyn std::error::Error>> {
+    let count = Rc::new(Cell::new(0));
+    let counted = BufReader::new(CountingReader {
+        inner: File::open(path)?,
+        count: Rc::clone(&count),
+    });
+    let reader: Box<dyn Read> = match Compression::of(path) {
+        Compression::Gzip => Box::new(MultiGzDecoder::new(counted)),
+        Compression::Zstd => Box::new(zstd::Decoder::with_buffer(counted)?),
+        Compression::None => Box::new(counted),
+    };
+    Ok((reader, count))
+}
+
+/// Posição de leitura da entrada, usada para retomar uma importação interrompida
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReadPosition {
+    pub byte: u64,
+    pub line: u64,
+    pub record: u64,
+    /// Candles válidos já entregues (usado para retomar entradas mescladas)
+    #[serde(default)]
+    pub emitted: u64,
+}
+
+/// Leitor incremental de candles de um único arquivo
+///
+/// Valida as linhas e detecta duplicados numa janela dos últimos `DEDUPE_WINDOW`
+/// timestamps para manter a memória constante em arquivos grandes.
+struct CandleReader<R: Read> {
+    csv_reader: csv::Reader<R>,
+    columns: ResolvedColumns,
+    report: ValidationReport,
+    recent: HashSet<i64>,
+    recent_order: VecDeque<i64>,
+    file: Option<String>,
+}
+
+impl<R: Read> CandleReader<R> {
+    const DEDUPE_WINDOW: usize = 100_000;
+
+    fn from_reader(
+        reader: R,
+        options: &CsvReadOptions,
+        file: Option<String>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut csv_reader = csv::ReaderBuilder::new()
+            .delimiter(options.delimiter)
+            .has_headers(options.has_headers)
+            .flexible(true)
+            .from_reader(reader);
+
+        let headers = if options.has_headers {
+            Some(csv_reader.headers()?.clone())
+        } else {
+            None
+        };
+        let columns = ResolvedColumns::resolve(&options.columns, headers.as_ref())?;
+
+        Ok(Self {
+            csv_reader,
+            columns,
+            report: ValidationReport::default(),
+            recent: HashSet::new(),
+            recent_order: VecDeque::new(),
+            file,
+        })
+    }
+
+    /// Próximo candle válido e sua linha; `None` no fim do arquivo
+    fn next_candle(&mut self) -> Option<(u64, CsvBtcFile)> {
+        let mut record = csv::StringRecord::new();
+        loop {
+            match self.csv_reader.read_record(&mut record) {
+                Ok(true) => {}
+                Ok(false) => return None,
+                Err(e) => {
+                    self.report.total_rows += 1;
+                    let line = e.position().map(|p| p.line()).unwrap_or(0);
+                    self.report.push(
+                        self.file.as_deref(),
+                        line,
+                        RowIssueKind::Invalid,
+                        e.to_string(),
+                    );
+                    // Erros de E/S não avançam o leitor: encerrar em vez de repetir
+                    if matches!(e.kind(), csv::ErrorKind::Io(_)) {
+                        return None;
+                    }
+                    continue;
+                }
+            }
+            self.report.total_rows += 1;
+            let line = record.position().map(|p| p.line()).unwrap_or(0);
+
+            let candle = match self.columns.parse(&record) {
+                Ok(candle) => candle,
+                Err(message) => {
+                    self.report
+                        .push(self.file.as_deref(), line, RowIssueKind::Invalid, message);
+                    continue;
+                }
+            };
+
+            if let Err(message) = check_ohlc(&candle) {
+                self.report.push(
+                    self.file.as_deref(),
+                    line,
+                    RowIssueKind::Inconsistent,
+                    message,
+                );
+                continue;
+            }
+
+            if !self.remember(candle.timestamp.timestamp_millis()) {
+                self.report.push(
+                    self.file.as_deref(),
+                    line,
+                    RowIssueKind::Duplicate,
+                    format!("timestamp repetido: {}", candle.timestamp.to_rfc3339()),
+                );
+                continue;
+            }
+
+            self.report.valid_rows += 1;
+            return Some((line, candle));
+        }
+    }
+
+    fn remember(&mut self, timestamp: i64) -> bool {
+        if !self.recent.insert(timestamp) {
+            return false;
+        }
+        self.recent_order.push_back(timestamp);
+        if self.recent_order.len() > Self::DEDUPE_WINDOW {
+            if let Some(oldest) = self.recent_order.pop_front() {
+                self.recent.remove(&oldest);
+            }
+        }
+        true
+    }
+}
+
+/// Arquivo ativo na mesclagem, com o próximo candle já lido
+struct MergeSource {
+    reader: CandleReader<Box<dyn Read>>,
+    bytes: ByteCounter,
+    head: (u64, CsvBtcFile),
+}
+
+/// Mescla vários arquivos ordenados por timestamp
+///
+/// Os arquivos são ordenados pelo primeiro timestamp e só ficam abertos enquanto se
+/// sobrepõem ao ponto atual da mesclagem, o que permite milhares de arquivos diários.
+struct MergedReader {
+    options: CsvReadOptions,
+    pending: VecDeque<(DateTime<Utc>, PathBuf)>,
+    active: Vec<Option<MergeSource>>,
+    heap: BinaryHeap<Reverse<(i64, usize)>>,
+    last_timestamp: Option<DateTime<Utc>>,
+    finished_bytes: u64,
+    // Candles válidos nos arquivos, mas descartados na mesclagem
+    dropped: usize,
+    report: ValidationReport,
+}
+
+impl MergedReader {
+    fn open(
+        files: &[PathBuf],
+        options: &CsvReadOptions,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        // Primeiro timestamp válido de cada arquivo define a ordem de abertura
+        let mut pending = Vec::with_capacity(files.len());
+        let mut report = ValidationReport::default();
+        for path in files {
+            let (reader, _) = open_decoded(path)?;
+            let label = path.display().to_string();
+            let mut reader = CandleReader::from_reader(reader, options, Some(label))?;
+            match reader.next_candle() {
+                Some((_, candle)) => pending.push((candle.timestamp, path.clone())),
+                None => {
+                    // Arquivo lido por inteiro: os problemas encontrados já são definitivos
+                    warn!("⚠️  Nenhum candle válido em {}", path.display());
+                    report.absorb(reader.report);
+                }
+            }
+        }
+        pending.sort_by_key(|(time, _)| *time);
+
+        Ok(Self {
+            options: options.clone(),
+            pending: pending.into(),
+            active: Vec::new(),
+            heap: BinaryHeap::new(),
+            last_timestamp: None,
+            finished_bytes: 0,
+            dropped: 0,
+            report,
+        })
+    }
+
+    /// Abre os arquivos pendentes que começam antes do próximo candle da mesclagem
+    ///
+    /// Um arquivo que não abre fica registrado em `failed_files`: os candles dele
+    /// faltariam na mesclagem, então quem consome a leitura deve interrompê-la.
+    fn activate_pending(&mut self) {
+        while let Some((first, _)) = self.pending.front() {
+            let frontier = self.heap.peek().map(|Reverse((ts, _))| *ts);
+            if frontier.is_some_and(|ts| first.timestamp_millis() > ts) {
+                break;
+            }
+            let (_, path) = self.pending.pop_front().expect("pendente verificado acima");
+            let label = path.display().to_string();
+            let opened = open_decoded(&path).and_then(|(reader, bytes)| {
+                CandleReader::from_reader(reader, &self.options, Some(label.clone()))
+                    .map(|reader| (reader, bytes))
+            });
+            let (mut reader, bytes) = match opened {
+                Ok(opened) => opened,
+                Err(e) => {
+                    error!("❌ Erro ao abrir arquivo da mesclagem {}: {}", label, e);
+                    self.report.failed_files.push(format!("{}: {}", label, e));
+                    continue;
+                }
+            };
+            match reader.next_candle() {
+                Some(head) => {
+                    let slot = self.active.len();
+                    self.heap
+                        .push(Reverse((head.1.timestamp.timestamp_millis(), slot)));
+                    self.active.push(Some(MergeSource {
+                        reader,
+                        bytes,
+                        head,
+                    }));
+                }
+                None => {
+                    self.finished_bytes += bytes.get();
+                    self.report.absorb(reader.report);
+                }
+            }
+        }
+    }
+
+    fn next_candle(&mut self) -> Option<CsvBtcFile> {
+        loop {
+            self.activate_pending();
+            let Reverse((_, slot)) = self.heap.pop()?;
+            let source = self.active[slot].as_mut().expect("fonte ativa no heap");
+
+            let ((line, candle), finished_file) = match source.reader.next_candle() {
+                Some(next) => {
+                    self.heap
+                        .push(Reverse((next.1.timestamp.timestamp_millis(), slot)));
+                    (std::mem::replace(&mut source.head, next), None)
+                }
+                None => {
+                    let source = self.active[slot].take().expect("fonte ativa no heap");
+                    self.finished_bytes += source.bytes.get();
+                    let file = source.reader.file.clone();
+                    self.report.absorb(source.reader.report);
+                    (source.head, file)
+                }
+            };
+
+            // Descarta sobreposições entre arquivos e candles que voltam no tempo
+            let kind = match self.last_timestamp {
+                Some(last) if candle.timestamp == last => RowIssueKind::Duplicate,
+                Some(last) if candle.timestamp < last => RowIssueKind::OutOfOrder,
+                _ => {
+                    self.last_timestamp = Some(candle.timestamp);
+                    return Some(candle);
+                }
+            };
+            let file = finished_file.or_else(|| {
+                self.active[slot]
+                    .as_ref()
+                    .and_then(|s| s.reader.file.clone())
+            });
+            self.dropped += 1;
+            self.report.push(
+                file.as_deref(),
+                line,
+                kind,
+                format!("timestamp {} já mesclado", candle.timestamp.to_rfc3339()),
+            );
+        }
+    }
+
+    fn bytes_read(&self) -> u64 {
+        self.finished_bytes
+            + self
+                .active
+                .iter()
+                .flatten()
+                .map(|s| s.bytes.get())
+                .sum::<u64>()
+    }
+
+    fn report(&self) -> ValidationReport {
+        let mut report = self.report.clone();
+        for source in self.active.iter().flatten() {
+            report.absorb(source.reader.report.clone());
+        }
+        report.valid_rows = report.valid_rows.saturating_sub(self.dropped);
+        report
+    }
+}
+
+enum StreamInner {
+    Plain(CandleReader<BufReader<File>>),
+    Merged(MergedReader),
+}
+
+/// Leitura incremental de uma entrada de candles (arquivo, diretório ou glob)
+pub struct CandleStream {
+    inner: StreamInner,
+    emitted: u64,
+}
+
+impl CandleStream {
+    /// Lê até `max` candles válidos; um bloco vazio indica fim da entrada
+    pub fn next_chunk(&mut self, max: usize) -> Vec<CsvBtcFile> {
+        let mut chunk = Vec::with_capacity(max.min(DEFAULT_CHUNK_SIZE));
+        while chunk.len() < max {
+            let candle = match &mut self.inner {
+                StreamInner::Plain(reader) => reader.next_candle().map(|(_, candle)| candle),
+                StreamInner::Merged(reader) => reader.next_candle(),
+            };
+            match candle {
+                Some(candle) => chunk.push(candle),
+                None => break,
+            }
+        }
+        self.emitted += chunk.len() as u64;
+        chunk
+    }
+
+    /// Continua de uma posição salva
+    ///
+    /// Um CSV simples é reposicionado direto no byte salvo e mantém o relatório anterior e a
+    /// janela de duplicados (`dedupe_window`); entradas mescladas ou compactadas não têm
+    /// como ser reposicionadas e são relidas do início descartando os candles já entregues,
+    /// o que reconstrói a janela e o relatório.
+    pub fn resume(
+        &mut self,
+        position: &ReadPosition,
+        report: ValidationReport,
+        dedupe_window: &[TimestampRun],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        match &mut self.inner {
+            StreamInner::Plain(reader) => {
+                let mut pos = csv::Position::new();
+                pos.set_byte(position.byte)
+                    .set_line(position.line)
+                    .set_record(position.record);
+                reader.csv_reader.seek(pos)?;
+                reader.report = report;
+                for timestamp in TimestampRun::expand(dedupe_window) {
+                    reader.remember(timestamp);
+                }
+                self.emitted = position.emitted;
+            }
+            StreamInner::Merged(_) => {
+                println!("⏩ Pulando {} candles já importados...", position.emitted);
+                while self.emitted < position.emitted {
+                    let remaining = (position.emitted - self.emitted) as usize;
+                    let chunk = self.next_chunk(remaining.min(DEFAULT_CHUNK_SIZE));
+                    if chunk.is_empty() {
+                        break;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Timestamps (ms) da janela de duplicados, do mais antigo ao mais recente, agrupados
+    /// em sequências de passo constante
+    ///
+    /// Vazia para entradas mescladas, que reconstroem a janela ao serem relidas.
+    pub fn dedupe_window(&self) -> Vec<TimestampRun> {
+        match &self.inner {
+            StreamInner::Plain(reader) => {
+                TimestampRun::compress(reader.recent_order.iter().copied())
+            }
+            StreamInner::Merged(_) => Vec::new(),
+        }
+    }
+
+    /// Problemas registrados a partir do `start`-ésimo, na ordem em que foram encontrados
+    ///
+    /// Vazio para entradas mescladas, que reconstroem o relatório ao serem relidas.
+    pub fn issues_from(&self, start: usize) -> &[RowIssue] {
+        match &self.inner {
+            StreamInner::Plain(reader) => reader.report.issues.get(start..).unwrap_or_default(),
+            StreamInner::Merged(_) => &[],
+        }
+    }
+
+    /// Posição logo após o último candle entregue
+    pub fn position(&self) -> ReadPosition {
+        match &self.inner {
+            StreamInner::Plain(reader) => {
+                let position = reader.csv_reader.position();
+                ReadPosition {
+                    byte: position.byte(),
+                    line: position.line(),
+                    record: position.record(),
+                    emitted: self.emitted,
+                }
+            }
+            StreamInner::Merged(reader) => ReadPosition {
+                byte: reader.bytes_read(),
+                line: 0,
+                record: 0,
+                emitted: self.emitted,
+            },
+        }
+    }
+
+    /// Falha se algum arquivo da entrada não pôde ser aberto
+    ///
+    /// Deve ser consultado a cada bloco: a leitura segue sem o arquivo, e os candles dele
+    /// faltariam no resultado.
+    pub fn ensure_complete(&self) -> Result<(), Box<dyn std::error::Error>> {
+        match &self.inner {
+            StreamInner::Plain(_) => Ok(()),
+            StreamInner::Merged(reader) => reader.report.ensure_complete(),
         }
+    }
+
+    /// Contadores do relatório, sem copiar a lista de problemas
+    pub fn report_counters(&self) -> ValidationReport {
+        match &self.inner {
+            StreamInner::Plain(reader) => reader.report.counters(),
+            StreamInner::Merged(reader) => reader.report().counters(),
+        }
+    }
+
+    pub fn into_report(self) -> ValidationReport {
+        match self.inner {
+            StreamInner::Plain(reader) => reader.report,
+            StreamInner::Merged(reader) => reader.report(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(value: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(value)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    fn candle(open: f64, high: f64, low: f64, close: f64, volume: f64) -> CsvBtcFile {
+        CsvBtcFile {
+            open,
+            high,
+            low,
+            close,
+            volume,
+            timestamp: at("2024-01-01T00:00:00Z"),
+        }
+    }
+
+    fn reader<'a>(data: &'a str, options: &CsvReadOptions) -> CandleReader<&'a [u8]> {
+        CandleReader::from_reader(data.as_bytes(), options, None).unwrap()
+    }
+
+    #[test]
+    fn parse_timestamp_detects_epoch_unit_by_digits() {
+        let expected = at("2024-01-02T03:04:05Z");
+        for value in [
+            "1704164645",
+            "1704164645000",
+            "1704164645000000",
+            "1704164645000000000",
+        ] {
+            assert_eq!(parse_timestamp(value), Some(expected), "{}", value);
+        }
+        assert_eq!(
+            parse_timestamp("1704164645.5"),
+            Some(expected + chrono::Duration::milliseconds(500))
+        );
+        assert_eq!(
+            parse_timestamp("1704164645123456"),
+            Some(expected + chrono::Duration::microseconds(123_456))
+        );
+    }
+
+    #[test]
+    fn parse_timestamp_accepts_text_formats() {
+        let expected = at("2024-01-02T03:04:00Z");
+        for value in [
+            "2024-01-02T03:04:00Z",
+            "2024-01-02T00:04:00-03:00",
+            "2024-01-02 03:04:00",
+            "2024-01-02 03:04",
+            "2024-01-02T03:04",
+            "02/01/2024 03:04",
+            " 2024-01-02 03:04:00.000 ",
+        ] {
+            assert_eq!(parse_timestamp(value), Some(expected), "{}", value);
+        }
+        assert_eq!(
+            parse_timestamp("2024-01-02"),
+            Some(at("2024-01-02T00:00:00Z"))
+        );
+    }
+
+    #[test]
+    fn parse_timestamp_rejects_invalid_and_implausible_values() {
+        for value in [
+            "",
+            "abc",
+            "1e12",
+            "-1704164645",
+            // 2200-01-01 em segundos: fora da faixa aceita
+            "7258118400",
+            // 20 dígitos: nenhuma unidade conhecida
+            "17041646450000000000",
+            "1900-01-01",
+        ] {
+            assert_eq!(parse_timestamp(value), None, "{}", value);
+        }
+    }
+
+    #[test]
+    fn check_ohlc_rejects_inconsistent_candles() {
+        assert!(check_ohlc(&candle(10.0, 12.0, 9.0, 11.0, 1.0)).is_ok());
+        assert!(check_ohlc(&candle(10.0, 12.0, 9.0, 11.0, 0.0)).is_ok());
+        assert!(check_ohlc(&candle(10.0, 8.0, 9.0, 11.0, 1.0)).is_err());
+        assert!(check_ohlc(&candle(10.0, 10.5, 9.0, 11.0, 1.0)).is_err());
+        assert!(check_ohlc(&candle(10.0, 12.0, 10.5, 11.0, 1.0)).is_err());
+        assert!(check_ohlc(&candle(0.0, 12.0, 9.0, 11.0, 1.0)).is_err());
+        assert!(check_ohlc(&candle(10.0, 12.0, 9.0, 11.0, -1.0)).is_err());
+        assert!(check_ohlc(&candle(10.0, f64::NAN, 9.0, 11.0, 1.0)).is_err());
+    }
+
+    #[test]
+    fn rows_are_validated_and_reported() {
+        let data = "open,high,low,close,volume,timestamp\n\
+                    10,12,9,11,1,2024-01-01T00:00:00Z\n\
+                    10,12,9,x,1,2024-01-01T00:01:00Z\n\
+                    10,8,9,11,1,2024-01-01T00:02:00Z\n\
+                    10,12,9,11,1,nope\n\
+                    10,12,9,11,2,2024-01-01T00:00:00Z\n\
+                    11,13,10,12,1,2024-01-01T00:03:00Z\n";
+        let mut reader = reader(data, &CsvReadOptions::default());
+
+        let (line, first) = reader.next_candle().unwrap();
+        assert_eq!(line, 2);
+        assert_eq!(first.close, 11.0);
+        let (line, second) = reader.next_candle().unwrap();
+        assert_eq!(line, 7);
+        assert_eq!(second.timestamp, at("2024-01-01T00:03:00Z"));
+        assert!(reader.next_candle().is_none());
+
+        let report = &reader.report;
+        assert_eq!(report.total_rows, 6);
+        assert_eq!(report.valid_rows, 2);
+        assert_eq!(report.invalid_rows, 2);
+        assert_eq!(report.inconsistent_rows, 1);
+        assert_eq!(report.duplicate_rows, 1);
+        let lines: Vec<u64> = report.issues.iter().map(|issue| issue.line).collect();
+        assert_eq!(lines, vec![3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn columns_map_by_name_or_position() {
+        let options = CsvReadOptions {
+            delimiter: b';',
+            has_headers: true,
+            columns: ColumnMapping {
+                timestamp: ColumnRef::parse("Time"),
+                volume: ColumnRef::parse("vol"),
+                ..ColumnMapping::default()
+            },
+        };
+        let data = "time;close;open;high;low;vol\n1704067200;11;10;12;9;3.5\n";
+        let (_, candle) = reader(data, &options).next_candle().unwrap();
+        assert_eq!(
+            (
+                candle.open,
+                candle.high,
+                candle.low,
+                candle.close,
+                candle.volume
+            ),
+            (10.0, 12.0, 9.0, 11.0, 3.5)
+        );
+        assert_eq!(candle.timestamp, at("2024-01-01T00:00:00Z"));
+
+        let options = CsvReadOptions {
+            delimiter: b',',
+            has_headers: false,
+            columns: ColumnMapping {
+                timestamp: ColumnRef::parse("0"),
+                open: ColumnRef::parse("1"),
+                high: ColumnRef::parse("2"),
+                low: ColumnRef::parse("3"),
+                close: ColumnRef::parse("4"),
+                volume: ColumnRef::parse("5"),
+            },
+        };
+        let (_, candle) = reader("1704067200000,10,12,9,11,2\n", &options)
+            .next_candle()
+            .unwrap();
+        assert_eq!(candle.close, 11.0);
+        assert_eq!(candle.timestamp, at("2024-01-01T00:00:00Z"));
+    }
+
+    #[test]
+    fn missing_named_column_is_an_error() {
+        let options = CsvReadOptions {
+            columns: ColumnMapping {
+                volume: ColumnRef::parse("vol"),
+                ..ColumnMapping::default()
+            },
+            ..CsvReadOptions::default()
+        };
+        let data = "open,high,low,close,volume,timestamp\n";
+        assert!(CandleReader::from_reader(data.as_bytes(), &options, None).is_err());
+    }
+
+    /// Diretório temporário exclusivo do teste, recriado vazio
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("btc-reader-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn write_csv(path: &Path, minutes: &[i64]) {
+        let mut data = String::from("open,high,low,close,volume,timestamp\n");
+        for minute in minutes {
+            let time = at("2024-01-01T00:00:00Z") + chrono::Duration::minutes(*minute);
+            data.push_str(&format!("10,12,9,11,{},{}\n", minute, time.to_rfc3339()));
+        }
+        if path.extension().is_some_and(|ext| ext == "gz") {
+            let mut encoder = flate2::write::GzEncoder::new(
+                File::create(path).unwrap(),
+                flate2::Compression::default(),
+            );
+            std::io::Write::write_all(&mut encoder, data.as_bytes()).unwrap();
+            encoder.finish().unwrap();
+        } else {
+            std::fs::write(path, data).unwrap();
+        }
+    }
+
+    fn minutes(candles: &[CsvBtcFile]) -> Vec<i64> {
+        candles
+            .iter()
+            .map(|c| (c.timestamp - at("2024-01-01T00:00:00Z")).num_minutes())
+            .collect()
+    }
+
+    fn read_all(stream: &mut CandleStream) -> Vec<CsvBtcFile> {
+        let mut candles = Vec::new();
+        loop {
+            let chunk = stream.next_chunk(2);
+            if chunk.is_empty() {
+                return candles;
+            }
+            candles.extend(chunk);
+        }
+    }
+
+    #[test]
+    fn merged_reader_orders_files_by_timestamp() {
+        let dir = temp_dir("merge");
+        write_csv(&dir.join("a.csv"), &[0, 2, 4]);
+        write_csv(&dir.join("b.csv.gz"), &[1, 2, 3]);
+        write_csv(&dir.join("c.csv"), &[5, 6]);
+        // Começa depois dos outros, mas volta no tempo
+        write_csv(&dir.join("d.csv"), &[6, 1, 7]);
+
+        let options = CsvReadOptions::default();
+        let mut stream = ReaderBtcFile::open_stream(dir.to_str().unwrap(), &options).unwrap();
+        let candles = read_all(&mut stream);
+        let report = stream.into_report();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(minutes(&candles), vec![0, 1, 2, 3, 4, 5, 6, 7]);
+        assert_eq!(report.duplicate_rows, 2);
+        assert_eq!(report.out_of_order_rows, 1);
+        assert_eq!(report.valid_rows, 8);
+        assert!(report.issues.iter().all(|issue| issue.file.is_some()));
+    }
+
+    #[test]
+    fn resume_keeps_dedupe_window_across_checkpoint() {
+        let dir = temp_dir("resume");
+        let path = dir.join("candles.csv");
+        write_csv(&path, &[0, 1, 2, 1, 3]);
+        let input = path.to_str().unwrap();
+        let options = CsvReadOptions::default();
+
+        let mut stream = ReaderBtcFile::open_stream(input, &options).unwrap();
+        assert_eq!(minutes(&stream.next_chunk(3)), vec![0, 1, 2]);
+        let position = stream.position();
+        let report = stream.report_counters();
+        let window = stream.dedupe_window();
+        assert_eq!(TimestampRun::expand(&window).count(), 3);
+
+        let mut resumed = ReaderBtcFile::open_stream(input, &options).unwrap();
+        resumed.resume(&position, report, &window).unwrap();
+        let rest = read_all(&mut resumed);
+        let report = resumed.into_report();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(minutes(&rest), vec![3]);
+        assert_eq!(report.duplicate_rows, 1);
+        assert_eq!(report.valid_rows, 4);
+    }
+
+    #[test]
+    fn merged_resume_skips_candles_already_delivered() {
+        let dir = temp_dir("resume-merged");
+        write_csv(&dir.join("a.csv.gz"), &[0, 2, 4]);
+        write_csv(&dir.join("b.csv"), &[1, 3]);
+        let input = dir.to_str().unwrap();
+        let options = CsvReadOptions::default();
+
+        let mut stream = ReaderBtcFile::open_stream(input, &options).unwrap();
+        assert_eq!(minutes(&stream.next_chunk(2)), vec![0, 1]);
+        let position = stream.position();
+
+        let mut resumed = ReaderBtcFile::open_stream(input, &options).unwrap();
+        resumed
+            .resume(&position, ValidationReport::default(), &[])
+            .unwrap();
+        let rest = read_all(&mut resumed);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(minutes(&rest), vec![2, 3, 4]);
+    }
+
+    #[test]
+    fn timestamp_runs_round_trip_irregular_windows() {
+        let minute = 60_000;
+        let mut window: Vec<i64> = (0..1000).map(|i| i * minute).collect();
+        window.extend([
+            5 * minute,
+            2_000 * minute,
+            2_000 * minute + 1,
+            i64::MAX,
+            i64::MIN,
+        ]);
+
+        let runs = TimestampRun::compress(window.iter().copied());
+        assert_eq!(
+            runs[0],
+            TimestampRun {
+                first: 0,
+                step: minute,
+                count: 1000
+            }
+        );
+        assert!(runs.len() <= 5);
+        assert_eq!(TimestampRun::expand(&runs).collect::<Vec<_>>(), window);
+        assert!(TimestampRun::compress(Vec::new()).is_empty());
+    }
+
+    #[test]
+    fn report_counters_drop_the_issue_list() {
+        let mut report = ValidationReport::default();
+        report.push(None, 2, RowIssueKind::Duplicate, "repetido".to_string());
+        report.total_rows = 3;
+
+        let counters = report.counters();
+        assert_eq!((counters.total_rows, counters.duplicate_rows), (3, 1));
+        assert!(counters.issues.is_empty());
+        assert_eq!(report.issues.len(), 1);
+    }
+
+    #[test]
+    fn merged_file_that_fails_to_open_stops_the_read() {
+        let dir = temp_dir("merge-failure");
+        write_csv(&dir.join("a.csv"), &[0, 1, 2]);
+        write_csv(&dir.join("b.csv.gz"), &[5, 6]);
+        let input = dir.to_str().unwrap();
+        let options = CsvReadOptions::default();
+
+        let mut stream = ReaderBtcFile::open_stream(input, &options).unwrap();
+        // O segundo arquivo só é aberto quando a mesclagem chega ao primeiro timestamp dele
+        std::fs::remove_file(dir.join("b.csv.gz")).unwrap();
+        assert_eq!(minutes(&stream.next_chunk(10)), vec![0, 1, 2]);
+        let error = stream.ensure_complete().unwrap_err().to_string();
+        assert!(error.contains("b.csv.gz"), "{}", error);
 
-        Ok(data)
+        let report = stream.into_report();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(report.failed_files.len(), 1);
+        assert!(report.ensure_complete().is_err());
+        assert!(report.counters().ensure_complete().is_err());
     }
 }
@@ -1,11 +1,16 @@
+use redis::streams::{StreamMaxlen, StreamReadOptions, StreamReadReply};
 use redis::{Client, Commands, RedisError};
 use std::env;
 use std::thread;
 use std::time::{Duration, Instant};
 use tracing::{info, warn, debug};
 
+use crate::metrics;
 use crate::reader_csv::CsvBtcFile;
 
+/// Chave com a quantidade de índices do histórico `btc_N`, gravada junto de cada bloco
+pub const CANDLE_COUNT_KEY: &str = "btc_count";
+
 /// Configuração para conexão Redis
 #[derive(Debug, Clone)]
 pub struct RedisConfig {
@@ -54,6 +59,7 @@ impl RedisConfig {
 }
 
 /// Cliente Redis com funcionalidades robustas
+#[derive(Clone)]
 pub struct RedisClient {
     client: Client,
     config: RedisConfig,
@@ -82,6 +88,41 @@ impl RedisClient {
         Self::new(RedisConfig::from_env())
     }
 
+    /// Cliente que nunca conectou, para testes que não chegam ao Redis
+    #[cfg(test)]
+    pub fn offline() -> Self {
+        let config = RedisConfig {
+            url: "redis://127.0.0.1:1".to_string(),
+            ..RedisConfig::default()
+        };
+        Self {
+            client: Client::open(config.url.as_str()).unwrap(),
+            config,
+        }
+    }
+
+    /// URL do servidor Redis em uso
+    pub fn url(&self) -> &str {
+        &self.config.url
+    }
+
+    /// Executa `op` numa conexão nova, contando latência e erros para o `/metrics`
+    ///
+    /// Usado nos comandos do dia a dia da simulação (candles, estado, eventos); leituras
+    /// bloqueantes de stream e a importação ficam de fora para não distorcer a latência.
+    fn timed<T>(
+        &self,
+        op: impl FnOnce(&mut redis::Connection) -> redis::RedisResult<T>,
+    ) -> redis::RedisResult<T> {
+        let started = Instant::now();
+        let result = self
+            .client
+            .get_connection()
+            .and_then(|mut con| op(&mut con));
+        metrics::record_redis(started.elapsed(), result.is_ok());
+        result
+    }
+
     /// Testa a conexão com Redis
     pub fn test_connection(&self) -> Result<(), RedisClientError> {
         info!("🧪 Testando conexão Redis...");
@@ -132,146 +173,388 @@ impl RedisClient {
     }
 
 
-    pub fn set_all_btc(&self, data: &[CsvBtcFile]) -> Result<(), Box<dyn std::error::Error>> {
+    /// Grava um bloco de candles a partir de `btc_{start_index}` num único pipeline
+    ///
+    /// Registros idênticos aos já gravados são ignorados. Os blocos são gravados em
+    /// sequência, então `btc_count` passa a ser o fim deste bloco. Retorna (salvos, ignorados).
+    pub fn store_chunk(
+        &self,
+        start_index: usize,
+        chunk: &[CsvBtcFile],
+    ) -> Result<(usize, usize), Box<dyn std::error::Error>> {
+        if chunk.is_empty() {
+            return Ok((0, 0));
+        }
         let mut con = self.client.get_connection()?;
-        let batch_size = 20000; // Ajuste conforme sua memória RAM disponível
 
-        let mut total_records_saved = 0;
-        let mut total_records_skipped = 0;
+        // 1. Gera todas as chaves do batch atual
+        let keys: Vec<String> = (0..chunk.len())
+            .map(|i| format!("btc_{}", start_index + i))
+            .collect();
 
-        println!(
-            "🚀 Iniciando processamento com batches de {} registros",
-            batch_size
-        );
+        // 2. Busca todos os valores existentes de uma vez com MGET
+        let existing_values: Vec<Option<String>> = redis::cmd("MGET").arg(&keys).query(&mut con)?;
 
-        for (batch_num, chunk) in data.chunks(batch_size).enumerate() {
-            let batch_start_index = batch_num * batch_size;
+        // 3. Prepara pipeline apenas com registros que precisam ser atualizados
+        let mut pipe = redis::pipe();
+        let mut records_to_save = 0;
+        let mut records_skipped = 0;
 
-            // 1. Gera todas as chaves do batch atual
-            let keys: Vec<String> = (0..chunk.len())
-                .map(|i| format!("btc_{}", batch_start_index + i))
-                .collect();
+        for (i, record) in chunk.iter().enumerate() {
+            let json_data = serde_json::to_string(record)?;
 
-            // 2. Busca todos os valores existentes de uma vez com MGET
-            let existing_values: Vec<Option<String>> = if !keys.is_empty() {
-                con.get(&keys)?
-            } else {
-                vec![]
+            // Verifica se deve salvar comparando com valor existente
+            let should_save = match existing_values.get(i) {
+                Some(Some(existing_data)) => json_data != *existing_data,
+                Some(None) => true, // Chave não existe
+                None => true,       // Índice fora do range (não deveria acontecer)
             };
 
-            // 3. Prepara pipeline apenas com registros que precisam ser atualizados
-            let mut pipe = redis::pipe();
-            let mut records_to_save = 0;
-            let mut records_skipped = 0;
-
-            for (i, record) in chunk.iter().enumerate() {
-                let json_data = serde_json::to_string(record)?;
-
-                // Verifica se deve salvar comparando com valor existente
-                let should_save = match existing_values.get(i) {
-                    Some(Some(existing_data)) => json_data != *existing_data,
-                    Some(None) => true, // Chave não existe
-                    None => true,       // Índice fora do range (não deveria acontecer)
-                };
-
-                if should_save {
-                    pipe.set(&keys[i], &json_data);
-                    records_to_save += 1;
-                } else {
-                    records_skipped += 1;
-                }
-            }
-
-            // 4. Executa o pipeline apenas se houver registros para salvar
-            if records_to_save > 0 {
-                let _: Vec<()> = pipe.query(&mut con)?;
+            if should_save {
+                pipe.set(&keys[i], &json_data);
+                records_to_save += 1;
+            } else {
+                records_skipped += 1;
             }
-
-            // 5. Atualiza contadores totais
-            total_records_saved += records_to_save;
-            total_records_skipped += records_skipped;
-
-            // 6. Log de progresso por batch
-            let progress = ((batch_num + 1) * batch_size).min(data.len());
-            debug!(
-                "📦 Batch {}: {}/{} - Salvos: {} | Ignorados: {} | Progresso: {}/{}",
-                batch_num + 1,
-                records_to_save,
-                chunk.len(),
-                records_to_save,
-                records_skipped,
-                progress,
-                data.len()
-            );
-            println!(
-                "📦 Batch {}: {}/{} - Salvos: {} | Ignorados: {} | Progresso: {}/{}",
-                batch_num + 1,
-                records_to_save,
-                chunk.len(),
-                records_to_save,
-                records_skipped,
-                progress,
-                data.len()
-            );
         }
 
-        // 7. Relatório final
-        if total_records_saved > 0 {
-            info!(
-                "✅ {} registros salvos no Redis em batches",
-                total_records_saved
-            );
-            println!(
-                "✅ {} registros salvos no Redis em batches",
-                total_records_saved
-            );
-        }
-        if total_records_skipped > 0 {
-            info!(
-                "⏭️  {} registros já existiam e foram ignorados",
-                total_records_skipped
-            );
-            println!(
-                "⏭️  {} registros já existiam e foram ignorados",
-                total_records_skipped
-            );
-        }
-        info!(
-            "📊 Total processado: {} registros (btc_0 a btc_{})",
-            data.len(),
-            data.len() - 1
-        );
-        println!(
-            "📊 Total processado: {} registros (btc_0 a btc_{})",
-            data.len(),
-            data.len() - 1
-        );
+        // 4. Executa o pipeline com os registros alterados e o novo total
+        pipe.set(CANDLE_COUNT_KEY, start_index + chunk.len());
+        let _: Vec<()> = pipe.query(&mut con)?;
 
-        Ok(())
+        Ok((records_to_save, records_skipped))
     }
+
     // Método para carregar por índice
     pub fn load_by_index(
         &self,
         index: usize,
     ) -> Result<Option<CsvBtcFile>, Box<dyn std::error::Error>> {
-        let mut con = self.client.get_connection()?;
         let key = format!("btc_{}", index);
 
-        match con.get::<String, String>(key) {
-            Ok(json_data) => {
+        match self.timed(|con| con.get::<String, Option<String>>(key))? {
+            Some(json_data) => {
                 let record: CsvBtcFile = serde_json::from_str(&json_data)?;
                 Ok(Some(record))
             }
-            Err(e) => {
-                if e.kind() == redis::ErrorKind::TypeError {
-                    Ok(None)
-                } else {
-                    Err(e.into())
-                }
+            None => Ok(None),
+        }
+    }
+
+    /// Carrega `count` candles consecutivos a partir de `start` com um único MGET
+    pub fn load_range(
+        &self,
+        start: usize,
+        count: usize,
+    ) -> Result<Vec<Option<CsvBtcFile>>, Box<dyn std::error::Error>> {
+        if count == 0 {
+            return Ok(Vec::new());
+        }
+
+        let keys: Vec<String> = (start..start + count).map(|i| format!("btc_{}", i)).collect();
+        let values: Vec<Option<String>> =
+            self.timed(|con| redis::cmd("MGET").arg(&keys).query(con))?;
+
+        let mut records = Vec::with_capacity(values.len());
+        for value in values {
+            records.push(match value {
+                Some(json_data) => Some(serde_json::from_str(&json_data)?),
+                None => None,
+            });
+        }
+        Ok(records)
+    }
+
+    /// Quantidade de índices do histórico `btc_N` gravada pelo importador
+    ///
+    /// `None` em bases importadas antes da contagem existir.
+    pub fn candle_count(&self) -> Result<Option<usize>, Box<dyn std::error::Error>> {
+        Ok(self.timed(|con| con.get(CANDLE_COUNT_KEY))?)
+    }
+
+    /// Valor de uma chave, se existir
+    pub fn get_value(&self, key: &str) -> Result<Option<String>, Box<dyn std::error::Error>> {
+        Ok(self.timed(|con| con.get(key))?)
+    }
+
+    /// Valores de várias chaves com um único MGET
+    pub fn get_values(
+        &self,
+        keys: &[String],
+    ) -> Result<Vec<Option<String>>, Box<dyn std::error::Error>> {
+        if keys.is_empty() {
+            return Ok(Vec::new());
+        }
+        Ok(self.timed(|con| redis::cmd("MGET").arg(keys).query(con))?)
+    }
+
+    /// Grava a chave e registra `member` no conjunto `index` numa transação (MULTI/EXEC)
+    ///
+    /// Com `history`, o valor também entra no início da lista indicada, que é cortada
+    /// para manter apenas os `keep` valores mais recentes.
+    pub fn set_indexed(
+        &self,
+        key: &str,
+        value: &str,
+        index: &str,
+        member: &str,
+        history: Option<(&str, usize)>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut pipe = redis::pipe();
+        pipe.atomic()
+            .set(key, value)
+            .ignore()
+            .sadd(index, member)
+            .ignore();
+        if let Some((history_key, keep)) = history {
+            pipe.lpush(history_key, value)
+                .ignore()
+                .ltrim(history_key, 0, keep as isize - 1)
+                .ignore();
+        }
+        self.timed(|con| pipe.query::<()>(con))?;
+        Ok(())
+    }
+
+    /// Todos os valores de uma lista, do início ao fim
+    pub fn list_values(&self, key: &str) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        let mut con = self.client.get_connection()?;
+        Ok(con.lrange(key, 0, -1)?)
+    }
+
+    /// Remove a chave e o `member` do conjunto `index` numa transação; `true` se existia
+    pub fn delete_indexed(
+        &self,
+        key: &str,
+        index: &str,
+        member: &str,
+    ) -> Result<bool, Box<dyn std::error::Error>> {
+        let mut con = self.client.get_connection()?;
+        let (deleted, _): (usize, usize) = redis::pipe()
+            .atomic()
+            .del(key)
+            .srem(index, member)
+            .query(&mut con)?;
+        Ok(deleted > 0)
+    }
+
+    /// Membros de um conjunto
+    pub fn members(&self, index: &str) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        let mut con = self.client.get_connection()?;
+        Ok(con.smembers(index)?)
+    }
+
+    /// Assina um canal pub/sub e entrega cada mensagem ao callback
+    ///
+    /// Bloqueia até o callback retornar `false` ou a conexão cair.
+    pub fn subscribe<F>(&self, channel: &str, mut on_message: F) -> Result<(), Box<dyn std::error::Error>>
+    where
+        F: FnMut(String) -> bool,
+    {
+        let mut con = self.client.get_connection()?;
+        let mut pubsub = con.as_pubsub();
+        pubsub.subscribe(channel)?;
+        info!("📡 Assinando canal Redis {}", channel);
+
+        loop {
+            let payload: String = pubsub.get_message()?.get_payload()?;
+            if !on_message(payload) {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Lê entradas de um Redis Stream após `last_id` (XREAD), esperando até `block`
+    ///
+    /// Retorna pares (id, campo `data`) na ordem do stream.
+    pub fn read_stream(
+        &self,
+        key: &str,
+        last_id: &str,
+        count: usize,
+        block: Duration,
+    ) -> Result<Vec<(String, String)>, Box<dyn std::error::Error>> {
+        let mut con = self.client.get_connection()?;
+        let options = StreamReadOptions::default()
+            .count(count)
+            .block(block.as_millis() as usize);
+        let reply: StreamReadReply = con.xread_options(&[key], &[last_id], &options)?;
+        Ok(stream_entries(reply))
+    }
+
+    /// Publica candles num Redis Stream (XADD) no campo `data`, num único pipeline
+    ///
+    /// Com `max_len`, o stream é aparado aproximadamente nesse tamanho. Retorna os ids gerados.
+    pub fn publish_candles(
+        &self,
+        key: &str,
+        candles: &[CsvBtcFile],
+        max_len: Option<usize>,
+    ) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        if candles.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut con = self.client.get_connection()?;
+        let mut pipe = redis::pipe();
+        for candle in candles {
+            let data = serde_json::to_string(candle)?;
+            match max_len {
+                Some(len) => pipe.xadd_maxlen(key, StreamMaxlen::Approx(len), "*", &[("data", data)]),
+                None => pipe.xadd(key, "*", &[("data", data)]),
+            };
+        }
+        Ok(pipe.query(&mut con)?)
+    }
+
+    /// Publica uma mensagem num canal pub/sub; retorna quantos assinantes a receberam
+    pub fn publish_message(
+        &self,
+        channel: &str,
+        message: &str,
+    ) -> Result<usize, Box<dyn std::error::Error>> {
+        Ok(self.timed(|con| con.publish(channel, message))?)
+    }
+
+    /// Acrescenta uma entrada com o campo `data` num Redis Stream (XADD)
+    pub fn append_to_stream(
+        &self,
+        key: &str,
+        data: &str,
+        max_len: Option<usize>,
+    ) -> Result<String, Box<dyn std::error::Error>> {
+        let id: String = self.timed(|con| match max_len {
+            Some(len) => con.xadd_maxlen(key, StreamMaxlen::Approx(len), "*", &[("data", data)]),
+            None => con.xadd(key, "*", &[("data", data)]),
+        })?;
+        Ok(id)
+    }
+
+    /// Cria o grupo de consumidores (e o stream, se preciso); grupo existente não é erro
+    pub fn ensure_group(&self, key: &str, group: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let mut con = self.client.get_connection()?;
+        let result: redis::RedisResult<()> = con.xgroup_create_mkstream(key, group, "$");
+        match result {
+            Ok(()) => {
+                info!("👥 Grupo {} criado no stream {}", group, key);
+                Ok(())
             }
+            Err(e) if e.code() == Some("BUSYGROUP") => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Lê entradas do stream como membro de um grupo (XREADGROUP)
+    ///
+    /// `id` = `">"` entrega apenas entradas novas; `"0"` devolve as entregues a este
+    /// consumidor e ainda não confirmadas. Retorna pares (id, campo `data`).
+    pub fn read_group(
+        &self,
+        key: &str,
+        group: &str,
+        consumer: &str,
+        id: &str,
+        count: usize,
+        block: Duration,
+    ) -> Result<Vec<(String, String)>, Box<dyn std::error::Error>> {
+        let mut con = self.client.get_connection()?;
+        let options = StreamReadOptions::default()
+            .group(group, consumer)
+            .count(count)
+            .block(block.as_millis() as usize);
+        let reply: StreamReadReply = con.xread_options(&[key], &[id], &options)?;
+        Ok(stream_entries(reply))
+    }
+
+    /// Confirma o processamento de entradas do grupo (XACK)
+    pub fn ack(&self, key: &str, group: &str, ids: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+        if ids.is_empty() {
+            return Ok(());
         }
+        let _: usize = self.timed(|con| con.xack(key, group, ids))?;
+        Ok(())
+    }
+
+}
+
+/// Bloco lido do histórico: índice do primeiro candle e os candles (`None` nos vazios)
+pub type CandleBatch = (usize, Vec<Option<CsvBtcFile>>);
+
+/// Leitura sequencial do histórico `btc_N` em blocos, até o total gravado em `btc_count`
+///
+/// Índices vazios dentro do total são devolvidos como `None`. Sem a contagem (bases
+/// antigas), a leitura termina no primeiro bloco inteiramente vazio.
+pub struct CandleScan {
+    next: usize,
+    count: Option<usize>,
+    batch: usize,
+}
+
+impl CandleScan {
+    pub fn start(redis: &RedisClient, batch: usize) -> Result<Self, Box<dyn std::error::Error>> {
+        let count = redis.candle_count()?;
+        if count.is_none() {
+            warn!(
+                "⚠️  Chave {} ausente: o histórico termina no primeiro bloco vazio (reimporte para gravá-la)",
+                CANDLE_COUNT_KEY
+            );
+            println!(
+                "⚠️  Chave {} ausente: o histórico termina no primeiro bloco vazio (reimporte para gravá-la)",
+                CANDLE_COUNT_KEY
+            );
+        }
+        Ok(Self::with_count(count, batch))
+    }
+
+    fn with_count(count: Option<usize>, batch: usize) -> Self {
+        Self {
+            next: 0,
+            count,
+            batch,
+        }
+    }
+
+    /// Total de índices, quando conhecido
+    pub fn count(&self) -> Option<usize> {
+        self.count
+    }
+
+    /// Próximo intervalo (início, tamanho) a ler; `None` ao atingir o total
+    fn next_range(&self) -> Option<(usize, usize)> {
+        let len = match self.count {
+            Some(count) => self.batch.min(count.saturating_sub(self.next)),
+            None => self.batch,
+        };
+        (len > 0).then_some((self.next, len))
+    }
+
+    /// Próximo bloco e o índice do seu primeiro candle; `None` ao fim do histórico
+    pub fn next_batch(
+        &mut self,
+        redis: &RedisClient,
+    ) -> Result<Option<CandleBatch>, Box<dyn std::error::Error>> {
+        let Some((start, len)) = self.next_range() else {
+            return Ok(None);
+        };
+        let batch = redis.load_range(start, len)?;
+        if self.count.is_none() && batch.iter().all(Option::is_none) {
+            return Ok(None);
+        }
+        self.next = start + len;
+        Ok(Some((start, batch)))
     }
+}
 
+/// Extrai (id, campo `data`) das entradas de uma leitura de stream
+fn stream_entries(reply: StreamReadReply) -> Vec<(String, String)> {
+    reply
+        .keys
+        .into_iter()
+        .flat_map(|key| key.ids)
+        .filter_map(|entry| {
+            let data: Option<String> = entry.get("data");
+            data.map(|data| (entry.id, data))
+        })
+        .collect()
 }
 
 /// Erros personalizados para o cliente Redis
@@ -307,3 +590,33 @@ impl From<RedisError> for RedisClientError {
         RedisClientError::OperationError(err.to_string())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ranges(mut scan: CandleScan) -> Vec<(usize, usize)> {
+        let mut ranges = Vec::new();
+        while let Some((start, len)) = scan.next_range() {
+            ranges.push((start, len));
+            scan.next = start + len;
+        }
+        ranges
+    }
+
+    #[test]
+    fn scan_stops_at_the_stored_count() {
+        let scan = CandleScan::with_count(Some(25), 10);
+        assert_eq!(scan.count(), Some(25));
+        assert_eq!(ranges(scan), vec![(0, 10), (10, 10), (20, 5)]);
+        assert!(ranges(CandleScan::with_count(Some(0), 10)).is_empty());
+    }
+
+    #[test]
+    fn scan_without_count_keeps_reading_full_batches() {
+        let mut scan = CandleScan::with_count(None, 10);
+        assert_eq!(scan.next_range(), Some((0, 10)));
+        scan.next = 1_000_000;
+        assert_eq!(scan.next_range(), Some((1_000_000, 10)));
+    }
+}
@@ -0,0 +1,341 @@
+use chrono::{DateTime, Utc};
+use std::env;
+use std::thread;
+use std::time::{Duration, Instant};
+use tracing::info;
+
+use crate::candle_cache::CandleCache;
+use crate::reader_csv::{parse_timestamp, CandleStream, CsvBtcFile, CsvReadOptions, ReaderBtcFile};
+use crate::redis_client::{CandleScan, RedisClient};
+
+/// Stream padrão para o replay (`CANDLE_STREAM`)
+pub const DEFAULT_STREAM: &str = "btc_candles_stream";
+
+/// Candles enviados por pipeline
+const BATCH: usize = 500;
+
+/// Origem do histórico reproduzido no stream
+enum ReplaySource {
+    /// Candles já importados (`btc_0`, `btc_1`, ...)
+    Redis { scan: CandleScan },
+    /// CSV (arquivo, diretório ou glob, compactado ou não)
+    File {
+        path: String,
+        stream: Box<CandleStream>,
+    },
+    /// Cache binário de candles
+    Cache { cache: CandleCache, next: usize },
+}
+
+impl ReplaySource {
+    fn describe(&self, redis: &RedisClient) -> String {
+        match self {
+            ReplaySource::Redis { .. } => redis.url().to_string(),
+            ReplaySource::File { path, .. } => path.clone(),
+            ReplaySource::Cache { cache, .. } => format!("cache:{}", cache.path().display()),
+        }
+    }
+
+    /// Próximo bloco de candles em ordem; vazio ao fim do histórico
+    fn next_batch(
+        &mut self,
+        redis: &RedisClient,
+    ) -> Result<Vec<CsvBtcFile>, Box<dyn std::error::Error>> {
+        match self {
+            ReplaySource::Redis { scan } => loop {
+                let Some((_, batch)) = scan.next_batch(redis)? else {
+                    return Ok(Vec::new());
+                };
+                let candles: Vec<CsvBtcFile> = batch.into_iter().flatten().collect();
+                if !candles.is_empty() {
+                    return Ok(candles);
+                }
+            },
+            ReplaySource::File { stream, .. } => {
+                let chunk = stream.next_chunk(BATCH);
+                stream.ensure_complete()?;
+                Ok(chunk)
+            }
+            ReplaySource::Cache { cache, next } => loop {
+                // Linhas ausentes (índices sem candle) são puladas
+                if *next >= cache.len() {
+                    return Ok(Vec::new());
+                }
+                let end = (*next + BATCH).min(cache.len());
+                let candles: Vec<CsvBtcFile> = (*next..end).filter_map(|i| cache.get(i)).collect();
+                *next = end;
+                if !candles.is_empty() {
+                    return Ok(candles);
+                }
+            },
+        }
+    }
+}
+
+/// Ritmo do replay
+#[derive(Debug, Clone, Copy)]
+pub enum ReplaySpeed {
+    /// Multiplicador do tempo real dos candles (60 = um candle de 1 minuto por segundo)
+    Factor(f64),
+    /// Sem pausas
+    Max,
+}
+
+impl ReplaySpeed {
+    pub fn parse(value: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        if value.eq_ignore_ascii_case("max") {
+            return Ok(ReplaySpeed::Max);
+        }
+        let factor: f64 = value.trim_end_matches('x').parse()?;
+        if !factor.is_finite() || factor <= 0.0 {
+            return Err("--speed deve ser maior que zero ou 'max'".into());
+        }
+        Ok(ReplaySpeed::Factor(factor))
+    }
+}
+
+/// Opções do comando `replay`
+#[derive(Debug, Clone)]
+pub struct ReplayOptions {
+    pub stream: String,
+    pub speed: ReplaySpeed,
+    pub from: Option<DateTime<Utc>>,
+    pub limit: Option<usize>,
+    pub max_len: Option<usize>,
+}
+
+impl Default for ReplayOptions {
+    fn default() -> Self {
+        Self {
+            stream: env::var("CANDLE_STREAM").unwrap_or_else(|_| DEFAULT_STREAM.to_string()),
+            speed: ReplaySpeed::Factor(60.0),
+            from: None,
+            limit: None,
+            max_len: None,
+        }
+    }
+}
+
+/// Publica o histórico no Redis Stream respeitando o intervalo entre candles
+///
+/// O horário de envio de cada candle é calculado a partir do primeiro (e não do anterior),
+/// então atrasos de rede não se acumulam ao longo do replay. Retorna o total publicado.
+fn replay(
+    redis: &RedisClient,
+    mut source: ReplaySource,
+    options: &ReplayOptions,
+) -> Result<usize, Box<dyn std::error::Error>> {
+    let speed_label = match options.speed {
+        ReplaySpeed::Factor(factor) => format!("{}x", factor),
+        ReplaySpeed::Max => "máxima".to_string(),
+    };
+    info!(
+        "▶️  Replay de {} para o stream {} (velocidade {})",
+        source.describe(redis),
+        options.stream,
+        speed_label
+    );
+    println!(
+        "▶️  Replay de {} para o stream {} (velocidade {})",
+        source.describe(redis),
+        options.stream,
+        speed_label
+    );
+
+    let started = Instant::now();
+    let mut first_time: Option<DateTime<Utc>> = None;
+    let mut pending: Vec<CsvBtcFile> = Vec::with_capacity(BATCH);
+    let mut published = 0;
+    let mut last_progress = Instant::now();
+    let limit = options.limit.unwrap_or(usize::MAX);
+
+    'outer: loop {
+        let batch = source.next_batch(redis)?;
+        if batch.is_empty() {
+            break;
+        }
+        for candle in batch {
+            if options.from.is_some_and(|from| candle.timestamp < from) {
+                continue;
+            }
+            if published + pending.len() >= limit {
+                break 'outer;
+            }
+
+            let first = *first_time.get_or_insert(candle.timestamp);
+            if let ReplaySpeed::Factor(factor) = options.speed {
+                let offset = (candle.timestamp - first).num_milliseconds().max(0) as f64 / factor;
+                let due = started + Duration::from_millis(offset as u64);
+                let now = Instant::now();
+                if due > now {
+                    published += flush(redis, options, &mut pending)?;
+                    thread::sleep(due - now);
+                }
+            }
+
+            pending.push(candle);
+            if pending.len() >= BATCH {
+                published += flush(redis, options, &mut pending)?;
+            }
+
+            if last_progress.elapsed() >= Duration::from_secs(5) {
+                let rate = published as f64 / started.elapsed().as_secs_f64().max(0.001);
+                info!(
+                    "📤 {} candles publicados | {:.0} candles/s",
+                    published, rate
+                );
+                println!(
+                    "📤 {} candles publicados | {:.0} candles/s",
+                    published, rate
+                );
+                last_progress = Instant::now();
+            }
+        }
+    }
+    published += flush(redis, options, &mut pending)?;
+
+    info!(
+        "✅ Replay concluído: {} candles publicados em {} ({:.2?})",
+        published,
+        options.stream,
+        started.elapsed()
+    );
+    println!(
+        "✅ Replay concluído: {} candles publicados em {} ({:.2?})",
+        published,
+        options.stream,
+        started.elapsed()
+    );
+    Ok(published)
+}
+
+fn flush(
+    redis: &RedisClient,
+    options: &ReplayOptions,
+    pending: &mut Vec<CsvBtcFile>,
+) -> Result<usize, Box<dyn std::error::Error>> {
+    let count = redis
+        .publish_candles(&options.stream, pending, options.max_len)?
+        .len();
+    pending.clear();
+    Ok(count)
+}
+
+/// Comando `replay [--redis|--input arquivo|--cache arquivo] [--stream chave]
+/// [--speed N|max] [--from timestamp] [--limit N] [--maxlen N]`
+pub fn run_replay_command(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let mut options = ReplayOptions::default();
+    let mut input: Option<String> = None;
+    let mut cache: Option<String> = None;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--redis" => {
+                input = None;
+                cache = None;
+            }
+            "--input" => input = Some(iter.next().ok_or("--input requer um arquivo")?.clone()),
+            "--cache" => cache = Some(iter.next().ok_or("--cache requer um arquivo")?.clone()),
+            "--stream" => options.stream = iter.next().ok_or("--stream requer uma chave")?.clone(),
+            "--speed" => {
+                options.speed = ReplaySpeed::parse(iter.next().ok_or("--speed requer um valor")?)?
+            }
+            "--from" => {
+                let value = iter.next().ok_or("--from requer um timestamp")?;
+                options.from =
+                    Some(parse_timestamp(value).ok_or(format!("Timestamp inválido: {}", value))?);
+            }
+            "--limit" => {
+                options.limit = Some(iter.next().ok_or("--limit requer um valor")?.parse()?)
+            }
+            "--maxlen" => {
+                options.max_len = Some(iter.next().ok_or("--maxlen requer um valor")?.parse()?)
+            }
+            other => return Err(format!("Opção desconhecida: {}", other).into()),
+        }
+    }
+
+    let redis = RedisClient::from_env()?;
+    let source = match (input, cache) {
+        (_, Some(path)) => {
+            let cache = CandleCache::open(&path)?;
+            let next = options.from.map_or(0, |from| cache.index_at_or_after(from));
+            ReplaySource::Cache { cache, next }
+        }
+        (Some(path), None) => {
+            let stream = ReaderBtcFile::open_stream(&path, &CsvReadOptions::from_env()?)?;
+            ReplaySource::File {
+                path,
+                stream: Box::new(stream),
+            }
+        }
+        (None, None) => ReplaySource::Redis {
+            scan: CandleScan::start(&redis, BATCH)?,
+        },
+    };
+
+    replay(&redis, source, &options)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn candle(minute: i64) -> CsvBtcFile {
+        CsvBtcFile {
+            open: 100.0,
+            high: 101.0,
+            low: 99.0,
+            close: 100.5,
+            volume: 1.0,
+            timestamp: Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap()
+                + chrono::Duration::minutes(minute),
+        }
+    }
+
+    #[test]
+    fn parses_replay_speeds() {
+        assert!(matches!(
+            ReplaySpeed::parse("MAX").unwrap(),
+            ReplaySpeed::Max
+        ));
+        assert!(matches!(
+            ReplaySpeed::parse("120x").unwrap(),
+            ReplaySpeed::Factor(factor) if factor == 120.0
+        ));
+        for value in ["0", "-5", "inf", "rápido"] {
+            assert!(
+                ReplaySpeed::parse(value).is_err(),
+                "{} deveria falhar",
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn cache_source_skips_missing_rows_across_batches() {
+        let mut rows: Vec<Option<CsvBtcFile>> =
+            (0..BATCH as i64 + 10).map(|i| Some(candle(i))).collect();
+        // Um bloco inteiro sem candles no meio do histórico não encerra o replay
+        for row in rows.iter_mut().take(BATCH) {
+            *row = None;
+        }
+        let path = std::env::temp_dir().join(format!("btc-replay-{}.cache", std::process::id()));
+        let path = path.to_str().unwrap().to_string();
+        crate::candle_cache::build_from_candles(&rows, &path).unwrap();
+
+        let redis = RedisClient::offline();
+        let mut source = ReplaySource::Cache {
+            cache: CandleCache::open(&path).unwrap(),
+            next: 0,
+        };
+        let batch = source.next_batch(&redis).unwrap();
+        assert_eq!(batch.len(), 10);
+        assert_eq!(batch[0].timestamp, candle(BATCH as i64).timestamp);
+        assert!(source.next_batch(&redis).unwrap().is_empty());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
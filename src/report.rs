@@ -0,0 +1,491 @@
+use chrono::Utc;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+use crate::chart::{self, ChartMarker, ChartSeries};
+use crate::periods::{self, PeriodKind};
+use crate::run_manifest::SavedRun;
+
+const MONTHS: [&str; 12] = [
+    "Jan", "Fev", "Mar", "Abr", "Mai", "Jun", "Jul", "Ago", "Set", "Out", "Nov", "Dez",
+];
+
+fn heatmap_color(value: f64) -> String {
+    let intensity = (value.abs() / 20.0).min(1.0);
+    let (r, g, b) = if value >= 0.0 {
+        (22, 163, 74)
+    } else {
+        (220, 38, 38)
+    };
+    format!("rgba({},{},{},{:.2})", r, g, b, 0.1 + intensity * 0.8)
+}
+
+/// Gera o relatório HTML autocontido (sem recursos externos) de uma execução
+pub fn render_html(run: &SavedRun) -> String {
+    let state = &run.state;
+    let initial = state.config.initial_balance;
+    let final_value = run.final_value();
+
+    let mut html = String::new();
+    html.push_str(
+        "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>Relatório de backtest</title>\
+         <style>body{font-family:sans-serif;margin:24px;color:#111}table{border-collapse:collapse;margin-bottom:16px}\
+         td,th{border:1px solid #ddd;padding:4px 10px;text-align:right;font-size:13px}\
+         th:first-child,td:first-child{text-align:left}.buy{color:#16a34a}.sell{color:#dc2626}</style></head><body>",
+    );
+    let _ = write!(
+        html,
+        "<h1>Relatório de backtest — {}</h1><p>Gerado em {}</p>",
+        chart::escape(&run.name),
+        Utc::now().format("%Y-%m-%d %H:%M:%S UTC")
+    );
+
+    // Métricas
+    let period_start = run
+        .manifest
+        .as_ref()
+        .map(|m| m.sim_start_time)
+        .or_else(|| run.equity_curve.first().map(|p| p.time));
+    let metrics: Vec<(&str, String)> = vec![
+        (
+            "Período",
+            format!(
+                "{} → {}",
+                period_start
+                    .map(|t| t.format("%Y-%m-%d %H:%M").to_string())
+                    .unwrap_or_else(|| "?".to_string()),
+                state.current_time.format("%Y-%m-%d %H:%M")
+            ),
+        ),
+        ("Saldo inicial", format!("${:.2}", initial)),
+        ("Valor final", format!("${:.2}", final_value)),
+        ("Retorno", format!("{:+.2}%", run.return_percentage())),
+        (
+            "Lucro líquido (trades)",
+            format!("${:.2}", state.stats.net_profit()),
+        ),
+        (
+            "Total de lucros",
+            format!("${:.2}", state.stats.total_profit),
+        ),
+        ("Total de perdas", format!("${:.2}", state.stats.total_loss)),
+        (
+            "P&L de funding",
+            format!("${:.2}", state.stats.total_funding),
+        ),
+        ("Trades realizados", state.stats.total_trades.to_string()),
+        ("Trades vencedores", state.stats.winning_trades.to_string()),
+        ("Trades perdedores", state.stats.losing_trades.to_string()),
+        ("Win rate", format!("{:.1}%", state.stats.win_rate())),
+        (
+            "Drawdown máximo",
+            format!("{:.2}%", state.stats.max_drawdown),
+        ),
+        ("Saldo fiat", format!("${:.2}", state.saldo_fiat)),
+        ("Saldo BTC", format!("{:.6} BTC", state.saldo_btc)),
+        ("Total investido", format!("${:.2}", state.total_investido)),
+        ("Ordens abertas", state.buy_orders.len().to_string()),
+        (
+            "Percentual por trade",
+            format!("{:.1}%", state.config.trade_percentage),
+        ),
+        (
+            "Take profit",
+            format!("{:.1}%", state.config.take_profit_percentage),
+        ),
+        (
+            "Gatilho de compra",
+            format!("-{:.1}%", state.config.percentual_queda_para_comprar),
+        ),
+    ];
+    html.push_str("<h2>Métricas</h2><table>");
+    for (label, value) in metrics {
+        let _ = write!(
+            html,
+            "<tr><td>{}</td><td>{}</td></tr>",
+            label,
+            chart::escape(&value)
+        );
+    }
+    if let Some(manifest) = &run.manifest {
+        let _ = write!(
+            html,
+            "<tr><td>Dataset</td><td>{} - {}</td></tr><tr><td>Versão</td><td>{}</td></tr>",
+            manifest.dataset.describe(),
+            chart::escape(&manifest.dataset.source),
+            chart::escape(&manifest.binary_version)
+        );
+        if let Some(settings) = &manifest.settings {
+            let _ = write!(
+                html,
+                "<tr><td>Ambiente</td><td>{}</td></tr>",
+                chart::escape(&settings.describe())
+            );
+        }
+        if let Some(error) = &manifest.error {
+            let _ = write!(
+                html,
+                "<tr><td>Interrompida</td><td>{}</td></tr>",
+                chart::escape(error)
+            );
+        }
+    }
+    html.push_str("</table>");
+
+    // Gráficos
+    let equity = [ChartSeries {
+        label: "Valor do portfólio",
+        color: chart::COLORS[0],
+        points: run
+            .equity_curve
+            .iter()
+            .map(|p| (p.time, p.total_value))
+            .collect(),
+    }];
+    let drawdown = [ChartSeries {
+        label: "Drawdown (%)",
+        color: chart::COLORS[1],
+        points: run
+            .equity_curve
+            .iter()
+            .map(|p| (p.time, -p.drawdown))
+            .collect(),
+    }];
+    let price = [ChartSeries {
+        label: "Preço BTC",
+        color: "#6b7280",
+        points: run.equity_curve.iter().map(|p| (p.time, p.price)).collect(),
+    }];
+    let markers: Vec<ChartMarker> = state
+        .transaction_history
+        .iter()
+        .filter_map(|tx| {
+            let color = match tx.transaction_type.as_str() {
+                "BUY" => "#16a34a",
+                "SELL" => "#dc2626",
+                _ => return None,
+            };
+            Some(ChartMarker {
+                time: tx.time,
+                value: tx.price,
+                color,
+                label: format!(
+                    "{} #{} {:.6} BTC @ ${:.2} em {}",
+                    tx.transaction_type,
+                    tx.id,
+                    tx.btc_quantity,
+                    tx.price,
+                    tx.time.format("%Y-%m-%d %H:%M")
+                ),
+            })
+        })
+        .collect();
+
+    let _ = write!(
+        html,
+        "<h2>Patrimônio</h2>{}<h2>Drawdown</h2>{}<h2>Preço com compras e vendas</h2>{}",
+        chart::line_chart_svg("Valor do portfólio ($)", &equity, &[], 1000, 340),
+        chart::line_chart_svg("Drawdown (%)", &drawdown, &[], 1000, 220),
+        chart::line_chart_svg(
+            "Preço BTC ($) — compras em verde, vendas em vermelho",
+            &price,
+            &markers,
+            1000,
+            380
+        )
+    );
+
+    // Heatmap de retornos mensais
+    let returns: BTreeMap<(i32, u32), f64> = periods::aggregate(
+        PeriodKind::Month,
+        &run.equity_curve,
+        &state.transaction_history,
+        initial,
+    )
+    .into_iter()
+    .map(|p| ((p.year, p.sub_period), p.return_percentage))
+    .collect();
+    html.push_str("<h2>Retornos mensais (%)</h2><table><tr><th>Ano</th>");
+    for month in MONTHS {
+        let _ = write!(html, "<th>{}</th>", month);
+    }
+    html.push_str("</tr>");
+    let mut years: Vec<i32> = returns.keys().map(|(y, _)| *y).collect();
+    years.dedup();
+    for year in years {
+        let _ = write!(html, "<tr><td>{}</td>", year);
+        for month in 1..=12 {
+            match returns.get(&(year, month)) {
+                Some(value) => {
+                    let _ = write!(
+                        html,
+                        "<td style=\"background:{}\">{:+.2}</td>",
+                        heatmap_color(*value),
+                        value
+                    );
+                }
+                None => html.push_str("<td></td>"),
+            }
+        }
+        html.push_str("</tr>");
+    }
+    html.push_str("</table>");
+
+    for (kind, title) in [
+        (PeriodKind::Year, "Resultados anuais"),
+        (PeriodKind::Quarter, "Resultados trimestrais"),
+    ] {
+        let _ = write!(
+            html,
+            "<h2>{}</h2><table><tr><th>Período</th><th>Valor inicial</th><th>Valor final</th><th>P&amp;L realizado</th>\
+             <th>Funding</th><th>Variação não realizada</th><th>Trades</th><th>Retorno</th></tr>",
+            title
+        );
+        for p in periods::aggregate(kind, &run.equity_curve, &state.transaction_history, initial) {
+            let _ = write!(
+                html,
+                "<tr><td>{}</td><td>${:.2}</td><td>${:.2}</td><td>${:.2}</td><td>${:.2}</td><td>${:.2}</td><td>{}</td>\
+                 <td style=\"background:{}\">{:+.2}%</td></tr>",
+                p.period,
+                p.start_value,
+                p.end_value,
+                p.realised_pnl,
+                p.funding_pnl,
+                p.unrealised_change,
+                p.trade_count,
+                heatmap_color(p.return_percentage),
+                p.return_percentage
+            );
+        }
+        html.push_str("</table>");
+    }
+
+    // Ordens abertas ao final
+    let _ = write!(
+        html,
+        "<h2>Ordens abertas ao final ({})</h2>",
+        state.buy_orders.len()
+    );
+    if !state.buy_orders.is_empty() {
+        let last_price = run.last_price();
+        html.push_str(
+            "<table><tr><th>Ordem</th><th>Data</th><th>BTC</th><th>Preço</th><th>Investido</th><th>P&amp;L não realizado</th></tr>",
+        );
+        for order in &state.buy_orders {
+            let unrealized = last_price
+                .map(|p| format!("${:.2}", (p - order.buy_price) * order.btc_quantity))
+                .unwrap_or_else(|| "-".to_string());
+            let _ = write!(
+                html,
+                "<tr><td>#{}</td><td>{}</td><td>{:.6}</td><td>${:.2}</td><td>${:.2}</td><td>{}</td></tr>",
+                order.id,
+                order.buy_time.format("%Y-%m-%d %H:%M"),
+                order.btc_quantity,
+                order.buy_price,
+                order.invested_amount,
+                unrealized
+            );
+        }
+        html.push_str("</table>");
+    }
+
+    // Tabela de trades
+    let trades: Vec<_> = state
+        .transaction_history
+        .iter()
+        .filter(|tx| tx.transaction_type != "FUNDING")
+        .collect();
+    let _ = write!(html, "<h2>Transações ({})</h2>", trades.len());
+    html.push_str(
+        "<table><tr><th>#</th><th>Tipo</th><th>Data</th><th>BTC</th><th>Preço</th><th>Valor</th><th>P&amp;L</th><th>Ordem</th></tr>",
+    );
+    for tx in trades {
+        let class = if tx.transaction_type == "BUY" {
+            "buy"
+        } else {
+            "sell"
+        };
+        let _ = write!(
+            html,
+            "<tr><td>{}</td><td class=\"{}\">{}</td><td>{}</td><td>{:.6}</td><td>${:.2}</td><td>${:.2}</td><td>{}</td><td>{}</td></tr>",
+            tx.id,
+            class,
+            tx.transaction_type,
+            tx.time.format("%Y-%m-%d %H:%M"),
+            tx.btc_quantity,
+            tx.price,
+            tx.amount,
+            tx.profit_loss.map(|p| format!("${:.2}", p)).unwrap_or_default(),
+            tx.buy_order_id.map(|id| format!("#{}", id)).unwrap_or_default()
+        );
+    }
+    html.push_str("</table></body></html>");
+    html
+}
+
+pub fn write_html_report(path: &Path, run: &SavedRun) -> Result<(), Box<dyn std::error::Error>> {
+    fs::write(path, render_html(run))?;
+    Ok(())
+}
+
+/// Comando `report`: regera o relatório HTML de uma execução salva
+pub fn generate_report(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let mut reference: Option<&str> = None;
+    let mut output: Option<PathBuf> = None;
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if arg == "--output" {
+            output = iter.next().map(PathBuf::from);
+        } else {
+            reference = Some(arg.as_str());
+        }
+    }
+
+    let reference =
+        reference.ok_or("Informe a execução (run id, diretório ou arquivo de estado)")?;
+    let run = SavedRun::load(reference)?;
+
+    let output = output.unwrap_or_else(|| match &run.manifest {
+        Some(manifest) => manifest.files.report.clone(),
+        None => PathBuf::from(format!(
+            "report-{}.html",
+            Utc::now().format("%Y%m%d-%H%M%S")
+        )),
+    });
+    write_html_report(&output, &run)?;
+
+    info!("📄 Relatório HTML salvo em {}", output.display());
+    println!("📄 Relatório HTML salvo em {}", output.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trade_btc::{
+        BuyOrder, EquityPoint, SimulationState, TradeConfig, TradeStats, Transaction,
+    };
+    use chrono::{DateTime, TimeZone};
+
+    fn at(day: i64) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap() + chrono::Duration::days(day)
+    }
+
+    fn transaction(
+        id: u32,
+        kind: &str,
+        day: i64,
+        price: f64,
+        profit_loss: Option<f64>,
+    ) -> Transaction {
+        Transaction {
+            id,
+            transaction_type: kind.to_string(),
+            btc_quantity: 0.01,
+            price,
+            time: at(day),
+            amount: 0.01 * price,
+            profit_loss,
+            buy_order_id: (kind == "SELL").then_some(1),
+        }
+    }
+
+    fn run() -> SavedRun {
+        let config = TradeConfig::default();
+        let equity_curve = [(0, 10_000.0), (30, 11_000.0), (59, 9_900.0)]
+            .into_iter()
+            .map(|(day, total_value)| EquityPoint {
+                time: at(day),
+                price: 40_000.0,
+                saldo_fiat: total_value - 400.0,
+                saldo_btc: 0.01,
+                total_value,
+                drawdown: 0.0,
+            })
+            .collect();
+        SavedRun {
+            name: "<execução>".to_string(),
+            manifest: None,
+            state: SimulationState {
+                config: config.clone(),
+                stats: TradeStats::new(config.initial_balance),
+                current_time: at(59),
+                end_time: at(59),
+                data_index: 59,
+                total_records: 59,
+                saldo_fiat: 9_500.0,
+                saldo_btc: 0.01,
+                preco_anterior: Some(45_000.0),
+                preco_pico_recente: 45_000.0,
+                total_investido: 400.0,
+                buy_orders: vec![BuyOrder {
+                    id: 2,
+                    btc_quantity: 0.01,
+                    buy_price: 40_000.0,
+                    buy_time: at(40),
+                    invested_amount: 400.0,
+                }],
+                transaction_history: vec![
+                    transaction(1, "BUY", 5, 39_000.0, None),
+                    transaction(2, "SELL", 10, 41_000.0, Some(20.0)),
+                    transaction(3, "FUNDING", 20, 0.0, None),
+                    transaction(4, "BUY", 40, 40_000.0, None),
+                ],
+                next_order_id: 3,
+                next_transaction_id: 5,
+                quedas_detectadas: 0,
+                quedas_para_comprar: 3,
+                next_funding_time: None,
+                equity_curve: Vec::new(),
+                last_equity_time: None,
+                equity_file: None,
+                benchmarks_file: None,
+                benchmarks: Vec::new(),
+            },
+            equity_curve,
+        }
+    }
+
+    #[test]
+    fn report_lists_metrics_trades_and_open_orders() {
+        let html = render_html(&run());
+
+        assert!(html.contains("<h1>Relatório de backtest — &lt;execução&gt;</h1>"));
+        assert!(html.contains("<tr><td>Valor final</td><td>$9950.00</td></tr>"));
+        assert!(html.contains("<tr><td>Retorno</td><td>-0.50%</td></tr>"));
+        // Funding aparece nos períodos, mas não na tabela de transações
+        assert!(html.contains("<h2>Transações (3)</h2>"));
+        assert!(!html.contains(">FUNDING<"));
+        // Compras e vendas viram marcadores no gráfico de preço
+        assert_eq!(html.matches("<circle").count(), 3);
+        // P&L não realizado ao último preço processado
+        assert!(html.contains("<h2>Ordens abertas ao final (1)</h2>"));
+        assert!(html.contains("<td>$400.00</td><td>$50.00</td></tr>"));
+        assert!(html.ends_with("</table></body></html>"));
+    }
+
+    #[test]
+    fn monthly_heatmap_fills_only_months_with_data() {
+        let html = render_html(&run());
+
+        let row = html
+            .split("<tr><td>2024</td>")
+            .nth(1)
+            .and_then(|rest| rest.split("</tr>").next())
+            .unwrap();
+        assert_eq!(row.matches("<td style=").count(), 2);
+        assert_eq!(row.matches("<td></td>").count(), 10);
+        assert!(row.contains(">+10.00</td>"));
+        assert!(row.contains(">-10.00</td>"));
+    }
+
+    #[test]
+    fn heatmap_color_saturates_at_twenty_percent() {
+        assert_eq!(heatmap_color(0.0), "rgba(22,163,74,0.10)");
+        assert_eq!(heatmap_color(-10.0), "rgba(220,38,38,0.50)");
+        assert_eq!(heatmap_color(50.0), heatmap_color(20.0));
+    }
+}
@@ -0,0 +1,376 @@
+use std::env;
+use std::fs::{self, File, TryLockError};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::state_store::{validate_run_id, StateStore, DEFAULT_RUN_ID};
+
+/// Diretório dos logs diários
+pub const LOG_DIR: &str = "logs";
+
+/// Simulação identificada por nome, com PID, estado e log próprios
+///
+/// O nome é o mesmo id do estado salvo (`STATE_RUN_ID`), então `daemon --name agressiva`
+/// usa `simulation.agressiva.pid`, o estado `agressiva` e `logs/btc_trading.agressiva.log.<data>`.
+/// A simulação `default` mantém os nomes de arquivo de antes.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct NamedSimulation {
+    name: String,
+}
+
+impl Default for NamedSimulation {
+    fn default() -> Self {
+        Self {
+            name: DEFAULT_RUN_ID.to_string(),
+        }
+    }
+}
+
+impl NamedSimulation {
+    pub fn new(name: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        validate_run_id(name)?;
+        Ok(Self {
+            name: name.to_string(),
+        })
+    }
+
+    /// Simulação do processo atual (`STATE_RUN_ID`, padrão `default`)
+    pub fn from_env() -> Result<Self, Box<dyn std::error::Error>> {
+        match env::var("STATE_RUN_ID") {
+            Ok(name) => Self::new(&name),
+            Err(_) => Ok(Self::default()),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn is_default(&self) -> bool {
+        self.name == DEFAULT_RUN_ID
+    }
+
+    pub fn pid_path(&self) -> PathBuf {
+        if self.is_default() {
+            PathBuf::from("simulation.pid")
+        } else {
+            PathBuf::from(format!("simulation.{}.pid", self.name))
+        }
+    }
+
+    /// Arquivo travado pelo processo da simulação enquanto ele roda
+    pub fn lock_path(&self) -> PathBuf {
+        if self.is_default() {
+            PathBuf::from("simulation.lock")
+        } else {
+            PathBuf::from(format!("simulation.{}.lock", self.name))
+        }
+    }
+
+    /// Socket do canal de controle, aberto pelo processo da simulação enquanto ele roda
+    pub fn control_path(&self) -> PathBuf {
+        if self.is_default() {
+            PathBuf::from("simulation.sock")
+        } else {
+            PathBuf::from(format!("simulation.{}.sock", self.name))
+        }
+    }
+
+    /// Prefixo dos arquivos de log diários (o appender acrescenta `.AAAA-MM-DD`)
+    pub fn log_prefix(&self) -> String {
+        if self.is_default() {
+            "btc_trading.log".to_string()
+        } else {
+            format!("btc_trading.{}.log", self.name)
+        }
+    }
+
+    pub fn today_log(&self) -> PathBuf {
+        Path::new(LOG_DIR).join(format!(
+            "{}.{}",
+            self.log_prefix(),
+            chrono::Utc::now().format("%Y-%m-%d")
+        ))
+    }
+
+    /// Todos os logs diários da simulação (rotacionados e o de hoje), do mais antigo ao mais novo
+    pub fn log_files(&self) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+        if !Path::new(LOG_DIR).exists() {
+            return Ok(Vec::new());
+        }
+        let prefix = format!("{}.", self.log_prefix());
+        let mut files = Vec::new();
+        for entry in fs::read_dir(LOG_DIR)? {
+            let path = entry?.path();
+            let dated = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.strip_prefix(&prefix))
+                .is_some_and(|date| chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d").is_ok());
+            if dated {
+                files.push(path);
+            }
+        }
+        files.sort();
+        Ok(files)
+    }
+
+    pub fn state_store(&self) -> Result<StateStore, Box<dyn std::error::Error>> {
+        StateStore::from_env_with_run_id(&self.name)
+    }
+
+    /// PID gravado pelo `daemon`, se houver
+    pub fn pid(&self) -> Option<u32> {
+        fs::read_to_string(self.pid_path())
+            .ok()?
+            .trim()
+            .parse()
+            .ok()
+    }
+
+    /// PID do processo que está rodando a simulação: o do `daemon` (ou do supervisor)
+    /// quando ele ainda existe, senão o gravado no lock (ex.: `simulate` no terminal)
+    pub fn running_pid(&self) -> Option<u32> {
+        if let Some(pid) = self.pid().filter(|pid| process_alive(*pid)) {
+            return Some(pid);
+        }
+        if !self.is_running() {
+            return None;
+        }
+        fs::read_to_string(self.lock_path())
+            .ok()?
+            .trim()
+            .parse()
+            .ok()
+    }
+
+    /// `true` enquanto algum processo mantém o lock da simulação
+    pub fn is_running(&self) -> bool {
+        let Ok(file) = File::open(self.lock_path()) else {
+            return false;
+        };
+        matches!(file.try_lock(), Err(TryLockError::WouldBlock))
+    }
+
+    /// PID gravado cujo processo não existe mais (simulação que caiu ou foi morta)
+    pub fn stale_pid(&self) -> Option<u32> {
+        self.pid().filter(|pid| !process_alive(*pid))
+    }
+
+    /// Simulações conhecidas: com arquivo de PID, log de hoje ou estado salvo no backend configurado
+    pub fn all() -> Result<Vec<Self>, Box<dyn std::error::Error>> {
+        let mut simulations = Vec::new();
+        for entry in fs::read_dir(".")? {
+            let path = entry?.path();
+            if let Some(simulation) = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(pid_file_simulation)
+            {
+                simulations.push(simulation);
+            }
+        }
+        if Path::new(LOG_DIR).exists() {
+            let suffix = format!(".{}", chrono::Utc::now().format("%Y-%m-%d"));
+            for entry in fs::read_dir(LOG_DIR)? {
+                let file_name = entry?.file_name();
+                if let Some(simulation) = file_name
+                    .to_str()
+                    .and_then(|name| name.strip_suffix(&suffix))
+                    .and_then(log_file_simulation)
+                {
+                    simulations.push(simulation);
+                }
+            }
+        }
+        for saved in StateStore::from_env()?.list()? {
+            simulations.push(Self::new(&saved.run_id)?);
+        }
+        simulations.sort();
+        simulations.dedup();
+        Ok(simulations)
+    }
+}
+
+/// Simulação dona de um arquivo `simulation.pid` / `simulation.<nome>.pid`
+fn pid_file_simulation(file_name: &str) -> Option<NamedSimulation> {
+    if file_name == "simulation.pid" {
+        return Some(NamedSimulation::default());
+    }
+    let name = file_name
+        .strip_prefix("simulation.")?
+        .strip_suffix(".pid")?;
+    NamedSimulation::new(name).ok()
+}
+
+/// Simulação dona de um log `btc_trading.log` / `btc_trading.<nome>.log` (sem a data)
+fn log_file_simulation(prefix: &str) -> Option<NamedSimulation> {
+    if prefix == "btc_trading.log" {
+        return Some(NamedSimulation::default());
+    }
+    let name = prefix.strip_prefix("btc_trading.")?.strip_suffix(".log")?;
+    NamedSimulation::new(name).ok()
+}
+
+/// Lê um arquivo no formato do `.env` (`CHAVE=valor`, `#` para comentários)
+///
+/// Os valores valem só para o processo iniciado, por cima do `.env` do diretório.
+pub fn read_env_file(path: &str) -> Result<Vec<(String, String)>, Box<dyn std::error::Error>> {
+    let mut vars = Vec::new();
+    for (number, line) in fs::read_to_string(path)?.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| format!("{}:{}: linha sem '='", path, number + 1))?;
+        let value = value.trim();
+        let value = value
+            .strip_prefix('"')
+            .and_then(|v| v.strip_suffix('"'))
+            .or_else(|| value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')))
+            .unwrap_or(value);
+        vars.push((key.trim().to_string(), value.to_string()));
+    }
+    Ok(vars)
+}
+
+/// Pede o encerramento do processo (SIGTERM), que salva o estado antes de sair
+pub fn terminate(pid: u32) -> Result<(), Box<dyn std::error::Error>> {
+    #[cfg(unix)]
+    {
+        Command::new("kill").arg(pid.to_string()).output()?;
+    }
+
+    #[cfg(windows)]
+    {
+        Command::new("taskkill")
+            .args(["/PID", &pid.to_string(), "/F"])
+            .output()?;
+    }
+    Ok(())
+}
+
+/// `true` se o processo existe; no Linux, processos zumbis (já encerrados, à espera do
+/// `wait` do pai) contam como encerrados
+pub fn process_alive(pid: u32) -> bool {
+    #[cfg(target_os = "linux")]
+    {
+        // O estado vem depois do nome do executável, que fica entre parênteses
+        fs::read_to_string(format!("/proc/{}/stat", pid))
+            .ok()
+            .and_then(|stat| {
+                stat.rsplit_once(')')
+                    .map(|(_, rest)| !rest.trim_start().starts_with('Z'))
+            })
+            .unwrap_or(false)
+    }
+
+    #[cfg(all(unix, not(target_os = "linux")))]
+    {
+        Command::new("kill")
+            .args(["-0", &pid.to_string()])
+            .output()
+            .map(|output| output.status.success())
+            .unwrap_or(false)
+    }
+
+    #[cfg(windows)]
+    {
+        Command::new("tasklist")
+            .args(["/FI", &format!("PID eq {}", pid)])
+            .output()
+            .map(|output| String::from_utf8_lossy(&output.stdout).contains(&pid.to_string()))
+            .unwrap_or(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_simulation_keeps_the_legacy_file_names() {
+        let simulation = NamedSimulation::default();
+        assert_eq!(simulation.name(), DEFAULT_RUN_ID);
+        assert_eq!(simulation.pid_path(), Path::new("simulation.pid"));
+        assert_eq!(simulation.lock_path(), Path::new("simulation.lock"));
+        assert_eq!(simulation.control_path(), Path::new("simulation.sock"));
+        assert_eq!(simulation.log_prefix(), "btc_trading.log");
+    }
+
+    #[test]
+    fn named_simulation_files_carry_the_name() {
+        let simulation = NamedSimulation::new("agressiva").unwrap();
+        assert_eq!(simulation.pid_path(), Path::new("simulation.agressiva.pid"));
+        assert_eq!(
+            simulation.lock_path(),
+            Path::new("simulation.agressiva.lock")
+        );
+        assert_eq!(
+            simulation.control_path(),
+            Path::new("simulation.agressiva.sock")
+        );
+        assert_eq!(simulation.log_prefix(), "btc_trading.agressiva.log");
+        let today = simulation.today_log();
+        assert_eq!(today.parent(), Some(Path::new(LOG_DIR)));
+        let name = today.file_name().unwrap().to_str().unwrap();
+        let date = name.strip_prefix("btc_trading.agressiva.log.").unwrap();
+        assert!(chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d").is_ok());
+        assert!(NamedSimulation::new("../fora").is_err());
+    }
+
+    #[test]
+    fn simulations_are_recognised_from_pid_and_log_names() {
+        let named = NamedSimulation::new("agressiva").unwrap();
+        assert_eq!(
+            pid_file_simulation("simulation.pid"),
+            Some(NamedSimulation::default())
+        );
+        assert_eq!(
+            pid_file_simulation("simulation.agressiva.pid"),
+            Some(named.clone())
+        );
+        assert_eq!(pid_file_simulation("simulation.lock"), None);
+        assert_eq!(pid_file_simulation("simulation.a b.pid"), None);
+
+        assert_eq!(
+            log_file_simulation("btc_trading.log"),
+            Some(NamedSimulation::default())
+        );
+        assert_eq!(
+            log_file_simulation("btc_trading.agressiva.log"),
+            Some(named)
+        );
+        assert_eq!(log_file_simulation("outro.log"), None);
+    }
+
+    #[test]
+    fn env_file_accepts_comments_exports_and_quotes() {
+        let path = env::temp_dir().join(format!("btc-env-{}.env", std::process::id()));
+        fs::write(
+            &path,
+            "# comentário\n\nSTATE_RUN_ID=papel\nexport LIVE_SOURCE = \"stream:btc\"\nNOTA='a=b'\nVAZIO=\n",
+        )
+        .unwrap();
+        let vars = read_env_file(path.to_str().unwrap()).unwrap();
+        assert_eq!(
+            vars,
+            vec![
+                ("STATE_RUN_ID".to_string(), "papel".to_string()),
+                ("LIVE_SOURCE".to_string(), "stream:btc".to_string()),
+                ("NOTA".to_string(), "a=b".to_string()),
+                ("VAZIO".to_string(), String::new()),
+            ]
+        );
+
+        fs::write(&path, "OK=1\nsem_igual\n").unwrap();
+        let error = read_env_file(path.to_str().unwrap())
+            .unwrap_err()
+            .to_string();
+        assert!(error.ends_with(":2: linha sem '='"), "{}", error);
+        fs::remove_file(&path).unwrap();
+    }
+}
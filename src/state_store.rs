@@ -0,0 +1,786 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::value::RawValue;
+use serde_json::Value;
+use std::env;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+use crate::redis_client::RedisClient;
+use crate::trade_btc::SimulationState;
+
+/// Identificador usado quando nenhum é informado (mantém o `simulation_state.json` antigo)
+pub const DEFAULT_RUN_ID: &str = "default";
+
+/// Prefixo das chaves de estado no Redis (`sim_state:<run_id>`)
+const REDIS_PREFIX: &str = "sim_state";
+
+/// Conjunto com os ids de todas as simulações salvas no Redis
+const REDIS_INDEX: &str = "sim_state:runs";
+
+/// Versão atual do formato do estado salvo
+pub const SCHEMA_VERSION: u32 = 2;
+
+/// Snapshots mantidos por simulação quando `STATE_SNAPSHOTS` não é informado
+const DEFAULT_SNAPSHOTS: usize = 5;
+
+/// Diretório dos snapshots do backend em arquivo (`state_snapshots/<run_id>/`)
+const SNAPSHOT_DIR: &str = "state_snapshots";
+
+/// Migrações do estado salvo: `MIGRATIONS[i]` leva da versão `i + 1` para a `i + 2`
+const MIGRATIONS: &[fn(&mut Value)] = &[migrate_v1_to_v2];
+
+/// A v1 era o `SimulationState` gravado diretamente, sem envelope; os campos
+/// adicionados depois dela recebem os valores padrão
+fn migrate_v1_to_v2(state: &mut Value) {
+    if let Some(fields) = state.as_object_mut() {
+        fields.entry("next_funding_time").or_insert(Value::Null);
+        fields
+            .entry("equity_curve")
+            .or_insert_with(|| Value::Array(Vec::new()));
+        fields
+            .entry("benchmarks")
+            .or_insert_with(|| Value::Array(Vec::new()));
+    }
+}
+
+/// Envelope gravado a cada salvamento; o checksum cobre exatamente os bytes de `state`
+#[derive(Serialize)]
+struct Envelope<'a> {
+    schema_version: u32,
+    checksum: String,
+    saved_at: DateTime<Utc>,
+    state: &'a RawValue,
+}
+
+/// Leitura do envelope; sem `schema_version` o conteúdo é um estado v1
+#[derive(Deserialize)]
+struct StoredEnvelope<'a> {
+    schema_version: Option<u32>,
+    checksum: Option<String>,
+    saved_at: Option<DateTime<Utc>>,
+    #[serde(borrow)]
+    state: Option<&'a RawValue>,
+}
+
+/// Estado lido e validado, já migrado para a versão atual
+pub struct Snapshot {
+    pub schema_version: u32,
+    pub saved_at: Option<DateTime<Utc>>,
+    pub state: SimulationState,
+}
+
+/// Snapshot guardado pela retenção; `snapshot` traz o erro quando ele não pôde ser lido
+pub struct SnapshotEntry {
+    pub id: String,
+    pub snapshot: Result<Snapshot, String>,
+}
+
+fn checksum(data: &str) -> String {
+    format!("crc32:{:08x}", crc32fast::hash(data.as_bytes()))
+}
+
+fn encode(state: &SimulationState) -> Result<(String, DateTime<Utc>), Box<dyn std::error::Error>> {
+    let body = RawValue::from_string(serde_json::to_string_pretty(state)?)?;
+    let saved_at = Utc::now();
+    let envelope = Envelope {
+        schema_version: SCHEMA_VERSION,
+        checksum: checksum(body.get()),
+        saved_at,
+        state: &body,
+    };
+    Ok((serde_json::to_string_pretty(&envelope)?, saved_at))
+}
+
+fn decode(data: &str) -> Result<Snapshot, Box<dyn std::error::Error>> {
+    let envelope: StoredEnvelope = serde_json::from_str(data)?;
+    let (schema_version, mut state): (u32, Value) = match envelope.schema_version {
+        None => (1, serde_json::from_str(data)?),
+        Some(version) => {
+            let body = envelope.state.ok_or("campo state ausente")?;
+            let expected = envelope.checksum.ok_or("campo checksum ausente")?;
+            let actual = checksum(body.get());
+            if expected != actual {
+                return Err(format!(
+                    "checksum não confere (gravado {}, calculado {})",
+                    expected, actual
+                )
+                .into());
+            }
+            (version, serde_json::from_str(body.get())?)
+        }
+    };
+    if schema_version == 0 || schema_version > SCHEMA_VERSION {
+        return Err(format!(
+            "versão {} do estado não suportada (atual: {})",
+            schema_version, SCHEMA_VERSION
+        )
+        .into());
+    }
+    for migrate in &MIGRATIONS[schema_version as usize - 1..] {
+        migrate(&mut state);
+    }
+    Ok(Snapshot {
+        schema_version,
+        saved_at: envelope.saved_at,
+        state: serde_json::from_value(state)?,
+    })
+}
+
+/// Lê um estado salvo em arquivo, com ou sem envelope (como o `state.json` das execuções)
+pub fn read_state_file(path: &Path) -> Result<Snapshot, Box<dyn std::error::Error>> {
+    decode(&fs::read_to_string(path)?)
+}
+
+/// Grava num arquivo temporário ao lado do destino e renomeia por cima dele, então
+/// uma queda no meio da escrita nunca deixa o estado pela metade
+fn write_atomic(path: &Path, data: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let tmp = path.with_extension("json.tmp");
+    let mut file = File::create(&tmp)?;
+    file.write_all(data.as_bytes())?;
+    file.sync_all()?;
+    fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+fn snapshot_id(saved_at: DateTime<Utc>) -> String {
+    saved_at.format("%Y%m%d-%H%M%S").to_string()
+}
+
+enum Backend {
+    /// Arquivo JSON no diretório atual
+    File {
+        path: PathBuf,
+        snapshot_dir: PathBuf,
+    },
+    /// Chave `sim_state:<run_id>` no Redis, com os snapshots em `sim_state:<run_id>:snapshots`
+    Redis(RedisClient),
+}
+
+/// Onde o estado da simulação é persistido
+///
+/// Cada simulação é identificada por um `run_id`, então várias simulações podem
+/// salvar estado no mesmo host (ou no mesmo Redis) sem sobrescrever umas às outras.
+/// Os últimos `keep_snapshots` salvamentos ficam guardados para o comando `restore`.
+pub struct StateStore {
+    run_id: String,
+    keep_snapshots: usize,
+    backend: Backend,
+}
+
+impl Default for StateStore {
+    fn default() -> Self {
+        Self::file(DEFAULT_RUN_ID)
+    }
+}
+
+/// Resumo de um estado salvo, para listagem
+#[derive(Debug)]
+pub struct SavedStateInfo {
+    pub run_id: String,
+    pub location: String,
+    pub current_time: DateTime<Utc>,
+    pub data_index: usize,
+    pub saldo_fiat: f64,
+    pub saldo_btc: f64,
+    pub total_trades: u32,
+}
+
+impl SavedStateInfo {
+    fn from_state(run_id: &str, location: String, state: &SimulationState) -> Self {
+        Self {
+            run_id: run_id.to_string(),
+            location,
+            current_time: state.current_time,
+            data_index: state.data_index,
+            saldo_fiat: state.saldo_fiat,
+            saldo_btc: state.saldo_btc,
+            total_trades: state.stats.total_trades,
+        }
+    }
+}
+
+impl StateStore {
+    /// Arquivo `simulation_state.json` (id padrão) ou `simulation_state.<run_id>.json`
+    pub fn file(run_id: &str) -> Self {
+        let path = if run_id == DEFAULT_RUN_ID {
+            PathBuf::from("simulation_state.json")
+        } else {
+            PathBuf::from(format!("simulation_state.{}.json", run_id))
+        };
+        StateStore {
+            run_id: run_id.to_string(),
+            keep_snapshots: DEFAULT_SNAPSHOTS,
+            backend: Backend::File {
+                path,
+                snapshot_dir: Path::new(SNAPSHOT_DIR).join(run_id),
+            },
+        }
+    }
+
+    pub fn redis(redis: RedisClient, run_id: &str) -> Self {
+        StateStore {
+            run_id: run_id.to_string(),
+            keep_snapshots: DEFAULT_SNAPSHOTS,
+            backend: Backend::Redis(redis),
+        }
+    }
+
+    /// Quantos salvamentos anteriores manter (0 desliga os snapshots)
+    pub fn with_snapshots(mut self, keep: usize) -> Self {
+        self.keep_snapshots = keep;
+        self
+    }
+
+    /// Lê `STATE_BACKEND` (`file` ou `redis`, padrão `file`) e `STATE_RUN_ID`
+    pub fn from_env() -> Result<Self, Box<dyn std::error::Error>> {
+        let run_id = env::var("STATE_RUN_ID").unwrap_or_else(|_| DEFAULT_RUN_ID.to_string());
+        Self::from_env_with_run_id(&run_id)
+    }
+
+    /// Backend de `STATE_BACKEND` com um id escolhido pelo chamador
+    pub fn from_env_with_run_id(run_id: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        validate_run_id(run_id)?;
+        let store = match env::var("STATE_BACKEND").as_deref() {
+            Ok("redis") => Self::redis(RedisClient::from_env()?, run_id),
+            Ok("file") | Err(_) => Self::file(run_id),
+            Ok(other) => {
+                return Err(format!("STATE_BACKEND inválido: {} (use file ou redis)", other).into())
+            }
+        };
+        match env::var("STATE_SNAPSHOTS") {
+            Ok(keep) => Ok(store.with_snapshots(keep.parse()?)),
+            Err(_) => Ok(store),
+        }
+    }
+
+    pub fn run_id(&self) -> &str {
+        &self.run_id
+    }
+
+    pub fn describe(&self) -> String {
+        match &self.backend {
+            Backend::File { path, .. } => path.display().to_string(),
+            Backend::Redis(redis) => format!("{} ({})", redis_key(&self.run_id), redis.url()),
+        }
+    }
+
+    /// Estado salvo desta simulação, se existir
+    ///
+    /// Estados de versões anteriores são migrados; checksum inválido é erro, e o
+    /// comando `restore` permite voltar a um snapshot anterior.
+    pub fn load(&self) -> Result<Option<SimulationState>, Box<dyn std::error::Error>> {
+        let data = match &self.backend {
+            Backend::File { path, .. } => {
+                if !path.exists() {
+                    return Ok(None);
+                }
+                fs::read_to_string(path)?
+            }
+            Backend::Redis(redis) => match redis.get_value(&redis_key(&self.run_id))? {
+                Some(data) => data,
+                None => return Ok(None),
+            },
+        };
+        let snapshot = decode(&data)
+            .map_err(|e| format!("Estado salvo em {} inválido: {}", self.describe(), e))?;
+        if snapshot.schema_version < SCHEMA_VERSION {
+            info!(
+                "🔄 Estado migrado da versão {} para a {}",
+                snapshot.schema_version, SCHEMA_VERSION
+            );
+            println!(
+                "🔄 Estado migrado da versão {} para a {}",
+                snapshot.schema_version, SCHEMA_VERSION
+            );
+        }
+        Ok(Some(snapshot.state))
+    }
+
+    /// Grava o estado e guarda o salvamento entre os snapshots
+    ///
+    /// Em arquivo a escrita é feita em arquivo temporário + rename; no Redis, chave,
+    /// índice e lista de snapshots são atualizados numa única transação.
+    pub fn save(&self, state: &SimulationState) -> Result<(), Box<dyn std::error::Error>> {
+        let (data, saved_at) = encode(state)?;
+        match &self.backend {
+            Backend::File { path, snapshot_dir } => {
+                write_atomic(path, &data)?;
+                if self.keep_snapshots > 0 {
+                    if let Err(e) = self.keep_file_snapshot(path, snapshot_dir, saved_at) {
+                        warn!("⚠️  Falha ao guardar snapshot do estado: {}", e);
+                    }
+                }
+            }
+            Backend::Redis(redis) => {
+                let history = snapshots_key(&self.run_id);
+                redis.set_indexed(
+                    &redis_key(&self.run_id),
+                    &data,
+                    REDIS_INDEX,
+                    &self.run_id,
+                    (self.keep_snapshots > 0).then_some((history.as_str(), self.keep_snapshots)),
+                )?
+            }
+        }
+        Ok(())
+    }
+
+    /// Liga o arquivo recém-gravado no diretório de snapshots e apaga os mais antigos
+    fn keep_file_snapshot(
+        &self,
+        path: &Path,
+        snapshot_dir: &Path,
+        saved_at: DateTime<Utc>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        fs::create_dir_all(snapshot_dir)?;
+        let target = snapshot_dir.join(format!("{}.json", snapshot_id(saved_at)));
+        if target.exists() {
+            fs::remove_file(&target)?;
+        }
+        // O rename do próximo salvamento cria um novo arquivo, então o link não muda depois
+        if fs::hard_link(path, &target).is_err() {
+            fs::copy(path, &target)?;
+        }
+
+        let files = snapshot_files(snapshot_dir)?;
+        for old in files.iter().skip(self.keep_snapshots) {
+            fs::remove_file(old)?;
+        }
+        Ok(())
+    }
+
+    /// Snapshots guardados, do mais recente para o mais antigo
+    pub fn snapshots(&self) -> Result<Vec<SnapshotEntry>, Box<dyn std::error::Error>> {
+        let mut entries = Vec::new();
+        match &self.backend {
+            Backend::File { snapshot_dir, .. } => {
+                if !snapshot_dir.exists() {
+                    return Ok(entries);
+                }
+                for path in snapshot_files(snapshot_dir)? {
+                    let id = path
+                        .file_stem()
+                        .and_then(|stem| stem.to_str())
+                        .unwrap_or_default()
+                        .to_string();
+                    let snapshot = fs::read_to_string(&path)
+                        .map_err(|e| e.to_string())
+                        .and_then(|data| decode(&data).map_err(|e| e.to_string()));
+                    entries.push(SnapshotEntry { id, snapshot });
+                }
+            }
+            Backend::Redis(redis) => {
+                for (position, data) in redis
+                    .list_values(&snapshots_key(&self.run_id))?
+                    .into_iter()
+                    .enumerate()
+                {
+                    let snapshot = decode(&data).map_err(|e| e.to_string());
+                    let id = match &snapshot {
+                        Ok(Snapshot {
+                            saved_at: Some(saved_at),
+                            ..
+                        }) => snapshot_id(*saved_at),
+                        _ => format!("invalido-{}", position + 1),
+                    };
+                    entries.push(SnapshotEntry { id, snapshot });
+                }
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Volta o estado para um snapshot, pelo id ou pela posição na listagem (1 = mais recente)
+    ///
+    /// O estado restaurado é salvo normalmente, tornando-se também o snapshot mais recente.
+    pub fn restore(&self, id: &str) -> Result<Snapshot, Box<dyn std::error::Error>> {
+        let entries = self.snapshots()?;
+        let entry = match id.parse::<usize>() {
+            Ok(position) if position >= 1 => entries.into_iter().nth(position - 1),
+            _ => entries.into_iter().find(|entry| entry.id == id),
+        }
+        .ok_or_else(|| format!("Snapshot {} não encontrado para '{}'", id, self.run_id))?;
+        let snapshot = entry
+            .snapshot
+            .map_err(|e| format!("Snapshot {} inválido: {}", entry.id, e))?;
+        self.save(&snapshot.state)?;
+        Ok(snapshot)
+    }
+
+    /// Remove o estado salvo; retorna `true` se havia algo para remover
+    ///
+    /// Os snapshots são mantidos, então um `clear` por engano ainda pode ser desfeito.
+    pub fn clear(&self) -> Result<bool, Box<dyn std::error::Error>> {
+        match &self.backend {
+            Backend::File { path, .. } => {
+                if path.exists() {
+                    fs::remove_file(path)?;
+                    Ok(true)
+                } else {
+                    Ok(false)
+                }
+            }
+            Backend::Redis(redis) => {
+                redis.delete_indexed(&redis_key(&self.run_id), REDIS_INDEX, &self.run_id)
+            }
+        }
+    }
+
+    /// Todos os estados salvos no mesmo backend
+    pub fn list(&self) -> Result<Vec<SavedStateInfo>, Box<dyn std::error::Error>> {
+        let mut saved = Vec::new();
+        match &self.backend {
+            Backend::File { .. } => {
+                for entry in fs::read_dir(".")? {
+                    let path = entry?.path();
+                    let Some(run_id) = file_run_id(&path) else {
+                        continue;
+                    };
+                    if let Ok(snapshot) = decode(&fs::read_to_string(&path)?) {
+                        saved.push(SavedStateInfo::from_state(
+                            &run_id,
+                            path.strip_prefix(".")
+                                .unwrap_or(&path)
+                                .display()
+                                .to_string(),
+                            &snapshot.state,
+                        ));
+                    }
+                }
+            }
+            Backend::Redis(redis) => {
+                let mut run_ids = redis.members(REDIS_INDEX)?;
+                run_ids.sort();
+                let keys: Vec<String> = run_ids.iter().map(|id| redis_key(id)).collect();
+                for (run_id, data) in run_ids.iter().zip(redis.get_values(&keys)?) {
+                    if let Some(snapshot) = data.and_then(|d| decode(&d).ok()) {
+                        saved.push(SavedStateInfo::from_state(
+                            run_id,
+                            redis_key(run_id),
+                            &snapshot.state,
+                        ));
+                    }
+                }
+            }
+        }
+        saved.sort_by(|a, b| a.run_id.cmp(&b.run_id));
+        Ok(saved)
+    }
+}
+
+fn redis_key(run_id: &str) -> String {
+    format!("{}:{}", REDIS_PREFIX, run_id)
+}
+
+fn snapshots_key(run_id: &str) -> String {
+    format!("{}:{}:snapshots", REDIS_PREFIX, run_id)
+}
+
+/// Arquivos de snapshot de uma simulação, do mais recente para o mais antigo
+fn snapshot_files(snapshot_dir: &Path) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(snapshot_dir)? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == "json") {
+            files.push(path);
+        }
+    }
+    // Os nomes são timestamps, então a ordem alfabética é a cronológica
+    files.sort();
+    files.reverse();
+    Ok(files)
+}
+
+/// Id da simulação a partir do nome do arquivo de estado
+fn file_run_id(path: &Path) -> Option<String> {
+    let name = path.file_name()?.to_str()?;
+    if name == "simulation_state.json" {
+        return Some(DEFAULT_RUN_ID.to_string());
+    }
+    name.strip_prefix("simulation_state.")?
+        .strip_suffix(".json")
+        .filter(|id| validate_run_id(id).is_ok())
+        .map(str::to_string)
+}
+
+/// Ids viram nomes de arquivo e chaves: apenas letras, números, `-` e `_`
+pub fn validate_run_id(run_id: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let valid = !run_id.is_empty()
+        && run_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(format!(
+            "Id de simulação inválido: '{}' (use letras, números, - e _)",
+            run_id
+        )
+        .into())
+    }
+}
+
+/// Comando `states`: lista as simulações com estado salvo no backend configurado
+pub fn run_states_command() -> Result<(), Box<dyn std::error::Error>> {
+    let store = StateStore::from_env()?;
+    let saved = store.list()?;
+
+    info!("💾 {} estado(s) salvo(s) encontrados", saved.len());
+    println!("\n💾 ESTADOS SALVOS");
+    println!("{}", "=".repeat(100));
+    if saved.is_empty() {
+        println!("Nenhum estado salvo encontrado");
+        return Ok(());
+    }
+    println!(
+        "{:<20} {:<18} {:>10} {:>12} {:>12} {:>7}  Local",
+        "Simulação", "Data simulada", "Índice", "Fiat", "BTC", "Trades"
+    );
+    println!("{}", "-".repeat(100));
+    for info in saved {
+        println!(
+            "{:<20} {:<18} {:>10} {:>12.2} {:>12.6} {:>7}  {}",
+            info.run_id,
+            info.current_time.format("%Y-%m-%d %H:%M"),
+            info.data_index,
+            info.saldo_fiat,
+            info.saldo_btc,
+            info.total_trades,
+            info.location
+        );
+    }
+    Ok(())
+}
+
+/// Comando `restore [--list] [--snapshot id|posição]`: lista os snapshots da simulação
+/// configurada ou volta o estado para um deles
+pub fn run_restore_command(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let mut target: Option<String> = None;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--list" => target = None,
+            "--snapshot" => {
+                target = Some(
+                    iter.next()
+                        .ok_or("--snapshot requer um id ou posição")?
+                        .clone(),
+                )
+            }
+            other => return Err(format!("Opção desconhecida: {}", other).into()),
+        }
+    }
+
+    let store = StateStore::from_env()?;
+    let Some(id) = target else {
+        return print_snapshots(&store);
+    };
+
+    let snapshot = store.restore(&id)?;
+    info!(
+        "⏪ Estado de '{}' restaurado do snapshot {} - Índice: {} - Data: {}",
+        store.run_id(),
+        id,
+        snapshot.state.data_index,
+        snapshot.state.current_time.format("%Y-%m-%d %H:%M")
+    );
+    println!(
+        "⏪ Estado de '{}' restaurado do snapshot {} - Índice: {} - Data: {}",
+        store.run_id(),
+        id,
+        snapshot.state.data_index,
+        snapshot.state.current_time.format("%Y-%m-%d %H:%M")
+    );
+    Ok(())
+}
+
+fn print_snapshots(store: &StateStore) -> Result<(), Box<dyn std::error::Error>> {
+    let entries = store.snapshots()?;
+
+    println!(
+        "\n📚 SNAPSHOTS DE '{}' ({})",
+        store.run_id(),
+        store.describe()
+    );
+    println!("{}", "=".repeat(100));
+    if entries.is_empty() {
+        println!("Nenhum snapshot encontrado");
+        return Ok(());
+    }
+    println!(
+        "{:>3}  {:<16} {:<18} {:>10} {:>12} {:>12}",
+        "Nº", "Snapshot", "Data simulada", "Índice", "Fiat", "BTC"
+    );
+    println!("{}", "-".repeat(100));
+    for (position, entry) in entries.iter().enumerate() {
+        match &entry.snapshot {
+            Ok(snapshot) => println!(
+                "{:>3}  {:<16} {:<18} {:>10} {:>12.2} {:>12.6}",
+                position + 1,
+                entry.id,
+                snapshot.state.current_time.format("%Y-%m-%d %H:%M"),
+                snapshot.state.data_index,
+                snapshot.state.saldo_fiat,
+                snapshot.state.saldo_btc
+            ),
+            Err(e) => println!("{:>3}  {:<16} ❌ inválido: {}", position + 1, entry.id, e),
+        }
+    }
+    println!("\n💡 Restaurar: cargo run restore --snapshot <Nº ou id>");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trade_btc::{TradeConfig, TradeStats};
+    use chrono::TimeZone;
+
+    fn state() -> SimulationState {
+        let config = TradeConfig::default();
+        SimulationState {
+            stats: TradeStats::new(config.initial_balance),
+            config,
+            current_time: Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap(),
+            end_time: Utc.with_ymd_and_hms(2024, 2, 1, 0, 0, 0).unwrap(),
+            data_index: 42,
+            total_records: 1000,
+            saldo_fiat: 8765.25,
+            saldo_btc: 0.5,
+            preco_anterior: Some(42000.0),
+            preco_pico_recente: 43000.0,
+            total_investido: 1234.75,
+            buy_orders: Vec::new(),
+            transaction_history: Vec::new(),
+            next_order_id: 3,
+            next_transaction_id: 7,
+            quedas_detectadas: 1,
+            quedas_para_comprar: 2,
+            next_funding_time: Some(Utc.with_ymd_and_hms(2024, 1, 1, 8, 0, 0).unwrap()),
+            equity_curve: Vec::new(),
+            last_equity_time: None,
+            equity_file: None,
+            benchmarks_file: None,
+            benchmarks: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn encoded_state_round_trips() {
+        let (data, saved_at) = encode(&state()).unwrap();
+        let snapshot = decode(&data).unwrap();
+        assert_eq!(snapshot.schema_version, SCHEMA_VERSION);
+        assert_eq!(snapshot.saved_at, Some(saved_at));
+        assert_eq!(snapshot.state.data_index, 42);
+        assert_eq!(snapshot.state.saldo_fiat, 8765.25);
+        assert_eq!(snapshot.state.next_funding_time, state().next_funding_time);
+    }
+
+    #[test]
+    fn tampered_state_fails_checksum() {
+        let (data, _) = encode(&state()).unwrap();
+        assert!(data.contains("8765.25"));
+        let tampered = data.replace("8765.25", "9765.25");
+        let error = decode(&tampered).err().unwrap().to_string();
+        assert!(error.contains("checksum não confere"), "{}", error);
+
+        let mut envelope: Value = serde_json::from_str(&data).unwrap();
+        envelope.as_object_mut().unwrap().remove("checksum");
+        let error = decode(&envelope.to_string()).err().unwrap().to_string();
+        assert!(error.contains("checksum ausente"), "{}", error);
+    }
+
+    #[test]
+    fn v1_state_without_envelope_is_migrated() {
+        let mut v1 = serde_json::to_value(state()).unwrap();
+        let fields = v1.as_object_mut().unwrap();
+        for field in ["next_funding_time", "equity_curve", "benchmarks"] {
+            fields.remove(field);
+        }
+
+        let mut migrated = v1.clone();
+        migrate_v1_to_v2(&mut migrated);
+        assert_eq!(migrated["next_funding_time"], Value::Null);
+        assert_eq!(migrated["equity_curve"], Value::Array(Vec::new()));
+        assert_eq!(migrated["benchmarks"], Value::Array(Vec::new()));
+
+        let snapshot = decode(&v1.to_string()).unwrap();
+        assert_eq!(snapshot.schema_version, 1);
+        assert!(snapshot.saved_at.is_none());
+        assert_eq!(snapshot.state.total_investido, 1234.75);
+        assert!(snapshot.state.next_funding_time.is_none());
+        assert!(snapshot.state.equity_curve.is_empty());
+    }
+
+    #[test]
+    fn future_schema_version_is_rejected() {
+        let (data, _) = encode(&state()).unwrap();
+        // Troca só a versão no texto: o checksum do estado continua válido
+        let current = format!("\"schema_version\": {}", SCHEMA_VERSION);
+        assert!(data.contains(&current));
+        let future = data.replace(
+            &current,
+            &format!("\"schema_version\": {}", SCHEMA_VERSION + 1),
+        );
+        let error = decode(&future).err().unwrap().to_string();
+        assert!(error.contains("não suportada"), "{}", error);
+    }
+
+    #[test]
+    fn redis_keys_are_namespaced_by_run_id() {
+        assert_eq!(redis_key("papel"), format!("{}:papel", REDIS_PREFIX));
+        assert_eq!(
+            snapshots_key("papel"),
+            format!("{}:papel:snapshots", REDIS_PREFIX)
+        );
+        let store = StateStore::redis(RedisClient::offline(), "papel");
+        assert_eq!(store.run_id(), "papel");
+        assert!(store.describe().starts_with(&redis_key("papel")));
+    }
+
+    #[test]
+    fn file_store_paths_follow_the_run_id() {
+        let default = StateStore::file(DEFAULT_RUN_ID);
+        assert_eq!(default.describe(), "simulation_state.json");
+        let named = StateStore::file("papel");
+        assert_eq!(named.describe(), "simulation_state.papel.json");
+        let Backend::File { snapshot_dir, .. } = &named.backend else {
+            panic!("backend de arquivo esperado");
+        };
+        assert_eq!(snapshot_dir, &Path::new(SNAPSHOT_DIR).join("papel"));
+    }
+
+    #[test]
+    fn run_ids_are_read_back_from_state_file_names() {
+        assert_eq!(
+            file_run_id(Path::new("simulation_state.json")).as_deref(),
+            Some(DEFAULT_RUN_ID)
+        );
+        assert_eq!(
+            file_run_id(Path::new("./simulation_state.papel-2.json")).as_deref(),
+            Some("papel-2")
+        );
+        for name in [
+            "simulation_state.json.tmp",
+            "simulation_state..json",
+            "simulation_state.a b.json",
+            "other.json",
+        ] {
+            assert!(file_run_id(Path::new(name)).is_none(), "{}", name);
+        }
+    }
+
+    #[test]
+    fn run_ids_are_restricted_to_safe_characters() {
+        for id in ["default", "papel_1", "A-b-9"] {
+            assert!(validate_run_id(id).is_ok(), "{}", id);
+        }
+        for id in ["", "../etc", "a:b", "a b", "sim*"] {
+            assert!(validate_run_id(id).is_err(), "{}", id);
+        }
+    }
+}
@@ -0,0 +1,246 @@
+use std::env;
+use std::fs::{File, OpenOptions, TryLockError};
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant};
+use tracing::{error, info, warn};
+
+use crate::simulations::{self, NamedSimulation};
+
+/// Reinícios permitidos pelo supervisor quando `--max-restarts` não é informado
+const DEFAULT_MAX_RESTARTS: u32 = 5;
+
+/// Após rodar esse tempo sem cair, o contador de reinícios volta a zero
+const STABLE_RUN: Duration = Duration::from_secs(600);
+
+/// Espera máxima entre reinícios (a espera dobra a cada queda seguida)
+const MAX_RESTART_DELAY: Duration = Duration::from_secs(300);
+
+static SHUTDOWN: OnceLock<Arc<AtomicBool>> = OnceLock::new();
+
+/// Lock da simulação, mantido aberto até o processo terminar
+static LOCK: OnceLock<File> = OnceLock::new();
+
+/// Trata SIGTERM/SIGINT marcando o pedido de parada, para o laço da simulação salvar o
+/// estado e o relatório final antes de sair. Um segundo sinal encerra na hora.
+pub fn install_shutdown_handler() -> Result<(), Box<dyn std::error::Error>> {
+    if SHUTDOWN.get().is_some() {
+        return Ok(());
+    }
+    let flag = Arc::new(AtomicBool::new(false));
+    for signal in [signal_hook::consts::SIGTERM, signal_hook::consts::SIGINT] {
+        // A ordem importa: o desligamento forçado só vale se a flag já estiver marcada
+        signal_hook::flag::register_conditional_shutdown(signal, 1, Arc::clone(&flag))?;
+        signal_hook::flag::register(signal, Arc::clone(&flag))?;
+    }
+    let _ = SHUTDOWN.set(flag);
+    Ok(())
+}
+
+/// `true` depois que um sinal de parada foi recebido
+pub fn shutdown_requested() -> bool {
+    SHUTDOWN
+        .get()
+        .is_some_and(|flag| flag.load(Ordering::Relaxed))
+}
+
+/// Garante que só um processo rode cada simulação nomeada
+///
+/// O lock (`flock`) é liberado pelo sistema quando o processo termina, mesmo numa
+/// queda, então não fica lock "preso" como acontece com arquivos de PID.
+pub fn acquire_lock(simulation: &NamedSimulation) -> Result<(), Box<dyn std::error::Error>> {
+    if LOCK.get().is_some() {
+        return Ok(());
+    }
+    let path = simulation.lock_path();
+    let mut file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&path)?;
+    match file.try_lock() {
+        Ok(()) => {}
+        Err(TryLockError::WouldBlock) => {
+            return Err(format!(
+                "A simulação '{}' já está rodando (lock em {})",
+                simulation.name(),
+                path.display()
+            )
+            .into())
+        }
+        Err(TryLockError::Error(e)) => return Err(e.into()),
+    }
+    file.set_len(0)?;
+    writeln!(file, "{}", std::process::id())?;
+    let _ = LOCK.set(file);
+    Ok(())
+}
+
+/// Comando interno `supervise [--max-restarts N] -- comando args...`, usado por
+/// `daemon --restart`: roda o comando e o reinicia quando ele cai
+///
+/// Saída com sucesso (fim da simulação ou parada por sinal) não é reiniciada. O
+/// SIGTERM recebido pelo supervisor é repassado ao processo da simulação. Os reinícios
+/// retomam o estado salvo (veja `restart_command`).
+pub fn run_supervisor(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let mut max_restarts = DEFAULT_MAX_RESTARTS;
+    let mut command: Vec<String> = Vec::new();
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--max-restarts" => {
+                max_restarts = iter
+                    .next()
+                    .ok_or("--max-restarts requer um valor")?
+                    .parse()?
+            }
+            "--" => command = iter.by_ref().cloned().collect(),
+            other => return Err(format!("Opção desconhecida: {}", other).into()),
+        }
+    }
+    if command.is_empty() {
+        return Err("supervise requer um comando após --".into());
+    }
+
+    install_shutdown_handler()?;
+    let simulation = NamedSimulation::from_env()?;
+    let exe_path = env::current_exe()?;
+    let restart = restart_command(&command);
+    if restart != command {
+        info!(
+            "🔁 Supervisor: reinícios usam '{}' para retomar o estado salvo",
+            restart.join(" ")
+        );
+    }
+    let mut restarts = 0;
+    let mut first_start = true;
+
+    loop {
+        let started = Instant::now();
+        let args = if first_start { &command } else { &restart };
+        first_start = false;
+        let mut child = Command::new(&exe_path)
+            .args(args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()?;
+        info!(
+            "👀 Supervisor: simulação '{}' iniciada (PID {})",
+            simulation.name(),
+            child.id()
+        );
+
+        let status = loop {
+            if let Some(status) = child.try_wait()? {
+                break status;
+            }
+            if shutdown_requested() {
+                info!("🛑 Supervisor: repassando parada para o PID {}", child.id());
+                simulations::terminate(child.id())?;
+                let status = child.wait()?;
+                info!("✅ Supervisor: simulação encerrada ({})", status);
+                return Ok(());
+            }
+            thread::sleep(Duration::from_millis(200));
+        };
+
+        if status.success() {
+            info!("✅ Supervisor: simulação terminou normalmente");
+            return Ok(());
+        }
+
+        if started.elapsed() >= STABLE_RUN {
+            restarts = 0;
+        }
+        restarts += 1;
+        if restarts > max_restarts {
+            error!(
+                "💥 Supervisor: simulação caiu ({}) e o limite de {} reinícios foi atingido",
+                status, max_restarts
+            );
+            return Err(format!("limite de {} reinícios atingido", max_restarts).into());
+        }
+
+        let delay = Duration::from_secs(5)
+            .saturating_mul(2u32.saturating_pow(restarts - 1))
+            .min(MAX_RESTART_DELAY);
+        warn!(
+            "💥 Supervisor: simulação caiu ({}) - reiniciando em {:?} ({}/{})",
+            status, delay, restarts, max_restarts
+        );
+        let waiting = Instant::now();
+        while waiting.elapsed() < delay {
+            if shutdown_requested() {
+                info!("🛑 Supervisor: parada solicitada durante a espera para reiniciar");
+                return Ok(());
+            }
+            thread::sleep(Duration::from_millis(200));
+        }
+    }
+}
+
+/// Comando usado nos reinícios: o mesmo da primeira execução, sem o que descarta o
+/// progresso salvo
+///
+/// `fresh` vira `simulate` e saem `--fresh` (`live`, `ticks`) e o `--restart` do
+/// `import`; senão cada queda apagaria o estado e recomeçaria do zero.
+fn restart_command(command: &[String]) -> Vec<String> {
+    let Some((name, args)) = command.split_first() else {
+        return Vec::new();
+    };
+    let name = if name == "fresh" {
+        "simulate"
+    } else {
+        name.as_str()
+    };
+    let discards = |arg: &String| arg == "--fresh" || (name == "import" && arg == "--restart");
+
+    std::iter::once(name.to_string())
+        .chain(args.iter().filter(|arg| !discards(arg)).cloned())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn fresh_restarts_as_simulate() {
+        assert_eq!(restart_command(&args("fresh")), args("simulate"));
+        assert_eq!(restart_command(&args("simulate")), args("simulate"));
+    }
+
+    #[test]
+    fn restarts_drop_the_options_that_discard_progress() {
+        assert_eq!(
+            restart_command(&args("live --source stream:btc --fresh --idle-timeout 60")),
+            args("live --source stream:btc --idle-timeout 60")
+        );
+        assert_eq!(
+            restart_command(&args("ticks replay trades.csv --fresh")),
+            args("ticks replay trades.csv")
+        );
+        assert_eq!(
+            restart_command(&args("import data/btc.csv --restart --chunk 5000")),
+            args("import data/btc.csv --chunk 5000")
+        );
+        assert!(restart_command(&[]).is_empty());
+    }
+
+    #[test]
+    fn supervise_requires_a_command() {
+        assert!(run_supervisor(&[]).is_err());
+        assert!(run_supervisor(&args("--max-restarts 3")).is_err());
+        assert!(run_supervisor(&args("--max-restarts x -- simulate")).is_err());
+        assert!(run_supervisor(&args("--verbose -- simulate")).is_err());
+    }
+}
@@ -0,0 +1,620 @@
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use std::io::Read;
+use std::path::PathBuf;
+use tracing::{info, warn};
+
+use crate::data_quality;
+use crate::reader_csv::{parse_timestamp, CsvBtcFile, ReaderBtcFile, DEFAULT_CHUNK_SIZE};
+use crate::redis_client::RedisClient;
+use crate::trade_btc;
+
+/// Lado agressor de um trade (quem executou a ordem a mercado)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TradeSide {
+    Buy,
+    Sell,
+    Unknown,
+}
+
+impl TradeSide {
+    fn parse(value: &str) -> Self {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "buy" | "b" | "bid" => TradeSide::Buy,
+            "sell" | "s" | "ask" => TradeSide::Sell,
+            _ => TradeSide::Unknown,
+        }
+    }
+
+    /// `is_buyer_maker = true` significa que o agressor foi o vendedor (formato Binance)
+    fn from_buyer_maker(value: &str) -> Self {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "true" | "1" => TradeSide::Sell,
+            "false" | "0" => TradeSide::Buy,
+            _ => TradeSide::Unknown,
+        }
+    }
+}
+
+/// Um trade individual da exchange
+#[derive(Debug, Clone)]
+pub struct Tick {
+    pub timestamp: DateTime<Utc>,
+    pub price: f64,
+    pub size: f64,
+    pub side: TradeSide,
+}
+
+impl Tick {
+    /// Candle degenerado com o preço do trade, usado para simular tick a tick
+    pub fn to_candle(&self) -> CsvBtcFile {
+        CsvBtcFile {
+            open: self.price,
+            high: self.price,
+            low: self.price,
+            close: self.price,
+            volume: self.size,
+            timestamp: self.timestamp,
+        }
+    }
+}
+
+/// Como o lado do trade é informado no arquivo
+#[derive(Debug, Clone, Copy)]
+enum SideColumn {
+    Side(usize),
+    BuyerMaker(usize),
+    Missing,
+}
+
+/// Posições das colunas detectadas pelo cabeçalho
+#[derive(Debug, Clone, Copy)]
+struct TickColumns {
+    timestamp: usize,
+    price: usize,
+    size: usize,
+    side: SideColumn,
+}
+
+impl TickColumns {
+    const TIMESTAMP: &'static [&'static str] =
+        &["timestamp", "time", "ts", "date", "datetime", "trade_time"];
+    const PRICE: &'static [&'static str] = &["price", "p"];
+    const SIZE: &'static [&'static str] = &["size", "qty", "quantity", "amount", "volume", "q"];
+    const SIDE: &'static [&'static str] = &["side", "taker_side", "direction"];
+    const BUYER_MAKER: &'static [&'static str] =
+        &["is_buyer_maker", "isbuyermaker", "buyer_maker", "m"];
+
+    fn detect(headers: &csv::StringRecord) -> Result<Self, String> {
+        let find = |aliases: &[&str]| {
+            headers
+                .iter()
+                .position(|col| aliases.iter().any(|a| col.trim().eq_ignore_ascii_case(a)))
+        };
+        let required = |aliases: &[&str], name: &str| {
+            find(aliases).ok_or_else(|| {
+                format!(
+                    "Coluna de {} não encontrada no cabeçalho (aceitos: {})",
+                    name,
+                    aliases.join(", ")
+                )
+            })
+        };
+
+        let side = match (find(Self::SIDE), find(Self::BUYER_MAKER)) {
+            (Some(index), _) => SideColumn::Side(index),
+            (None, Some(index)) => SideColumn::BuyerMaker(index),
+            (None, None) => SideColumn::Missing,
+        };
+
+        Ok(Self {
+            timestamp: required(Self::TIMESTAMP, "timestamp")?,
+            price: required(Self::PRICE, "preço")?,
+            size: required(Self::SIZE, "quantidade")?,
+            side,
+        })
+    }
+
+    fn parse(&self, record: &csv::StringRecord) -> Option<Tick> {
+        let timestamp = parse_timestamp(record.get(self.timestamp)?)?;
+        let price: f64 = record.get(self.price)?.trim().parse().ok()?;
+        let size: f64 = record.get(self.size)?.trim().parse().ok()?;
+        if !price.is_finite() || price <= 0.0 || !size.is_finite() || size < 0.0 {
+            return None;
+        }
+        let side = match self.side {
+            SideColumn::Side(index) => TradeSide::parse(record.get(index)?),
+            SideColumn::BuyerMaker(index) => TradeSide::from_buyer_maker(record.get(index)?),
+            SideColumn::Missing => TradeSide::Unknown,
+        };
+        Some(Tick {
+            timestamp,
+            price,
+            size,
+            side,
+        })
+    }
+}
+
+/// Leitura sequencial de trades de um ou mais arquivos (`.csv`, `.csv.gz`, `.csv.zst`)
+///
+/// Os arquivos são lidos em ordem de nome, cada um com seu próprio cabeçalho. Linhas
+/// que não podem ser interpretadas são contadas e ignoradas.
+pub struct TickReader {
+    pending: Vec<PathBuf>,
+    current: Option<(csv::Reader<Box<dyn Read>>, TickColumns)>,
+    record: csv::StringRecord,
+    pub ticks_read: u64,
+    pub invalid_rows: u64,
+}
+
+impl TickReader {
+    pub fn open(input: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut pending = ReaderBtcFile::resolve_inputs(input)?;
+        pending.reverse();
+        info!(
+            "📥 Lendo trades de {} arquivo(s) em {}",
+            pending.len(),
+            input
+        );
+        println!(
+            "📥 Lendo trades de {} arquivo(s) em {}",
+            pending.len(),
+            input
+        );
+        Ok(Self {
+            pending,
+            current: None,
+            record: csv::StringRecord::new(),
+            ticks_read: 0,
+            invalid_rows: 0,
+        })
+    }
+
+    fn open_next_file(&mut self) -> Result<bool, Box<dyn std::error::Error>> {
+        let Some(path) = self.pending.pop() else {
+            return Ok(false);
+        };
+        let mut reader = csv::ReaderBuilder::new()
+            .flexible(true)
+            .from_reader(ReaderBtcFile::open_decoded(&path)?);
+        let columns = TickColumns::detect(reader.headers()?)
+            .map_err(|e| format!("{}: {}", path.display(), e))?;
+        self.current = Some((reader, columns));
+        Ok(true)
+    }
+
+    /// Próximo trade válido, ou `None` ao fim de todos os arquivos
+    pub fn next_tick(&mut self) -> Result<Option<Tick>, Box<dyn std::error::Error>> {
+        loop {
+            let Some((reader, columns)) = &mut self.current else {
+                if !self.open_next_file()? {
+                    return Ok(None);
+                }
+                continue;
+            };
+
+            match reader.read_record(&mut self.record) {
+                Ok(true) => match columns.parse(&self.record) {
+                    Some(tick) => {
+                        self.ticks_read += 1;
+                        return Ok(Some(tick));
+                    }
+                    None => self.invalid_rows += 1,
+                },
+                Ok(false) => self.current = None,
+                Err(_) => self.invalid_rows += 1,
+            }
+        }
+    }
+}
+
+/// Candle agregado a partir de trades, com o volume separado por lado agressor
+#[derive(Debug, Clone)]
+pub struct TickCandle {
+    pub candle: CsvBtcFile,
+    pub buy_volume: f64,
+    pub sell_volume: f64,
+    pub trades: u64,
+}
+
+impl TickCandle {
+    /// Colunas OHLCV + timestamp no formato do importador, seguidas do fluxo de ordens
+    const CSV_HEADER: [&'static str; 9] = [
+        "open",
+        "high",
+        "low",
+        "close",
+        "volume",
+        "timestamp",
+        "buy_volume",
+        "sell_volume",
+        "trades",
+    ];
+
+    fn csv_record(&self) -> [String; 9] {
+        let c = &self.candle;
+        [
+            c.open.to_string(),
+            c.high.to_string(),
+            c.low.to_string(),
+            c.close.to_string(),
+            c.volume.to_string(),
+            c.timestamp.to_rfc3339(),
+            self.buy_volume.to_string(),
+            self.sell_volume.to_string(),
+            self.trades.to_string(),
+        ]
+    }
+}
+
+/// Agrupa trades em candles de intervalo fixo (intervalos sem trades não geram candle)
+pub struct TickAggregator {
+    interval_ms: i64,
+    current: Option<TickCandle>,
+    pub late_ticks: u64,
+}
+
+impl TickAggregator {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval_ms: interval.num_milliseconds().max(1),
+            current: None,
+            late_ticks: 0,
+        }
+    }
+
+    fn bucket_start(&self, time: DateTime<Utc>) -> DateTime<Utc> {
+        let ms = time.timestamp_millis();
+        let start = ms - ms.rem_euclid(self.interval_ms);
+        Utc.timestamp_millis_opt(start).single().unwrap_or(time)
+    }
+
+    /// Adiciona um trade e devolve o candle anterior quando o intervalo muda
+    pub fn push(&mut self, tick: &Tick) -> Option<TickCandle> {
+        let bucket = self.bucket_start(tick.timestamp);
+
+        if let Some(current) = &mut self.current {
+            if bucket < current.candle.timestamp {
+                // Trades fora de ordem de um candle já fechado são descartados
+                self.late_ticks += 1;
+                return None;
+            }
+            if bucket == current.candle.timestamp {
+                let candle = &mut current.candle;
+                candle.high = candle.high.max(tick.price);
+                candle.low = candle.low.min(tick.price);
+                candle.close = tick.price;
+                candle.volume += tick.size;
+                match tick.side {
+                    TradeSide::Buy => current.buy_volume += tick.size,
+                    TradeSide::Sell => current.sell_volume += tick.size,
+                    TradeSide::Unknown => {}
+                }
+                current.trades += 1;
+                return None;
+            }
+        }
+
+        let (buy_volume, sell_volume) = match tick.side {
+            TradeSide::Buy => (tick.size, 0.0),
+            TradeSide::Sell => (0.0, tick.size),
+            TradeSide::Unknown => (0.0, 0.0),
+        };
+        self.current.replace(TickCandle {
+            candle: CsvBtcFile {
+                open: tick.price,
+                high: tick.price,
+                low: tick.price,
+                close: tick.price,
+                volume: tick.size,
+                timestamp: bucket,
+            },
+            buy_volume,
+            sell_volume,
+            trades: 1,
+        })
+    }
+
+    /// Fecha o último candle em aberto
+    pub fn finish(&mut self) -> Option<TickCandle> {
+        self.current.take()
+    }
+}
+
+/// Interpreta intervalos como `30s`, `1m`, `5m`, `1h`, `1d` ou segundos
+pub fn parse_interval(value: &str) -> Result<Duration, Box<dyn std::error::Error>> {
+    let value = value.trim();
+    let (number, unit) = value.split_at(
+        value
+            .find(|c: char| c.is_alphabetic())
+            .unwrap_or(value.len()),
+    );
+    let amount: i64 = number.parse()?;
+    if amount <= 0 {
+        return Err("Intervalo deve ser maior que zero".into());
+    }
+    match unit {
+        "" | "s" => Ok(Duration::seconds(amount)),
+        "m" => Ok(Duration::minutes(amount)),
+        "h" => Ok(Duration::hours(amount)),
+        "d" => Ok(Duration::days(amount)),
+        _ => Err(format!("Unidade de intervalo desconhecida: {}", unit).into()),
+    }
+}
+
+/// Grava o bloco no Redis quando ele atinge `DEFAULT_CHUNK_SIZE` candles
+fn flush_full_chunk(
+    redis: &RedisClient,
+    chunk: &mut Vec<CsvBtcFile>,
+    next_index: &mut usize,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if chunk.len() >= DEFAULT_CHUNK_SIZE {
+        redis.store_chunk(*next_index, chunk)?;
+        *next_index += chunk.len();
+        chunk.clear();
+    }
+    Ok(())
+}
+
+/// Agrega trades em candles e grava em CSV e/ou no Redis (`btc_0`, `btc_1`, ...)
+///
+/// A simulação trata `btc_N` como o minuto N a partir do primeiro candle, então no Redis
+/// só são aceitos candles de 1 minuto e os minutos sem trades viram candles repetindo o
+/// último fechamento (volume zero). O CSV continua sem candles para intervalos vazios.
+pub fn aggregate_ticks(
+    input: &str,
+    interval: Duration,
+    output: Option<&str>,
+    redis: Option<&RedisClient>,
+) -> Result<usize, Box<dyn std::error::Error>> {
+    if redis.is_some() && interval != Duration::minutes(1) {
+        return Err("No Redis só são gravados candles de 1 minuto (use --interval 1m)".into());
+    }
+
+    let mut reader = TickReader::open(input)?;
+    let mut aggregator = TickAggregator::new(interval);
+    let mut writer = match output {
+        Some(path) => {
+            let mut writer = csv::Writer::from_path(path)?;
+            writer.write_record(TickCandle::CSV_HEADER)?;
+            Some(writer)
+        }
+        None => None,
+    };
+    let mut chunk: Vec<CsvBtcFile> = Vec::with_capacity(DEFAULT_CHUNK_SIZE);
+    let mut candles: usize = 0;
+    let mut filled: usize = 0;
+    let mut next_index = 0;
+    let mut last_stored: Option<CsvBtcFile> = None;
+
+    let mut emit = |candle: TickCandle,
+                    writer: &mut Option<csv::Writer<std::fs::File>>,
+                    chunk: &mut Vec<CsvBtcFile>|
+     -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(writer) = writer {
+            writer.write_record(candle.csv_record())?;
+        }
+        if let Some(redis) = redis {
+            if let Some(previous) = &last_stored {
+                let mut time = previous.timestamp + interval;
+                while time < candle.candle.timestamp {
+                    chunk.push(data_quality::synthetic_candle(previous, None, 0.0, time));
+                    filled += 1;
+                    flush_full_chunk(redis, chunk, &mut next_index)?;
+                    time += interval;
+                }
+            }
+            last_stored = Some(candle.candle.clone());
+            chunk.push(candle.candle);
+            flush_full_chunk(redis, chunk, &mut next_index)?;
+        }
+        candles += 1;
+        Ok(())
+    };
+
+    while let Some(tick) = reader.next_tick()? {
+        if let Some(candle) = aggregator.push(&tick) {
+            emit(candle, &mut writer, &mut chunk)?;
+        }
+    }
+    if let Some(candle) = aggregator.finish() {
+        emit(candle, &mut writer, &mut chunk)?;
+    }
+    if let Some(redis) = redis {
+        redis.store_chunk(next_index, &chunk)?;
+    }
+    if let Some(writer) = &mut writer {
+        writer.flush()?;
+    }
+
+    if reader.invalid_rows > 0 || aggregator.late_ticks > 0 {
+        warn!(
+            "⚠️  {} linhas inválidas e {} trades fora de ordem ignorados",
+            reader.invalid_rows, aggregator.late_ticks
+        );
+        println!(
+            "⚠️  {} linhas inválidas e {} trades fora de ordem ignorados",
+            reader.invalid_rows, aggregator.late_ticks
+        );
+    }
+    info!(
+        "✅ {} trades agregados em {} candles de {}s",
+        reader.ticks_read,
+        candles,
+        interval.num_seconds()
+    );
+    println!(
+        "✅ {} trades agregados em {} candles de {}s",
+        reader.ticks_read,
+        candles,
+        interval.num_seconds()
+    );
+    if let Some(path) = output {
+        println!("📄 Candles salvos em {}", path);
+    }
+    if redis.is_some() {
+        println!(
+            "🗄️  Candles gravados no Redis (btc_0 a btc_{}, {} minutos sem trades preenchidos)",
+            (candles + filled).saturating_sub(1),
+            filled
+        );
+    }
+
+    Ok(candles)
+}
+
+/// Comando `ticks aggregate <entrada> [--interval 1m] [--output arquivo.csv] [--redis]`
+/// e `ticks simulate <entrada> [--fresh]`
+pub fn run_ticks_command(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let subcommand = args.first().map(String::as_str);
+    let mut input: Option<String> = None;
+    let mut interval = Duration::minutes(1);
+    let mut output: Option<String> = None;
+    let mut use_redis = false;
+    let mut fresh = false;
+
+    let mut iter = args.iter().skip(1);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--interval" => {
+                interval = parse_interval(iter.next().ok_or("--interval requer um valor")?)?
+            }
+            "--output" => output = Some(iter.next().ok_or("--output requer um arquivo")?.clone()),
+            "--redis" => use_redis = true,
+            "--fresh" => fresh = true,
+            path => input = Some(path.to_string()),
+        }
+    }
+    let input = input.ok_or("Informe o arquivo, diretório ou glob com os trades")?;
+
+    match subcommand {
+        Some("aggregate") => {
+            if output.is_none() && !use_redis {
+                output = Some(ReaderBtcFile::artifact_path(&input, ".candles.csv"));
+            }
+            let redis = if use_redis {
+                Some(RedisClient::from_env()?)
+            } else {
+                None
+            };
+            aggregate_ticks(&input, interval, output.as_deref(), redis.as_ref())?;
+            Ok(())
+        }
+        Some("simulate") => trade_btc::run_tick_simulation(&input, fresh),
+        _ => {
+            Err("Subcomando desconhecido. Use: cargo run ticks aggregate|simulate <entrada>".into())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tick(second: i64, price: f64, size: f64, side: TradeSide) -> Tick {
+        Tick {
+            timestamp: Utc.timestamp_opt(1_704_067_200 + second, 0).unwrap(),
+            price,
+            size,
+            side,
+        }
+    }
+
+    #[test]
+    fn aggregator_builds_ohlcv_and_splits_volume_by_side() {
+        let mut aggregator = TickAggregator::new(Duration::minutes(1));
+        assert!(aggregator
+            .push(&tick(5, 100.0, 1.0, TradeSide::Buy))
+            .is_none());
+        assert!(aggregator
+            .push(&tick(20, 105.0, 2.0, TradeSide::Sell))
+            .is_none());
+        assert!(aggregator
+            .push(&tick(40, 95.0, 0.5, TradeSide::Unknown))
+            .is_none());
+        assert!(aggregator
+            .push(&tick(59, 98.0, 1.5, TradeSide::Buy))
+            .is_none());
+
+        let closed = aggregator
+            .push(&tick(61, 99.0, 1.0, TradeSide::Sell))
+            .expect("mudança de minuto fecha o candle anterior");
+        let c = &closed.candle;
+        assert_eq!(c.timestamp, Utc.timestamp_opt(1_704_067_200, 0).unwrap());
+        assert_eq!((c.open, c.high, c.low, c.close), (100.0, 105.0, 95.0, 98.0));
+        assert_eq!(c.volume, 5.0);
+        assert_eq!(closed.buy_volume, 2.5);
+        assert_eq!(closed.sell_volume, 2.0);
+        assert_eq!(closed.trades, 4);
+
+        let last = aggregator.finish().unwrap();
+        assert_eq!(
+            last.candle.timestamp,
+            Utc.timestamp_opt(1_704_067_260, 0).unwrap()
+        );
+        assert_eq!(last.sell_volume, 1.0);
+        assert_eq!(last.trades, 1);
+        assert!(aggregator.finish().is_none());
+    }
+
+    #[test]
+    fn aggregator_skips_empty_intervals_and_drops_late_ticks() {
+        let mut aggregator = TickAggregator::new(Duration::minutes(1));
+        aggregator.push(&tick(10, 100.0, 1.0, TradeSide::Buy));
+        // Três minutos sem trades: o próximo candle começa direto no minuto 3
+        let first = aggregator
+            .push(&tick(190, 110.0, 1.0, TradeSide::Buy))
+            .unwrap();
+        assert_eq!(first.trades, 1);
+
+        assert!(aggregator
+            .push(&tick(50, 90.0, 1.0, TradeSide::Sell))
+            .is_none());
+        assert_eq!(aggregator.late_ticks, 1);
+
+        let current = aggregator.finish().unwrap();
+        assert_eq!(
+            current.candle.timestamp,
+            Utc.timestamp_opt(1_704_067_380, 0).unwrap()
+        );
+        assert_eq!(current.candle.low, 110.0);
+        assert_eq!(current.trades, 1);
+    }
+
+    #[test]
+    fn parse_interval_accepts_units_and_rejects_invalid() {
+        assert_eq!(parse_interval("30s").unwrap(), Duration::seconds(30));
+        assert_eq!(parse_interval("45").unwrap(), Duration::seconds(45));
+        assert_eq!(parse_interval("5m").unwrap(), Duration::minutes(5));
+        assert_eq!(parse_interval("1h").unwrap(), Duration::hours(1));
+        assert_eq!(parse_interval("1d").unwrap(), Duration::days(1));
+        assert!(parse_interval("0m").is_err());
+        assert!(parse_interval("5w").is_err());
+        assert!(parse_interval("m").is_err());
+    }
+
+    #[test]
+    fn columns_detect_buyer_maker_side() {
+        let headers = csv::StringRecord::from(vec!["time", "qty", "price", "is_buyer_maker"]);
+        let columns = TickColumns::detect(&headers).unwrap();
+
+        let sell = columns
+            .parse(&csv::StringRecord::from(vec![
+                "1704067200000",
+                "0.5",
+                "42000",
+                "true",
+            ]))
+            .unwrap();
+        assert_eq!(sell.side, TradeSide::Sell);
+        assert_eq!(sell.price, 42000.0);
+        assert_eq!(sell.size, 0.5);
+        assert_eq!(sell.timestamp, Utc.timestamp_opt(1_704_067_200, 0).unwrap());
+
+        let invalid_price = csv::StringRecord::from(vec!["1704067200000", "0.5", "0", "false"]);
+        assert!(columns.parse(&invalid_price).is_none());
+
+        let missing_price = csv::StringRecord::from(vec!["time", "qty", "side"]);
+        assert!(TickColumns::detect(&missing_price).is_err());
+    }
+}
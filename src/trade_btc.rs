@@ -1,11 +1,35 @@
-use crate::{reader_csv::CsvBtcFile, redis_client::RedisClient};
-use chrono::{DateTime, Utc};
+use crate::{
+    benchmark::{self, Benchmark, BenchmarkSample, ReturnMoments},
+    candle_cache::CandleCache,
+    control::{
+        ControlCommand, ControlHandle, ControlReply, ControlServer, LiveStatus, OpenOrderStatus, Portfolio,
+        RunControl, RunState, Session, TransactionPage, INACTIVE_FIELDS, SETTABLE_FIELDS,
+    },
+    data_quality::{self, FillPolicy},
+    events::{BuyKind, EventPublisher, SimEvent},
+    export::{self, BenchmarkLog, EquityLog},
+    funding::FundingSchedule,
+    http_api::{self, HttpServer},
+    live::{self, LiveCandle, LiveFeed, LiveSource, StreamAcks},
+    periods::{self, PeriodBreakdown, PeriodKind},
+    reader_csv::CsvBtcFile,
+    redis_client::RedisClient,
+    report,
+    run_manifest::{RunManifest, RunSettings, SavedRun},
+    simulations::NamedSimulation,
+    state_store::StateStore,
+    supervisor,
+    ticks::{Tick, TickReader},
+};
+use std::sync::mpsc::RecvTimeoutError;
+use chrono::{DateTime, SecondsFormat, Utc};
 use serde::{Deserialize, Serialize};
+use std::env;
 use std::fs;
-use std::path::Path;
-use std::thread;
+use std::path::{Path, PathBuf};
 use std::time::{Duration, Instant};
-use tracing::{debug, error, info, warn};
+use tracing::span::EnteredSpan;
+use tracing::{debug, error, field, info, info_span, warn};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BuyOrder {
@@ -19,7 +43,7 @@ pub struct BuyOrder {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Transaction {
     pub id: u32,
-    pub transaction_type: String, // "BUY" or "SELL"
+    pub transaction_type: String, // "BUY", "SELL" or "FUNDING"
     pub btc_quantity: f64,
     pub price: f64,
     pub time: DateTime<Utc>,
@@ -28,6 +52,16 @@ pub struct Transaction {
     pub buy_order_id: Option<u32>, // Para vendas, referencia a ordem de compra
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EquityPoint {
+    pub time: DateTime<Utc>,
+    pub price: f64,
+    pub saldo_fiat: f64,
+    pub saldo_btc: f64,
+    pub total_value: f64,
+    pub drawdown: f64,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TradeConfig {
     pub initial_balance: f64,                 // Saldo inicial em USD
@@ -64,6 +98,12 @@ pub struct TradeStats {
     pub total_loss: f64,
     pub max_drawdown: f64,
     pub current_drawdown: f64,
+    #[serde(default)]
+    pub total_funding: f64, // P&L de funding (separado do P&L dos trades)
+    #[serde(default)]
+    pub candles_missing: u64, // Índices sem candle no Redis
+    #[serde(default)]
+    pub candles_filled: u64, // Candles sintéticos gerados pela política de preenchimento
 }
 
 impl TradeStats {
@@ -78,6 +118,9 @@ impl TradeStats {
             total_loss: 0.0,
             max_drawdown: 0.0,
             current_drawdown: 0.0,
+            total_funding: 0.0,
+            candles_missing: 0,
+            candles_filled: 0,
         }
     }
 
@@ -114,6 +157,28 @@ pub struct SimulationState {
     pub next_transaction_id: u32,
     pub quedas_detectadas: u32,
     pub quedas_para_comprar: u32,
+    #[serde(default)]
+    pub next_funding_time: Option<DateTime<Utc>>,
+    /// Curva embutida por estados antigos; passa para o `equity.csv` da próxima execução
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub equity_curve: Vec<EquityPoint>,
+    /// Horário da última amostra da curva de patrimônio
+    #[serde(default)]
+    pub last_equity_time: Option<DateTime<Utc>>,
+    /// `equity.csv` da execução que gravou a curva até aqui
+    #[serde(default)]
+    pub equity_file: Option<PathBuf>,
+    /// `benchmarks.csv` da execução que gravou as curvas dos benchmarks até aqui
+    #[serde(default)]
+    pub benchmarks_file: Option<PathBuf>,
+    #[serde(default)]
+    pub benchmarks: Vec<Benchmark>,
+}
+
+/// Lote de dados em processamento, com o span `batch` aberto
+struct TickBatch {
+    number: usize,
+    span: EnteredSpan,
 }
 
 pub struct TradeSimulator {
@@ -138,10 +203,42 @@ pub struct TradeSimulator {
     // Contador de quedas para comprar apenas a cada 3 quedas
     quedas_detectadas: u32,   // Contador de quedas consecutivas
     quedas_para_comprar: u32, // Comprar apenas a cada N quedas
+    // Funding de contratos perpétuos (opcional)
+    funding: Option<FundingSchedule>,
+    next_funding_time: Option<DateTime<Utc>>,
+    // Curva de patrimônio amostrada a cada hora simulada, gravada no `equity.csv`
+    // da execução (o estado guarda só o arquivo e a última amostra)
+    equity_log: Option<EquityLog>,
+    equity_file: Option<PathBuf>,
+    last_equity_time: Option<DateTime<Utc>>,
+    legacy_equity_curve: Vec<EquityPoint>,
+    // Buy & hold e DCA ingênuo sobre os mesmos candles; o estado guarda só os agregados
+    // e as curvas vão para o `benchmarks.csv` da execução
+    benchmarks: Vec<Benchmark>,
+    benchmark_log: Option<BenchmarkLog>,
+    benchmarks_file: Option<PathBuf>,
+    // Preenchimento de candles ausentes (não persistido)
+    fill_policy: FillPolicy,
+    last_candle: Option<(usize, CsvBtcFile)>,
+    next_candle: Option<(usize, CsvBtcFile)>,
+    lookahead_checked_until: usize,
+    // Cache binário opcional no lugar do Redis (mesmos índices de `btc_N`)
+    candle_cache: Option<CandleCache>,
+    // Publicação opcional dos eventos da estratégia no Redis
+    events: Option<EventPublisher>,
+    // Onde o estado é salvo (arquivo ou Redis, por id de simulação)
+    state_store: StateStore,
+    // Pausa, velocidade e canal de controle (não persistido)
+    control: RunControl,
 }
 
 impl TradeSimulator {
-    const STATE_FILE: &'static str = "simulation_state.json";
+    // Janela máxima de busca pelo próximo candle real ao preencher gaps
+    const FILL_LOOKAHEAD: usize = 1000;
+    // Espera por candles no paper trading (também o atraso máximo do canal de controle)
+    const LIVE_POLL: Duration = Duration::from_millis(250);
+    // Dados por span `batch` nos logs
+    const TICK_BATCH: usize = 1000;
 
     pub fn new(
         redis_client: RedisClient,
@@ -162,6 +259,7 @@ impl TradeSimulator {
 
         Ok(Self {
             redis_client,
+            benchmarks: benchmark::default_benchmarks(config.initial_balance, start_time, end_time),
             stats: TradeStats::new(config.initial_balance),
             saldo_fiat: config.initial_balance,
             saldo_btc: 0.0,
@@ -179,25 +277,56 @@ impl TradeSimulator {
             end_time,
             data_index: 0,
             total_records: estimated_records,
+            funding: None,
+            next_funding_time: None,
+            equity_log: None,
+            equity_file: None,
+            last_equity_time: None,
+            legacy_equity_curve: Vec::new(),
+            benchmark_log: None,
+            benchmarks_file: None,
+            fill_policy: FillPolicy::Skip,
+            last_candle: None,
+            next_candle: None,
+            lookahead_checked_until: 0,
+            candle_cache: None,
+            events: None,
+            state_store: StateStore::default(),
+            control: RunControl::default(),
         })
     }
 
     pub fn from_saved_state(
         redis_client: RedisClient,
         config: TradeConfig,
+        state_store: StateStore,
     ) -> Result<Self, Box<dyn std::error::Error>> {
-        if Path::new(Self::STATE_FILE).exists() {
-            info!("📂 Arquivo de estado encontrado, carregando simulação anterior");
-            println!("📂 Arquivo de estado encontrado, carregando simulação anterior");
-            
-            let state_data = fs::read_to_string(Self::STATE_FILE)?;
-            let state: SimulationState = serde_json::from_str(&state_data)?;
-            
+        if let Some(state) = state_store.load()? {
+            info!("📂 Estado salvo da simulação '{}' encontrado em {}, carregando", state_store.run_id(), state_store.describe());
+            println!("📂 Estado salvo da simulação '{}' encontrado em {}, carregando", state_store.run_id(), state_store.describe());
+
             info!("✅ Estado carregado - Último índice: {} - Data: {}", 
                   state.data_index, state.current_time.format("%Y-%m-%d %H:%M"));
             println!("✅ Estado carregado - Último índice: {} - Data: {}", 
                      state.data_index, state.current_time.format("%Y-%m-%d %H:%M"));
-            
+
+            // Estados antigos não tinham benchmarks: começar a partir do ponto salvo
+            let benchmarks = if state.benchmarks.is_empty() {
+                benchmark::default_benchmarks(
+                    state.config.initial_balance,
+                    state.current_time,
+                    state.end_time,
+                )
+            } else {
+                let mut benchmarks = state.benchmarks;
+                // Estados antigos guardavam as amostras: refazer os agregados a partir delas
+                for benchmark in benchmarks.iter_mut().filter(|b| !b.samples.is_empty()) {
+                    benchmark.returns =
+                        ReturnMoments::from_curves(&state.equity_curve, &benchmark.samples);
+                }
+                benchmarks
+            };
+
             Ok(Self {
                 redis_client,
                 config: state.config,
@@ -217,16 +346,59 @@ impl TradeSimulator {
                 next_transaction_id: state.next_transaction_id,
                 quedas_detectadas: state.quedas_detectadas,
                 quedas_para_comprar: state.quedas_para_comprar,
+                funding: None,
+                next_funding_time: state.next_funding_time,
+                equity_log: None,
+                equity_file: state.equity_file,
+                last_equity_time: state
+                    .last_equity_time
+                    .or_else(|| state.equity_curve.last().map(|point| point.time)),
+                legacy_equity_curve: state.equity_curve,
+                benchmarks,
+                benchmark_log: None,
+                benchmarks_file: state.benchmarks_file,
+                fill_policy: FillPolicy::Skip,
+                last_candle: None,
+                next_candle: None,
+                lookahead_checked_until: 0,
+                candle_cache: None,
+                events: None,
+                state_store,
+                control: RunControl::default(),
             })
         } else {
-            info!("📝 Nenhum arquivo de estado encontrado, iniciando nova simulação");
-            println!("📝 Nenhum arquivo de estado encontrado, iniciando nova simulação");
-            Self::new(redis_client, config)
+            info!("📝 Nenhum estado salvo em {}, iniciando nova simulação", state_store.describe());
+            println!("📝 Nenhum estado salvo em {}, iniciando nova simulação", state_store.describe());
+            let mut simulator = Self::new(redis_client, config)?;
+            simulator.state_store = state_store;
+            Ok(simulator)
+        }
+    }
+
+    pub fn save_state(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        // A curva no disco precisa cobrir as amostras registradas no estado
+        if let Some(log) = &mut self.equity_log {
+            log.flush()?;
         }
+        if let Some(log) = &mut self.benchmark_log {
+            log.flush()?;
+        }
+        self.state_store.save(&self.snapshot())?;
+
+        debug!("💾 Estado salvo - Índice: {} - Data: {}", 
+               self.data_index, self.current_time.format("%Y-%m-%d %H:%M"));
+        
+        Ok(())
     }
 
-    pub fn save_state(&self) -> Result<(), Box<dyn std::error::Error>> {
-        let state = SimulationState {
+    fn write_state(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        let state_json = serde_json::to_string_pretty(&self.snapshot())?;
+        fs::write(path, state_json)?;
+        Ok(())
+    }
+
+    fn snapshot(&self) -> SimulationState {
+        SimulationState {
             config: self.config.clone(),
             stats: self.stats.clone(),
             current_time: self.current_time,
@@ -244,54 +416,138 @@ impl TradeSimulator {
             next_transaction_id: self.next_transaction_id,
             quedas_detectadas: self.quedas_detectadas,
             quedas_para_comprar: self.quedas_para_comprar,
+            next_funding_time: self.next_funding_time,
+            equity_curve: self.legacy_equity_curve.clone(),
+            last_equity_time: self.last_equity_time,
+            equity_file: self.equity_file.clone(),
+            benchmarks_file: self.benchmarks_file.clone(),
+            benchmarks: self.benchmarks.clone(),
+        }
+    }
+
+    /// Curva de patrimônio gravada até aqui, lida do `equity.csv`
+    fn equity_curve(&mut self) -> Result<Vec<EquityPoint>, Box<dyn std::error::Error>> {
+        match &mut self.equity_log {
+            Some(log) => {
+                log.flush()?;
+                export::read_equity_csv(log.path())
+            }
+            None => Ok(self.legacy_equity_curve.clone()),
+        }
+    }
+
+    /// Grava estado final, trades, períodos e relatório HTML no diretório da execução
+    ///
+    /// A curva de patrimônio já está no `equity.csv`, gravada durante a execução.
+    fn export_run(&mut self, manifest: &RunManifest) -> Result<(), Box<dyn std::error::Error>> {
+        self.write_state(&manifest.files.state)?;
+        let equity_curve = self.equity_curve()?;
+        export::write_trades_csv(&manifest.files.trades, &self.transaction_history)?;
+        export::write_periods_csv(
+            &manifest.files.periods,
+            &self.period_breakdown(&equity_curve),
+        )?;
+
+        let run = SavedRun {
+            name: manifest.run_id.clone(),
+            manifest: Some(manifest.clone()),
+            equity_curve,
+            state: self.snapshot(),
         };
+        report::write_html_report(&manifest.files.report, &run)?;
 
-        let state_json = serde_json::to_string_pretty(&state)?;
-        fs::write(Self::STATE_FILE, state_json)?;
-        
-        debug!("💾 Estado salvo - Índice: {} - Data: {}", 
-               self.data_index, self.current_time.format("%Y-%m-%d %H:%M"));
-        
-        Ok(())
+        manifest.save()
     }
 
-    pub fn clear_state_file() -> Result<(), Box<dyn std::error::Error>> {
-        if Path::new(Self::STATE_FILE).exists() {
-            fs::remove_file(Self::STATE_FILE)?;
-            info!("🗑️  Arquivo de estado removido");
-            println!("🗑️  Arquivo de estado removido");
+    pub fn set_funding_schedule(&mut self, funding: Option<FundingSchedule>) {
+        if funding.is_some() && self.next_funding_time.is_none() {
+            self.next_funding_time = Some(FundingSchedule::next_funding_time(self.current_time));
         }
-        Ok(())
+        self.funding = funding;
     }
 
-    pub fn run(&mut self) -> Result<(), Box<dyn std::error::Error>> {
-        info!("🚀 Iniciando simulador de trade BTC");
-        info!("💰 Saldo inicial: ${:.2}", self.config.initial_balance);
-        info!(
-            "📊 Perda máxima aceitável: {:.1}%",
-            self.config.max_loss_percentage
-        );
-        info!(
-            "🎯 Stop Loss: {:.1}% | Take Profit: {:.1}%",
-            self.config.stop_loss_percentage, self.config.take_profit_percentage
-        );
-        info!("⏰ Período: {} até {}", self.current_time, self.end_time);
+    pub fn set_event_publisher(&mut self, events: Option<EventPublisher>) {
+        self.events = events;
+    }
 
-        // Manter println para interface do usuário
-        println!("🚀 Iniciando simulador de trade BTC");
-        println!("💰 Saldo inicial: ${:.2}", self.config.initial_balance);
-        println!(
-            "📊 Perda máxima aceitável: {:.1}%",
-            self.config.max_loss_percentage
+    fn emit(&self, event: SimEvent) {
+        if let Some(events) = &self.events {
+            events.emit(self.current_time, &event);
+        }
+    }
+
+    pub fn set_fill_policy(&mut self, policy: FillPolicy) {
+        self.fill_policy = policy;
+    }
+
+    /// Lê os candles do cache mapeado em memória em vez do Redis
+    ///
+    /// O cache é conferido com o Redis antes do uso; um cache desatualizado é recusado.
+    pub fn set_candle_cache(
+        &mut self,
+        cache: CandleCache,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        cache.verify_against(&self.redis_client)?;
+        info!(
+            "🗄️  Usando cache {} ({} candles, dataset {})",
+            cache.path().display(),
+            cache.len(),
+            cache.fingerprint()
         );
         println!(
-            "🎯 Stop Loss: {:.1}% | Take Profit: {:.1}%",
-            self.config.stop_loss_percentage, self.config.take_profit_percentage
+            "🗄️  Usando cache {} ({} candles, dataset {})",
+            cache.path().display(),
+            cache.len(),
+            cache.fingerprint()
         );
-        println!("⏰ Período: {} até {}", self.current_time, self.end_time);
-        println!("{}", "=".repeat(80));
+        self.candle_cache = Some(cache);
+        Ok(())
+    }
+
+    /// Funding, preenchimento de gaps e cache em uso, registrados no manifesto
+    fn run_settings(&self) -> RunSettings {
+        RunSettings {
+            funding: self.funding.as_ref().map(FundingSchedule::describe),
+            fill_policy: self.fill_policy.name().to_string(),
+            candle_cache: self.candle_cache.as_ref().map(|cache| {
+                format!("{} ({})", cache.path().display(), cache.fingerprint())
+            }),
+        }
+    }
+
+    /// Origem dos candles registrada no manifesto da execução
+    fn data_source(&self) -> String {
+        match &self.candle_cache {
+            Some(cache) => format!("cache:{}", cache.path().display()),
+            None => self.redis_client.url().to_string(),
+        }
+    }
+
+    pub fn clear_saved_state(store: &StateStore) -> Result<(), Box<dyn std::error::Error>> {
+        if store.clear()? {
+            info!("🗑️  Estado removido de {}", store.describe());
+            println!("🗑️  Estado removido de {}", store.describe());
+        }
+        Ok(())
+    }
 
+    pub fn run(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let (mut manifest, _run) =
+            self.begin_run(&self.data_source(), Some(Duration::from_millis(10)))?;
         let start_simulation = Instant::now();
+        let mut batch = None;
+        let result = self.replay_candles(&mut manifest, &mut batch);
+        self.close_batch(&mut batch);
+        self.finish_run(&mut manifest, start_simulation, &result);
+        result
+    }
+
+    /// Laço do `run`: um candle por minuto simulado até o fim do período
+    fn replay_candles(
+        &mut self,
+        manifest: &mut RunManifest,
+        batch: &mut Option<TickBatch>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
         let mut last_display = Instant::now();
         let mut last_save = Instant::now();
 
@@ -300,9 +556,18 @@ impl TradeSimulator {
         const SAVE_INTERVAL_SECS: u64 = 30; // Salvar estado a cada 30 segundos
 
         while self.current_time < self.end_time {
+            self.poll_control(true);
+            if supervisor::shutdown_requested() {
+                Self::log_shutdown();
+                break;
+            }
+            self.enter_batch(batch);
+
             // Buscar dados do Redis para o índice atual
             if let Some(btc_data) = self.get_current_btc_data()? {
                 consecutive_no_data = 0; // Reset contador quando encontra dados
+                manifest.dataset.update(self.data_index, &btc_data);
+                self.last_candle = Some((self.data_index, btc_data.clone()));
                 self.process_tick(&btc_data)?;
 
                 // Atualizar display a cada 5 segundos de simulação
@@ -321,9 +586,16 @@ impl TradeSimulator {
                 }
             } else {
                 consecutive_no_data += 1;
-
-                // Log a cada 100 iterações sem dados
-                if consecutive_no_data % 100 == 0 {
+                self.stats.candles_missing += 1;
+
+                // Candle sintético conforme a política (apenas dentro de gaps)
+                if let Some(filled) = self.fill_missing_candle()? {
+                    // Gap preenchido: há candle real adiante, não conta para a parada
+                    consecutive_no_data = 0;
+                    self.stats.candles_filled += 1;
+                    self.process_tick(&filled)?;
+                } else if consecutive_no_data % 100 == 0 {
+                    // Log a cada 100 iterações sem dados
                     warn!(
                         "⚠️  {} iterações sem dados - Índice: {} - Data: {} - Progresso: {:.1}%",
                         consecutive_no_data,
@@ -368,11 +640,11 @@ impl TradeSimulator {
             }
 
             // Avançar tempo (simulando 1 hora por tick)
-            self.current_time = self.current_time + chrono::Duration::minutes(1);
+            self.current_time += chrono::Duration::minutes(1);
             self.data_index += 1;
 
-            // Pequena pausa para visualização
-            thread::sleep(Duration::from_millis(10));
+            // Pequena pausa para visualização (ajustável com `control speed`)
+            self.pace();
 
             // Verificar se deve parar por perda máxima
             // if self.should_stop_trading() {
@@ -381,38 +653,870 @@ impl TradeSimulator {
             // }
         }
 
-        info!("🏁 Simulação concluída!");
+        Ok(())
+    }
+
+    /// Executa a estratégia trade a trade em vez de candle a candle
+    ///
+    /// Cada trade vira um tick com o seu preço real, então compras e take profits são
+    /// executados no primeiro trade que cruza o gatilho, sem a aproximação pelo fechamento
+    /// do candle. Não há pausa entre ticks (salvo com `control speed`). Ao retomar um estado salvo, os trades até o
+    /// horário salvo são ignorados.
+    pub fn run_on_ticks(
+        &mut self,
+        ticks: &mut TickReader,
+        source: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let resuming = self.data_index > 0;
+        let mut first = ticks.next_tick()?;
+        while let Some(tick) = &first {
+            if tick.timestamp > self.current_time
+                || (!resuming && tick.timestamp == self.current_time)
+            {
+                break;
+            }
+            first = ticks.next_tick()?;
+        }
+        let Some(first) = first else {
+            return Err(format!("Nenhum trade após {} em {}", self.current_time, source).into());
+        };
+        if !resuming {
+            self.start_at(first.timestamp);
+        }
+
+        let (mut manifest, _run) = self.begin_run(source, Some(Duration::ZERO))?;
+        let start_simulation = Instant::now();
+        let mut batch = None;
+        let result = self.replay_ticks(&mut manifest, &mut batch, ticks, first);
+        self.close_batch(&mut batch);
+        self.finish_run(&mut manifest, start_simulation, &result);
+        result
+    }
+
+    /// Laço do `run_on_ticks`: um tick por trade até o fim do período
+    fn replay_ticks(
+        &mut self,
+        manifest: &mut RunManifest,
+        batch: &mut Option<TickBatch>,
+        ticks: &mut TickReader,
+        first: Tick,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut last_display = Instant::now();
+        let mut last_save = Instant::now();
+        const SAVE_INTERVAL_SECS: u64 = 30; // Salvar estado a cada 30 segundos
+
+        let mut next = Some(first);
+        while let Some(tick) = next {
+            if tick.timestamp >= self.end_time {
+                break;
+            }
+            self.poll_control(true);
+            if supervisor::shutdown_requested() {
+                Self::log_shutdown();
+                break;
+            }
+            // Trades fora de ordem não voltam o relógio da simulação
+            if tick.timestamp > self.current_time {
+                self.current_time = tick.timestamp;
+            }
+
+            self.enter_batch(batch);
+            let btc_data = tick.to_candle();
+            manifest.dataset.update(self.data_index, &btc_data);
+            self.process_tick(&btc_data)?;
+            self.data_index += 1;
+
+            if last_display.elapsed() >= Duration::from_secs(5) {
+                self.display_status(&btc_data);
+                last_display = Instant::now();
+            }
+
+            if last_save.elapsed() >= Duration::from_secs(SAVE_INTERVAL_SECS) {
+                if let Err(e) = self.save_state() {
+                    error!("❌ Erro ao salvar estado: {}", e);
+                    eprintln!("❌ Erro ao salvar estado: {}", e);
+                }
+                last_save = Instant::now();
+            }
+
+            self.pace();
+            next = ticks.next_tick()?;
+        }
+
+        if ticks.invalid_rows > 0 {
+            warn!("⚠️  {} linhas de trades inválidas ignoradas", ticks.invalid_rows);
+            println!("⚠️  {} linhas de trades inválidas ignoradas", ticks.invalid_rows);
+        }
+
+        Ok(())
+    }
+
+    /// Paper trading: consome candles conforme chegam da fonte, no ritmo do relógio
+    ///
+    /// Não há laço por índice nem pausa artificial: o tempo simulado acompanha o
+    /// timestamp de cada candle recebido. Candles com timestamp igual ou anterior ao
+    /// último processado (repetidos ou atrasados) são ignorados. O estado continua sendo
+    /// salvo a cada 30 segundos. Pausada pelo `control`, os candles recebidos esperam
+    /// na fila. Termina quando a fonte encerra ou, se `idle_timeout`
+    /// for informado, após esse tempo sem receber candles. As entradas lidas por grupo de
+    /// consumidores só são confirmadas depois que o estado que as inclui é salvo.
+    pub fn run_live(
+        &mut self,
+        feed: &mut LiveFeed,
+        source: &str,
+        idle_timeout: Option<Duration>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let resuming = self.data_index > 0;
+
+        info!("📡 Aguardando candles de {}...", source);
+        println!("📡 Aguardando candles de {}...", source);
+        let waiting = Instant::now();
+        let first = loop {
+            self.poll_control(false);
+            if supervisor::shutdown_requested() {
+                // Nada foi processado ainda, então não há estado novo para salvar
+                info!("🛑 Parada solicitada antes do primeiro candle");
+                println!("🛑 Parada solicitada antes do primeiro candle");
+                return Ok(());
+            }
+            match feed.candles.recv_timeout(Self::LIVE_POLL) {
+                Ok(live) if resuming && live.candle.timestamp <= self.current_time => {
+                    feed.acks.push(&live);
+                }
+                Ok(live) => break live,
+                Err(RecvTimeoutError::Timeout)
+                    if idle_timeout.is_none_or(|timeout| waiting.elapsed() < timeout) => {}
+                Err(_) => return Err(format!("Nenhum candle recebido de {}", source).into()),
+            }
+        };
+        if !resuming {
+            self.start_at(first.candle.timestamp);
+            // Horizonte dos benchmarks DCA quando o período de backtest já passou
+            if self.end_time <= first.candle.timestamp {
+                self.end_time = first.candle.timestamp + chrono::Duration::days(365);
+            }
+        }
+
+        let (mut manifest, _run) = self.begin_run(source, None)?;
+        let start_simulation = Instant::now();
+        let mut batch = None;
+        let result = self.follow_feed(
+            &mut manifest,
+            &mut batch,
+            feed,
+            source,
+            idle_timeout,
+            first,
+        );
+        self.close_batch(&mut batch);
+        if self.finish_run(&mut manifest, start_simulation, &result) {
+            Self::confirm_entries(&mut feed.acks);
+        }
+        result
+    }
+
+    /// Confirma as entradas do grupo de consumidores já cobertas pelo estado salvo
+    fn confirm_entries(acks: &mut StreamAcks) {
+        let pending = acks.pending();
+        if let Err(e) = acks.flush() {
+            // Sem a confirmação as entradas voltam na próxima leitura e são ignoradas
+            // como repetidas
+            warn!("⚠️  Erro ao confirmar {} entradas do stream: {}", pending, e);
+            eprintln!("⚠️  Erro ao confirmar {} entradas do stream: {}", pending, e);
+        }
+    }
+
+    /// Laço do `run_live`: processa os candles da fonte conforme chegam
+    fn follow_feed(
+        &mut self,
+        manifest: &mut RunManifest,
+        batch: &mut Option<TickBatch>,
+        feed: &mut LiveFeed,
+        source: &str,
+        idle_timeout: Option<Duration>,
+        first: LiveCandle,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut last_display = Instant::now();
+        let mut last_save = Instant::now();
+        let mut last_received = Instant::now();
+        let mut last_idle_warning = Instant::now();
+        let mut ignored = 0u64;
+        const SAVE_INTERVAL_SECS: u64 = 30; // Salvar estado a cada 30 segundos
+        const IDLE_WARNING_SECS: u64 = 60; // Avisar quando a fonte ficar parada
+
+        let mut next = Some(first);
+        loop {
+            self.poll_control(next.is_some());
+            if supervisor::shutdown_requested() {
+                Self::log_shutdown();
+                break;
+            }
+            if let Some(live) = next.take() {
+                let btc_data = &live.candle;
+                last_received = Instant::now();
+                if btc_data.timestamp <= self.current_time && self.data_index > 0 {
+                    ignored += 1;
+                    debug!(
+                        "⏭️  Candle ignorado (repetido ou atrasado): {}",
+                        btc_data.timestamp
+                    );
+                } else {
+                    self.current_time = btc_data.timestamp;
+                    self.enter_batch(batch);
+                    manifest.dataset.update(self.data_index, btc_data);
+                    self.last_candle = Some((self.data_index, btc_data.clone()));
+                    self.process_tick(btc_data)?;
+                    self.data_index += 1;
+
+                    if last_display.elapsed() >= Duration::from_secs(5) {
+                        self.display_status(btc_data);
+                        last_display = Instant::now();
+                    }
+                }
+                feed.acks.push(&live);
+            }
+
+            if last_save.elapsed() >= Duration::from_secs(SAVE_INTERVAL_SECS) {
+                match self.save_state() {
+                    Ok(()) => Self::confirm_entries(&mut feed.acks),
+                    Err(e) => {
+                        error!("❌ Erro ao salvar estado: {}", e);
+                        eprintln!("❌ Erro ao salvar estado: {}", e);
+                    }
+                }
+                last_save = Instant::now();
+            }
+
+            let idle = last_received.elapsed();
+            if idle_timeout.is_some_and(|timeout| idle >= timeout) {
+                info!("⏹️  Nenhum candle há {:.0?}, encerrando paper trading", idle);
+                println!("⏹️  Nenhum candle há {:.0?}, encerrando paper trading", idle);
+                break;
+            }
+            if idle >= Duration::from_secs(IDLE_WARNING_SECS)
+                && last_idle_warning.elapsed() >= Duration::from_secs(IDLE_WARNING_SECS)
+            {
+                warn!(
+                    "⚠️  Nenhum candle de {} há {:.0?} - último: {}",
+                    source,
+                    idle,
+                    self.current_time.format("%Y-%m-%d %H:%M")
+                );
+                println!(
+                    "⚠️  Nenhum candle de {} há {:.0?} - último: {}",
+                    source,
+                    idle,
+                    self.current_time.format("%Y-%m-%d %H:%M")
+                );
+                last_idle_warning = Instant::now();
+            }
+
+            match feed.candles.recv_timeout(Self::LIVE_POLL) {
+                Ok(candle) => next = Some(candle),
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => {
+                    warn!("⚠️  Fonte {} encerrada", source);
+                    println!("⚠️  Fonte {} encerrada", source);
+                    break;
+                }
+            }
+        }
+
+        if ignored > 0 {
+            warn!("⚠️  {} candles repetidos ou atrasados ignorados", ignored);
+            println!("⚠️  {} candles repetidos ou atrasados ignorados", ignored);
+        }
+
+        Ok(())
+    }
+
+    /// Abre o canal de controle da simulação (`control pause|resume|step|...`)
+    pub fn set_control_server(&mut self, server: ControlServer) {
+        self.control.set_server(server);
+    }
+
+    /// Mantém a API HTTP aberta enquanto a simulação roda
+    pub fn set_http_server(&mut self, server: HttpServer) {
+        self.control.set_http_server(server);
+    }
+
+    /// Envio de comandos ao laço, para o socket de controle e a API HTTP
+    pub fn control_handle(&self) -> ControlHandle {
+        self.control.handle()
+    }
+
+    /// Atende os comandos do canal de controle; `tick` indica que a iteração vai
+    /// processar um dado, então a simulação pausada espera aqui e o `step` é contado
+    fn poll_control(&mut self, tick: bool) {
+        while let Some(request) = self.control.next_request(tick) {
+            let reply = self.apply_control(&request.command);
+            request.reply(reply);
+        }
+        if tick {
+            self.control.consume_step();
+        }
+    }
+
+    fn apply_control(&mut self, command: &ControlCommand) -> ControlReply {
+        match command {
+            ControlCommand::Pause => {
+                if self.control.state == RunState::Paused {
+                    return ControlReply::ok("Simulação já estava pausada");
+                }
+                self.control.state = RunState::Paused;
+                info!(
+                    "⏸️  Simulação pausada pelo controle - Data: {}",
+                    self.current_time.format("%Y-%m-%d %H:%M")
+                );
+                println!(
+                    "⏸️  Simulação pausada pelo controle - Data: {}",
+                    self.current_time.format("%Y-%m-%d %H:%M")
+                );
+                ControlReply::ok(format!(
+                    "Simulação pausada em {}",
+                    self.current_time.format("%Y-%m-%d %H:%M")
+                ))
+            }
+            ControlCommand::Resume => {
+                if self.control.state == RunState::Running {
+                    return ControlReply::ok("Simulação não estava pausada");
+                }
+                self.control.state = RunState::Running;
+                info!("▶️  Simulação retomada pelo controle");
+                println!("▶️  Simulação retomada pelo controle");
+                ControlReply::ok("Simulação retomada")
+            }
+            ControlCommand::Step { count } => {
+                if *count == 0 {
+                    return ControlReply::error("step requer ao menos 1 passo");
+                }
+                self.control.state = RunState::Stepping { remaining: *count };
+                info!(
+                    "⏯️  Avançando {} passo(s) - Data: {}",
+                    count,
+                    self.current_time.format("%Y-%m-%d %H:%M")
+                );
+                ControlReply::ok(format!(
+                    "Avançando {} passo(s) a partir de {}",
+                    count,
+                    self.current_time.format("%Y-%m-%d %H:%M")
+                ))
+            }
+            ControlCommand::Speed { speed } => {
+                if self
+                    .control
+                    .session
+                    .as_ref()
+                    .is_some_and(|session| session.default_delay.is_none())
+                {
+                    return ControlReply::error("No paper trading o ritmo é o da fonte de candles");
+                }
+                if let Err(e) = speed.validate() {
+                    return ControlReply::error(e);
+                }
+                self.control.speed = *speed;
+                info!("⏩ Velocidade alterada pelo controle: {}", speed.describe());
+                println!("⏩ Velocidade alterada pelo controle: {}", speed.describe());
+                ControlReply::ok(format!("Velocidade: {}", speed.describe()))
+            }
+            ControlCommand::Snapshot => match self.save_state() {
+                Ok(()) => {
+                    info!(
+                        "📸 Estado salvo pelo controle - Índice: {} - Data: {}",
+                        self.data_index,
+                        self.current_time.format("%Y-%m-%d %H:%M")
+                    );
+                    ControlReply::ok(format!(
+                        "Estado salvo em {} - Índice: {} - Data: {}",
+                        self.state_store.describe(),
+                        self.data_index,
+                        self.current_time.format("%Y-%m-%d %H:%M")
+                    ))
+                }
+                Err(e) => ControlReply::error(format!("Erro ao salvar estado: {}", e)),
+            },
+            ControlCommand::Set { field, value } => match self.set_config_field(field, *value) {
+                Ok(previous) => {
+                    info!(
+                        "⚙️  Configuração alterada pelo controle: {} {} → {}",
+                        field, previous, value
+                    );
+                    println!(
+                        "⚙️  Configuração alterada pelo controle: {} {} → {}",
+                        field, previous, value
+                    );
+                    ControlReply::ok(format!("{}: {} → {}", field, previous, value))
+                }
+                Err(e) => ControlReply::error(e.to_string()),
+            },
+            ControlCommand::Status => ControlReply::with_status(self.live_status()),
+            ControlCommand::Portfolio => ControlReply::with_data(&self.portfolio()),
+            ControlCommand::Orders => ControlReply::with_data(&self.open_orders()),
+            ControlCommand::Transactions {
+                offset,
+                limit,
+                newest_first,
+            } => ControlReply::with_data(&self.transactions_page(*offset, *limit, *newest_first)),
+            ControlCommand::EquityCurve => match self.equity_curve() {
+                Ok(curve) => ControlReply::with_data(&curve),
+                Err(e) => ControlReply::error(e.to_string()),
+            },
+            ControlCommand::Stats => ControlReply::with_data(&self.stats),
+        }
+    }
+
+    /// Altera um dos `SETTABLE_FIELDS` e devolve o valor anterior
+    ///
+    /// A nova configuração vale a partir do próximo dado e é salva junto com o estado.
+    fn set_config_field(
+        &mut self,
+        field: &str,
+        value: f64,
+    ) -> Result<f64, Box<dyn std::error::Error>> {
+        if !value.is_finite() || !(0.0..=100.0).contains(&value) {
+            return Err(format!("{} deve estar entre 0 e 100", field).into());
+        }
+        let slot = match field {
+            "trade_percentage" => &mut self.config.trade_percentage,
+            "take_profit_percentage" => &mut self.config.take_profit_percentage,
+            "percentual_queda_para_comprar" => &mut self.config.percentual_queda_para_comprar,
+            _ if INACTIVE_FIELDS.contains(&field) => {
+                return Err(format!(
+                    "Campo '{}' não é usado pela estratégia (sem stop loss) e alterá-lo não teria efeito",
+                    field
+                )
+                .into())
+            }
+            _ => {
+                return Err(format!(
+                    "Campo '{}' não pode ser alterado (campos: {})",
+                    field,
+                    SETTABLE_FIELDS.join(", ")
+                )
+                .into())
+            }
+        };
+        if value == 0.0 {
+            return Err(format!("{} deve ser maior que zero", field).into());
+        }
+        Ok(std::mem::replace(slot, value))
+    }
+
+    /// Pausa entre dois dados conforme a velocidade do controle
+    ///
+    /// A espera atende comandos do controle e termina cedo com um sinal de parada, uma
+    /// pausa ou uma mudança de velocidade (que passa a valer a partir daí).
+    fn pace(&mut self) {
+        let start = Instant::now();
+        loop {
+            let remaining = self.control.tick_delay().saturating_sub(start.elapsed());
+            if remaining.is_zero()
+                || supervisor::shutdown_requested()
+                || self.control.state == RunState::Paused
+            {
+                return;
+            }
+            if let Some(request) = self.control.wait_request(remaining) {
+                let reply = self.apply_control(&request.command);
+                request.reply(reply);
+            }
+        }
+    }
+
+    /// Ordens abertas avaliadas ao último preço processado
+    fn open_orders(&self) -> Vec<OpenOrderStatus> {
+        let price = self.preco_anterior.unwrap_or(0.0);
+        self.buy_orders
+            .iter()
+            .map(|order| {
+                let value = order.btc_quantity * price;
+                OpenOrderStatus {
+                    id: order.id,
+                    btc_quantity: order.btc_quantity,
+                    buy_price: order.buy_price,
+                    buy_time: order.buy_time,
+                    invested_amount: order.invested_amount,
+                    take_profit_price: order.buy_price
+                        * (1.0 + self.config.take_profit_percentage / 100.0),
+                    unrealized_pnl: value - order.invested_amount,
+                    unrealized_pnl_pct: (value / order.invested_amount - 1.0) * 100.0,
+                }
+            })
+            .collect()
+    }
+
+    fn portfolio(&self) -> Portfolio {
+        let price = self.preco_anterior.unwrap_or(0.0);
+        let btc_value = self.saldo_btc * price;
+        let equity = self.saldo_fiat + btc_value;
+        let profit_loss = equity - self.config.initial_balance;
+        let unrealized_pnl = self
+            .buy_orders
+            .iter()
+            .map(|order| order.btc_quantity * price - order.invested_amount)
+            .sum();
+
+        Portfolio {
+            sim_time: self.current_time,
+            last_price: self.preco_anterior,
+            fiat_balance: self.saldo_fiat,
+            btc_balance: self.saldo_btc,
+            btc_value,
+            equity,
+            profit_loss,
+            profit_loss_pct: (profit_loss / self.config.initial_balance) * 100.0,
+            total_invested: self.total_investido,
+            unrealized_pnl,
+            open_orders: self.buy_orders.len(),
+        }
+    }
+
+    /// Página do histórico de transações (`offset` a partir da mais antiga ou, com
+    /// `newest_first`, da mais recente)
+    fn transactions_page(&self, offset: usize, limit: usize, newest_first: bool) -> TransactionPage {
+        let items: Vec<Transaction> = if newest_first {
+            self.transaction_history.iter().rev().skip(offset).take(limit).cloned().collect()
+        } else {
+            self.transaction_history.iter().skip(offset).take(limit).cloned().collect()
+        };
+        TransactionPage {
+            total: self.transaction_history.len(),
+            offset,
+            limit,
+            items,
+        }
+    }
+
+    /// Status ao vivo do laço em execução (comando `control status`)
+    fn live_status(&self) -> LiveStatus {
+        let session = self.control.session.as_ref();
+        let uptime_secs = session.map_or(0.0, |session| session.started.elapsed().as_secs_f64());
+        let processed = session.map_or(0, |session| {
+            self.data_index.saturating_sub(session.start_index)
+        });
+
+        LiveStatus {
+            simulation: self.state_store.run_id().to_string(),
+            pid: std::process::id(),
+            run_id: session.map(|session| session.run_id.clone()),
+            source: session.map(|session| session.source.clone()),
+            state: self.control.state,
+            speed: match session {
+                Some(session) if session.default_delay.is_none() => "ritmo da fonte".to_string(),
+                _ => self.control.speed.describe(),
+            },
+            started_at: session.map(|session| session.started_at),
+            uptime_secs,
+            processed,
+            processed_per_sec: if uptime_secs > 0.0 {
+                processed as f64 / uptime_secs
+            } else {
+                0.0
+            },
+            end_time: self.end_time,
+            data_index: self.data_index,
+            total_records: self.total_records,
+            progress_pct: (self.data_index as f64 / self.total_records.max(1) as f64) * 100.0,
+            peak_price: self.preco_pico_recente,
+            portfolio: self.portfolio(),
+            realized_pnl: self.stats.net_profit(),
+            total_trades: self.stats.total_trades,
+            winning_trades: self.stats.winning_trades,
+            losing_trades: self.stats.losing_trades,
+            current_drawdown: self.stats.current_drawdown,
+            max_drawdown: self.stats.max_drawdown,
+            total_funding: self.stats.total_funding,
+            candles_missing: self.stats.candles_missing,
+            candles_filled: self.stats.candles_filled,
+            drops_detected: self.quedas_detectadas,
+            drops_to_buy: self.quedas_para_comprar,
+            orders: self.open_orders(),
+            config: self.config.clone(),
+            state_store: self.state_store.describe(),
+        }
+    }
+
+    fn log_shutdown() {
+        warn!("🛑 Sinal de parada recebido - salvando estado e relatório final");
+        println!("\n🛑 Sinal de parada recebido - salvando estado e relatório final");
+    }
+
+    /// Reinicia o relógio e os benchmarks no primeiro dado de uma fonte externa
+    fn start_at(&mut self, time: DateTime<Utc>) {
+        self.current_time = time;
+        self.benchmarks =
+            benchmark::default_benchmarks(self.config.initial_balance, time, self.end_time);
+    }
+
+    /// Exibe a configuração e registra o manifesto de uma nova execução
+    ///
+    /// `default_delay` é a pausa padrão entre dados do modo; `None` quando o ritmo é
+    /// o da fonte e a velocidade não pode ser alterada.
+    fn begin_run(
+        &mut self,
+        source: &str,
+        default_delay: Option<Duration>,
+    ) -> Result<(RunManifest, EnteredSpan), Box<dyn std::error::Error>> {
+        let manifest = RunManifest::start(
+            &self.config,
+            self.run_settings(),
+            source,
+            self.data_index,
+            self.current_time,
+        )?;
+        // Span `run`: todos os eventos da execução levam o run_id e a fonte
+        let run_span = info_span!(
+            "run",
+            run_id = %manifest.run_id,
+            simulation = %self.state_store.run_id(),
+            source = %source,
+        )
+        .entered();
+
+        info!("🚀 Iniciando simulador de trade BTC");
+        info!("💰 Saldo inicial: ${:.2}", self.config.initial_balance);
+        info!(
+            "📊 Perda máxima aceitável: {:.1}%",
+            self.config.max_loss_percentage
+        );
+        info!(
+            "🎯 Stop Loss: {:.1}% | Take Profit: {:.1}%",
+            self.config.stop_loss_percentage, self.config.take_profit_percentage
+        );
+        info!("⏰ Período: {} até {}", self.current_time, self.end_time);
+
+        // Manter println para interface do usuário
+        println!("🚀 Iniciando simulador de trade BTC");
+        println!("💰 Saldo inicial: ${:.2}", self.config.initial_balance);
+        println!(
+            "📊 Perda máxima aceitável: {:.1}%",
+            self.config.max_loss_percentage
+        );
+        println!(
+            "🎯 Stop Loss: {:.1}% | Take Profit: {:.1}%",
+            self.config.stop_loss_percentage, self.config.take_profit_percentage
+        );
+        println!("⏰ Período: {} até {}", self.current_time, self.end_time);
+        println!("{}", "=".repeat(80));
+
+        // Curva desta execução, continuando a anterior quando o estado foi retomado
+        let legacy = std::mem::take(&mut self.legacy_equity_curve);
+        self.equity_log = Some(EquityLog::create(
+            &manifest.files.equity,
+            self.equity_file.as_deref(),
+            self.last_equity_time,
+            &legacy,
+        )?);
+        self.equity_file = Some(manifest.files.equity.clone());
+
+        let mut legacy: Vec<BenchmarkSample> = Vec::new();
+        for benchmark in &mut self.benchmarks {
+            let name = benchmark.name();
+            legacy.extend(std::mem::take(&mut benchmark.samples).into_iter().map(
+                |(time, value)| BenchmarkSample {
+                    time,
+                    benchmark: name.to_string(),
+                    value,
+                },
+            ));
+        }
+        legacy.sort_by_key(|sample| sample.time);
+        self.benchmark_log = Some(BenchmarkLog::create(
+            &manifest.files.benchmarks,
+            self.benchmarks_file.as_deref(),
+            self.last_equity_time,
+            &legacy,
+        )?);
+        self.benchmarks_file = Some(manifest.files.benchmarks.clone());
+
+        if let Some(events) = &mut self.events {
+            events.set_run_id(&manifest.run_id);
+        }
+        self.control.session = Some(Session {
+            run_id: manifest.run_id.clone(),
+            source: source.to_string(),
+            started_at: Utc::now(),
+            started: Instant::now(),
+            start_index: self.data_index,
+            default_delay,
+        });
+        Ok((manifest, run_span))
+    }
+
+    /// Horário simulado em RFC 3339, para os campos `sim_time` dos logs
+    fn sim_time(time: DateTime<Utc>) -> String {
+        time.to_rfc3339_opts(SecondsFormat::Secs, true)
+    }
+
+    /// Abre o span `batch` do lote do dado atual (`TICK_BATCH` dados), fechando o anterior
+    fn enter_batch(&self, batch: &mut Option<TickBatch>) {
+        let number = self.data_index / Self::TICK_BATCH;
+        if batch.as_ref().is_some_and(|batch| batch.number == number) {
+            return;
+        }
+        self.close_batch(batch);
+        let span = info_span!(
+            "batch",
+            batch = number,
+            first_index = self.data_index,
+            sim_start = %Self::sim_time(self.current_time),
+            sim_end = field::Empty,
+            trades = self.stats.total_trades,
+        )
+        .entered();
+        *batch = Some(TickBatch { number, span });
+    }
+
+    /// Fecha o span `batch` registrando até onde o lote foi
+    fn close_batch(&self, batch: &mut Option<TickBatch>) {
+        if let Some(batch) = batch.take() {
+            batch
+                .span
+                .record("sim_end", field::display(Self::sim_time(self.current_time)));
+        }
+    }
+
+    /// Salva o estado final, exporta a execução e exibe o relatório
+    ///
+    /// Também chamado quando o laço termina com erro: o manifesto fica como `failed`,
+    /// com o erro, e o estado e os artefatos até o ponto da falha são gravados.
+    /// Retorna se o estado final foi salvo.
+    fn finish_run(
+        &mut self,
+        manifest: &mut RunManifest,
+        start_simulation: Instant,
+        result: &Result<(), Box<dyn std::error::Error>>,
+    ) -> bool {
+        let error = result.as_ref().err().map(|e| e.to_string());
+        match &error {
+            Some(e) => error!("❌ Execução {} interrompida: {}", manifest.run_id, e),
+            None => info!("🏁 Simulação concluída!"),
+        }
         info!(
             "⏱️  Tempo total de simulação: {:.2?}",
             start_simulation.elapsed()
         );
 
         // Salvar estado final
-        if let Err(e) = self.save_state() {
-            error!("❌ Erro ao salvar estado final: {}", e);
-            eprintln!("❌ Erro ao salvar estado final: {}", e);
+        let saved = match self.save_state() {
+            Ok(()) => {
+                info!("💾 Estado final salvo com sucesso");
+                println!("💾 Estado final salvo com sucesso");
+                true
+            }
+            Err(e) => {
+                error!("❌ Erro ao salvar estado final: {}", e);
+                eprintln!("❌ Erro ao salvar estado final: {}", e);
+                false
+            }
+        };
+
+        // Entregar os eventos ainda na fila
+        if let Some(events) = self.events.take() {
+            events.close();
+        }
+
+        // Registrar manifesto e artefatos da execução
+        manifest.finish(self.data_index, self.current_time, error.clone());
+        if let Err(e) = self.export_run(manifest) {
+            error!("❌ Erro ao exportar execução {}: {}", manifest.run_id, e);
+            eprintln!("❌ Erro ao exportar execução {}: {}", manifest.run_id, e);
         } else {
-            info!("💾 Estado final salvo com sucesso");
-            println!("💾 Estado final salvo com sucesso");
+            info!(
+                "🧾 Execução {} exportada em {} (dataset {} - {} registros)",
+                manifest.run_id,
+                manifest.run_dir().display(),
+                manifest.dataset.hash,
+                manifest.dataset.records
+            );
+            println!(
+                "🧾 Execução {} exportada em {}",
+                manifest.run_id,
+                manifest.run_dir().display()
+            );
         }
 
         // Manter println para interface do usuário
         println!("\n{}", "=".repeat(80));
-        println!("🏁 Simulação concluída!");
+        match &error {
+            Some(e) => println!("❌ Execução {} interrompida: {}", manifest.run_id, e),
+            None => println!("🏁 Simulação concluída!"),
+        }
+        let equity_curve = self.equity_curve().unwrap_or_else(|e| {
+            warn!("⚠️  Curva de patrimônio indisponível: {}", e);
+            Vec::new()
+        });
         self.display_transaction_history();
         self.display_final_stats();
+        self.display_period_breakdown(&equity_curve);
         println!(
             "⏱️  Tempo total de simulação: {:.2?}",
             start_simulation.elapsed()
         );
-
-        Ok(())
+        saved
     }
 
-    fn get_current_btc_data(&self) -> Result<Option<CsvBtcFile>, Box<dyn std::error::Error>> {
+    fn get_current_btc_data(&mut self) -> Result<Option<CsvBtcFile>, Box<dyn std::error::Error>> {
+        if let Some(cache) = &self.candle_cache {
+            return Ok(cache.get(self.data_index));
+        }
         self.redis_client.load_by_index(self.data_index)
     }
 
+    /// Gera um candle para o índice atual sem dados, se houver candle real antes e depois
+    ///
+    /// Exigir o próximo candle real evita negociar sobre preços inventados no fim do dataset.
+    fn fill_missing_candle(&mut self) -> Result<Option<CsvBtcFile>, Box<dyn std::error::Error>> {
+        if self.fill_policy == FillPolicy::Skip {
+            return Ok(None);
+        }
+        let Some((last_index, last)) = self.last_candle.clone() else {
+            return Ok(None);
+        };
+        let Some((next_index, next)) = self.next_real_candle()? else {
+            return Ok(None);
+        };
+
+        let fraction =
+            (self.data_index - last_index) as f64 / (next_index - last_index) as f64;
+        let next = (self.fill_policy == FillPolicy::Interpolate).then_some(&next);
+        Ok(Some(data_quality::synthetic_candle(
+            &last,
+            next,
+            fraction,
+            self.current_time,
+        )))
+    }
+
+    /// Próximo candle real após o índice atual (consulta o Redis uma vez por gap)
+    fn next_real_candle(
+        &mut self,
+    ) -> Result<Option<(usize, CsvBtcFile)>, Box<dyn std::error::Error>> {
+        if let Some(cache) = &self.candle_cache {
+            // No cache a consulta é só leitura do mmap: procurar a cada gap
+            return Ok((self.data_index + 1..)
+                .take(Self::FILL_LOOKAHEAD)
+                .find_map(|index| cache.get(index).map(|candle| (index, candle))));
+        }
+        match &self.next_candle {
+            Some((index, _)) if *index > self.data_index => return Ok(self.next_candle.clone()),
+            // O candle guardado ficou para trás (gap anterior): consultar de novo
+            Some(_) => {}
+            // Nada adiante dentro da janela já consultada
+            None if self.data_index < self.lookahead_checked_until => return Ok(None),
+            None => {}
+        }
+
+        let start = self.data_index + 1;
+        let window = self.redis_client.load_range(start, Self::FILL_LOOKAHEAD)?;
+        self.next_candle = window
+            .into_iter()
+            .enumerate()
+            .find_map(|(offset, candle)| candle.map(|c| (start + offset, c)));
+        self.lookahead_checked_until = start + Self::FILL_LOOKAHEAD;
+        Ok(self.next_candle.clone())
+    }
+
     fn process_tick(&mut self, btc_data: &CsvBtcFile) -> Result<(), Box<dyn std::error::Error>> {
         let current_price = btc_data.close;
 
@@ -421,6 +1525,13 @@ impl TradeSimulator {
             self.preco_pico_recente = current_price;
         }
 
+        // 0. Aplicar funding nas posições abertas a cada 8h de tempo simulado
+        self.aplicar_funding(current_price);
+
+        for benchmark in &mut self.benchmarks {
+            benchmark.on_tick(self.current_time, current_price);
+        }
+
         // 1. Verificar condições de COMPRA por queda de preço
         if self.saldo_fiat > 0.0 {
             let mut should_buy = false;
@@ -429,7 +1540,14 @@ impl TradeSimulator {
             // Se não tem BTC e nunca comprou, comprar na primeira oportunidade
             if self.saldo_btc == 0.0 && self.stats.total_trades == 0 {
                 should_buy = true;
-                info!("🎯 PRIMEIRA COMPRA detectada!");
+                info!(
+                    event = "buy_signal",
+                    side = "buy",
+                    price = current_price,
+                    reason = BuyKind::First.as_str(),
+                    sim_time = %Self::sim_time(self.current_time),
+                    "🎯 PRIMEIRA COMPRA detectada!"
+                );
                 // Log já adicionado acima, manter println para interface
                 println!("🎯 PRIMEIRA COMPRA detectada!");
             }
@@ -444,13 +1562,33 @@ impl TradeSimulator {
                     if queda_percentual >= queda_dupla {
                         should_buy = true;
                         self.quedas_detectadas = 0; // Reset contador após compra de emergência
+                        self.emit(SimEvent::EmergencyBuy {
+                            drop_percentage: queda_percentual,
+                            threshold_percentage: queda_dupla,
+                            peak_price: self.preco_pico_recente,
+                            price: current_price,
+                        });
                         warn!(
+                            event = "emergency_buy",
+                            side = "buy",
+                            price = current_price,
+                            peak_price = self.preco_pico_recente,
+                            drop_pct = queda_percentual,
+                            reason = BuyKind::Emergency.as_str(),
+                            sim_time = %Self::sim_time(self.current_time),
                             "🚨 COMPRA DE EMERGÊNCIA! Queda -{:.2}% (>= -{:.1}% dobro do gatilho)",
-                            queda_percentual, queda_dupla
+                            queda_percentual,
+                            queda_dupla
                         );
                         warn!(
+                            side = "buy",
+                            price = current_price,
+                            peak_price = self.preco_pico_recente,
+                            reason = BuyKind::Emergency.as_str(),
+                            sim_time = %Self::sim_time(self.current_time),
                             "⚡ EXECUTANDO COMPRA IMEDIATA do pico ${:.2} para ${:.2}",
-                            self.preco_pico_recente, current_price
+                            self.preco_pico_recente,
+                            current_price
                         );
                         // Log já adicionado acima, manter println para interface
                         println!(
@@ -464,8 +1602,22 @@ impl TradeSimulator {
                     } else {
                         // Lógica normal: incrementar contador de quedas
                         self.quedas_detectadas += 1;
+                        self.emit(SimEvent::DropDetected {
+                            count: self.quedas_detectadas,
+                            required: self.quedas_para_comprar,
+                            drop_percentage: queda_percentual,
+                            peak_price: self.preco_pico_recente,
+                            price: current_price,
+                        });
 
                         debug!(
+                            event = "drop_detected",
+                            price = current_price,
+                            peak_price = self.preco_pico_recente,
+                            drop_pct = queda_percentual,
+                            drops = self.quedas_detectadas,
+                            drops_required = self.quedas_para_comprar,
+                            sim_time = %Self::sim_time(self.current_time),
                             "📉 QUEDA DETECTADA #{}: -{:.2}% do pico ${:.2} para ${:.2}",
                             self.quedas_detectadas,
                             queda_percentual,
@@ -486,6 +1638,11 @@ impl TradeSimulator {
                             should_buy = true;
                             self.quedas_detectadas = 0; // Reset contador após compra
                             info!(
+                                event = "buy_signal",
+                                side = "buy",
+                                price = current_price,
+                                reason = BuyKind::Drop.as_str(),
+                                sim_time = %Self::sim_time(self.current_time),
                                 "✅ COMPRA LIBERADA: {} quedas atingidas!",
                                 self.quedas_para_comprar
                             );
@@ -496,6 +1653,11 @@ impl TradeSimulator {
                             );
                         } else {
                             debug!(
+                                event = "buy_waiting",
+                                price = current_price,
+                                drops = self.quedas_detectadas,
+                                drops_required = self.quedas_para_comprar,
+                                sim_time = %Self::sim_time(self.current_time),
                                 "⏳ AGUARDANDO: {}/{} quedas para próxima compra (ou queda -{:.1}% para emergência)",
                                 self.quedas_detectadas, self.quedas_para_comprar, queda_dupla
                             );
@@ -520,9 +1682,20 @@ impl TradeSimulator {
                 if total_apos_compra <= limite_investimento {
                     self.realizar_compra(current_price)?;
                 } else {
+                    self.emit(SimEvent::CapReached {
+                        total_after_buy: total_apos_compra,
+                        limit: limite_investimento,
+                    });
                     warn!(
+                        event = "cap_reached",
+                        side = "buy",
+                        price = current_price,
+                        amount = valor_proxima_compra,
+                        reason = "investment_cap",
+                        sim_time = %Self::sim_time(self.current_time),
                         "🚫 COMPRA CANCELADA: Limite de 90% da carteira atingido (${:.2}/{:.2})",
-                        total_apos_compra, limite_investimento
+                        total_apos_compra,
+                        limite_investimento
                     );
                     // Log já adicionado acima, manter println para interface
                     println!(
@@ -585,17 +1758,35 @@ impl TradeSimulator {
         self.next_transaction_id += 1;
         self.stats.total_trades += 1;
 
-        let tipo_compra = if self.buy_orders.len() == 1 {
+        let (tipo_compra, kind) = if self.buy_orders.len() == 1 {
             if self.stats.total_trades == 1 {
-                "PRIMEIRA COMPRA"
+                ("PRIMEIRA COMPRA", BuyKind::First)
             } else {
-                "COMPRA DE EMERGÊNCIA"
+                ("COMPRA DE EMERGÊNCIA", BuyKind::Emergency)
             }
         } else {
-            "COMPRA POR QUEDA"
+            ("COMPRA POR QUEDA", BuyKind::Drop)
         };
 
+        self.emit(SimEvent::Buy {
+            order_id: self.next_order_id - 1,
+            kind,
+            btc_quantity: quantidade_btc_a_comprar,
+            price,
+            amount: quantidade_fiat_para_comprar,
+            fiat_balance: self.saldo_fiat,
+            open_orders: self.buy_orders.len(),
+        });
+
         info!(
+            event = "buy",
+            order_id = self.next_order_id - 1,
+            side = "buy",
+            price,
+            qty = quantidade_btc_a_comprar,
+            amount = quantidade_fiat_para_comprar,
+            reason = kind.as_str(),
+            sim_time = %Self::sim_time(self.current_time),
             "🎯 {} REALIZADA - Ordem #{} - {:.6} BTC @ ${:.2} - Investido: ${:.2}",
             tipo_compra,
             self.next_order_id - 1,
@@ -691,7 +1882,30 @@ impl TradeSimulator {
         let holding_days = holding_duration.num_days();
         let holding_hours = holding_duration.num_hours() % 24;
 
+        self.emit(SimEvent::Sell {
+            order_id: order.id,
+            btc_quantity: order.btc_quantity,
+            buy_price: order.buy_price,
+            sell_price: current_price,
+            amount: sale_amount,
+            profit,
+            profit_percentage,
+            holding_hours: holding_duration.num_hours(),
+        });
+
         info!(
+            event = "sell",
+            order_id = order.id,
+            side = "sell",
+            price = current_price,
+            qty = order.btc_quantity,
+            amount = sale_amount,
+            pnl = profit,
+            pnl_pct = profit_percentage,
+            buy_price = order.buy_price,
+            holding_hours = holding_duration.num_hours(),
+            reason = "take_profit",
+            sim_time = %Self::sim_time(self.current_time),
             "💚 VENDA COM LUCRO - Ordem #{} - {:.6} BTC @ ${:.2} - Lucro: ${:.2} ({:.2}%) - Holding: {}d {}h",
             order.id,
             order.btc_quantity,
@@ -733,6 +1947,143 @@ impl TradeSimulator {
         Ok(())
     }
 
+    fn aplicar_funding(&mut self, current_price: f64) {
+        if self.funding.is_none() {
+            return;
+        }
+
+        while let Some(funding_time) = self.next_funding_time {
+            if self.current_time < funding_time {
+                break;
+            }
+            self.next_funding_time = Some(FundingSchedule::next_funding_time(funding_time));
+
+            if self.saldo_btc <= 0.0 {
+                continue;
+            }
+            let Some(rate) = self
+                .funding
+                .as_ref()
+                .and_then(|funding| funding.rate_at(funding_time))
+            else {
+                continue;
+            };
+
+            // Posições compradas pagam funding quando a taxa é positiva
+            let notional = self.saldo_btc * current_price;
+            let payment = -notional * rate;
+
+            self.saldo_fiat += payment;
+            self.stats.total_funding += payment;
+
+            self.transaction_history.push(Transaction {
+                id: self.next_transaction_id,
+                transaction_type: "FUNDING".to_string(),
+                btc_quantity: self.saldo_btc,
+                price: current_price,
+                time: funding_time,
+                amount: payment,
+                profit_loss: Some(payment),
+                buy_order_id: None,
+            });
+            self.next_transaction_id += 1;
+
+            debug!(
+                event = "funding",
+                price = current_price,
+                qty = self.saldo_btc,
+                amount = payment,
+                pnl = payment,
+                rate,
+                reason = "funding_rate",
+                sim_time = %Self::sim_time(funding_time),
+                "💱 FUNDING {} - Taxa: {:.4}% - Posição: ${:.2} - Pagamento: ${:.4} - Acumulado: ${:.4}",
+                funding_time.format("%Y-%m-%d %H:%M"),
+                rate * 100.0,
+                notional,
+                payment,
+                self.stats.total_funding
+            );
+
+            // Sem margem em fiat para o pagamento: reduzir a posição em vez de ficar negativo
+            if self.saldo_fiat < 0.0 {
+                self.liquidar_por_margem(current_price, funding_time);
+            }
+        }
+    }
+
+    /// Vende ordens abertas ao preço atual, da mais recente para a mais antiga, até o saldo
+    /// fiat voltar a ser positivo
+    ///
+    /// As vendas entram no histórico como `SELL` comuns (P&L realizado, ganhos/perdas), com
+    /// o motivo `funding_margin` no log e um evento `liquidation`.
+    fn liquidar_por_margem(&mut self, current_price: f64, time: DateTime<Utc>) {
+        while self.saldo_fiat < 0.0 {
+            let Some(order) = self.buy_orders.pop() else {
+                break;
+            };
+            let sale_amount = order.btc_quantity * current_price;
+            let profit = sale_amount - order.invested_amount;
+
+            self.saldo_fiat += sale_amount;
+            self.saldo_btc -= order.btc_quantity;
+            self.total_investido -= order.invested_amount;
+            if profit >= 0.0 {
+                self.stats.winning_trades += 1;
+                self.stats.total_profit += profit;
+            } else {
+                self.stats.losing_trades += 1;
+                self.stats.total_loss += -profit;
+            }
+
+            self.transaction_history.push(Transaction {
+                id: self.next_transaction_id,
+                transaction_type: "SELL".to_string(),
+                btc_quantity: order.btc_quantity,
+                price: current_price,
+                time,
+                amount: sale_amount,
+                profit_loss: Some(profit),
+                buy_order_id: Some(order.id),
+            });
+            self.next_transaction_id += 1;
+
+            warn!(
+                event = "liquidation",
+                order_id = order.id,
+                side = "sell",
+                price = current_price,
+                qty = order.btc_quantity,
+                amount = sale_amount,
+                pnl = profit,
+                reason = "funding_margin",
+                sim_time = %Self::sim_time(time),
+                "⚠️  LIQUIDAÇÃO - Ordem #{} vendida para cobrir funding - {:.6} BTC @ ${:.2} - P&L: ${:.2}",
+                order.id,
+                order.btc_quantity,
+                current_price,
+                profit
+            );
+            // Manter println para interface do usuário
+            println!(
+                "⚠️  LIQUIDAÇÃO - Ordem #{} vendida para cobrir funding - {:.6} BTC @ ${:.2} - P&L: ${:.2}",
+                order.id, order.btc_quantity, current_price, profit
+            );
+            self.emit(SimEvent::Liquidation {
+                order_id: order.id,
+                btc_quantity: order.btc_quantity,
+                buy_price: order.buy_price,
+                sell_price: current_price,
+                amount: sale_amount,
+                profit,
+                fiat_balance: self.saldo_fiat,
+            });
+        }
+        if self.buy_orders.is_empty() {
+            self.saldo_btc = 0.0;
+        }
+    }
+
     fn update_portfolio_value(&mut self, current_price: f64) {
         // Atualizar o valor do portfolio incluindo BTC holdings
         let total_value = self.saldo_fiat + (self.saldo_btc * current_price);
@@ -746,6 +2097,40 @@ impl TradeSimulator {
         if self.stats.current_drawdown > self.stats.max_drawdown {
             self.stats.max_drawdown = self.stats.current_drawdown;
         }
+
+        // Amostrar curva de patrimônio a cada hora simulada
+        let should_sample = match self.last_equity_time {
+            Some(last) => self.current_time >= last + chrono::Duration::hours(1),
+            None => true,
+        };
+        if should_sample {
+            let point = EquityPoint {
+                time: self.current_time,
+                price: current_price,
+                saldo_fiat: self.saldo_fiat,
+                saldo_btc: self.saldo_btc,
+                total_value,
+                drawdown: self.stats.current_drawdown,
+            };
+            if let Some(log) = &mut self.equity_log {
+                if let Err(e) = log.append(&point) {
+                    error!("❌ Erro ao gravar curva de patrimônio: {}", e);
+                    eprintln!("❌ Erro ao gravar curva de patrimônio: {}", e);
+                    self.equity_log = None;
+                }
+            }
+            self.last_equity_time = Some(self.current_time);
+            for benchmark in &mut self.benchmarks {
+                let sample = benchmark.sample(self.current_time, total_value);
+                if let Some(log) = &mut self.benchmark_log {
+                    if let Err(e) = log.append(&sample) {
+                        error!("❌ Erro ao gravar curva do benchmark: {}", e);
+                        eprintln!("❌ Erro ao gravar curva do benchmark: {}", e);
+                        self.benchmark_log = None;
+                    }
+                }
+            }
+        }
     }
 
 
@@ -801,7 +2186,7 @@ impl TradeSimulator {
                 self.buy_orders.len()
             );
 
-            for (_i, order) in self.buy_orders.iter().take(3).enumerate() {
+            for order in self.buy_orders.iter().take(3) {
                 let unrealized_pnl = (btc_data.close - order.buy_price) * order.btc_quantity;
                 let unrealized_percent =
                     ((btc_data.close - order.buy_price) / order.buy_price) * 100.0;
@@ -864,7 +2249,9 @@ impl TradeSimulator {
 
         let mut buy_count = 0;
         let mut sell_count = 0;
+        let mut funding_count = 0;
         let mut total_profit = 0.0;
+        let mut total_funding = 0.0;
 
         for transaction in &self.transaction_history {
             match transaction.transaction_type.as_str() {
@@ -924,6 +2311,11 @@ impl TradeSimulator {
                         profit_percent
                     );
                 }
+                "FUNDING" => {
+                    // Pagamentos de funding são muitos; apenas acumulados no resumo
+                    funding_count += 1;
+                    total_funding += transaction.amount;
+                }
                 _ => {}
             }
         }
@@ -939,6 +2331,17 @@ impl TradeSimulator {
             buy_count, sell_count, total_profit
         );
 
+        if funding_count > 0 {
+            info!(
+                "💱 FUNDING: {} pagamentos, P&L de funding: ${:.2}",
+                funding_count, total_funding
+            );
+            println!(
+                "║ 💱 FUNDING: {} pagamentos │ P&L de funding: ${:<12.2}                            ║",
+                funding_count, total_funding
+            );
+        }
+
         // Mostrar ordens ainda abertas
         if !self.buy_orders.is_empty() {
             info!(
@@ -976,9 +2379,117 @@ impl TradeSimulator {
         println!("╚{:═<98}╝", "");
     }
 
+    /// Agregação mensal, trimestral e anual da execução
+    fn period_breakdown(&self, equity_curve: &[EquityPoint]) -> Vec<PeriodBreakdown> {
+        PeriodKind::ALL
+            .iter()
+            .flat_map(|kind| {
+                periods::aggregate(
+                    *kind,
+                    equity_curve,
+                    &self.transaction_history,
+                    self.config.initial_balance,
+                )
+            })
+            .collect()
+    }
+
+    fn display_period_breakdown(&self, equity_curve: &[EquityPoint]) {
+        let breakdown = self.period_breakdown(equity_curve);
+        if breakdown.is_empty() {
+            return;
+        }
+
+        for (kind, title) in [
+            (PeriodKind::Year, "📅 RESULTADOS ANUAIS"),
+            (PeriodKind::Quarter, "📅 RESULTADOS TRIMESTRAIS"),
+            (PeriodKind::Month, "📅 RESULTADOS MENSAIS"),
+        ] {
+            println!("\n╔{:═<98}╗", "");
+            println!("║{:^98}║", title);
+            println!("╠{:═<98}╣", "");
+            println!(
+                "║ {:<9} │ {:>12} │ {:>12} │ {:>12} │ {:>10} │ {:>14} │ {:>6} │ {:>8} ║",
+                "Período", "Inicial", "Final", "Realizado", "Funding", "Não realizado", "Trades", "Retorno"
+            );
+            println!("╠{:─<98}╣", "");
+
+            for period in breakdown.iter().filter(|p| p.kind == kind.name()) {
+                if kind == PeriodKind::Year {
+                    info!(
+                        "📅 {} - Retorno: {:+.2}% | Realizado: ${:.2} | Não realizado: ${:.2} | Funding: ${:.2} | Trades: {}",
+                        period.period,
+                        period.return_percentage,
+                        period.realised_pnl,
+                        period.unrealised_change,
+                        period.funding_pnl,
+                        period.trade_count
+                    );
+                }
+                println!(
+                    "║ {:<9} │ {:>12.2} │ {:>12.2} │ {:>12.2} │ {:>10.2} │ {:>14.2} │ {:>6} │ {:>+7.2}% ║",
+                    period.period,
+                    period.start_value,
+                    period.end_value,
+                    period.realised_pnl,
+                    period.funding_pnl,
+                    period.unrealised_change,
+                    period.trade_count,
+                    period.return_percentage
+                );
+            }
+            println!("╚{:═<98}╝", "");
+        }
+    }
+
+    fn display_benchmarks(&self, net_return: f64) {
+        if self.benchmarks.is_empty() {
+            return;
+        }
+
+        println!("╠{:─<78}╣", "");
+        println!("║ 📊 BENCHMARKS (mesmos candles, mesmo saldo inicial):                        ║");
+        println!(
+            "║ {:<12} │ {:>9} │ {:>8} │ {:>9} │ {:>9} │ {:>8} │ {:>6} ║",
+            "Benchmark", "Retorno", "DD máx", "Δ Retorno", "Δ DD", "Alfa a.a.", "Beta"
+        );
+
+        for benchmark in &self.benchmarks {
+            let return_diff = net_return - benchmark.return_percentage();
+            let drawdown_diff = self.stats.max_drawdown - benchmark.max_drawdown;
+            let alpha_beta = benchmark.returns.alpha_beta();
+            let (alpha, beta) = match alpha_beta {
+                Some((alpha, beta)) => (format!("{:+.2}%", alpha), format!("{:.3}", beta)),
+                None => ("-".to_string(), "-".to_string()),
+            };
+
+            info!(
+                "📊 BENCHMARK {}: Retorno {:.2}% | DD máx {:.2}% | Δ retorno {:+.2}pp | Δ drawdown {:+.2}pp | Alfa {} | Beta {}",
+                benchmark.name(),
+                benchmark.return_percentage(),
+                benchmark.max_drawdown,
+                return_diff,
+                drawdown_diff,
+                alpha,
+                beta
+            );
+            println!(
+                "║ {:<12} │ {:>8.2}% │ {:>7.2}% │ {:>+8.2}pp │ {:>+7.2}pp │ {:>9} │ {:>6} ║",
+                benchmark.name(),
+                benchmark.return_percentage(),
+                benchmark.max_drawdown,
+                return_diff,
+                drawdown_diff,
+                alpha,
+                beta
+            );
+        }
+    }
+
     fn display_final_stats(&self) {
-        // Calcular valor total incluindo BTC restante se houver
-        let total_value = self.saldo_fiat + (self.saldo_btc * 50000.0); // Assumir preço final
+        // Calcular valor total incluindo BTC restante ao último preço processado
+        let preco_final = self.preco_anterior.unwrap_or(0.0);
+        let total_value = self.saldo_fiat + (self.saldo_btc * preco_final);
         let net_return =
             ((total_value - self.config.initial_balance) / self.config.initial_balance) * 100.0;
         let profit_total = self.stats.net_profit();
@@ -1002,7 +2513,7 @@ impl TradeSimulator {
             "💰 BALANÇO: Saldo fiat ${:.2} | BTC restante {:.6} | Valor BTC ${:.2}",
             self.saldo_fiat,
             self.saldo_btc,
-            self.saldo_btc * 50000.0
+            self.saldo_btc * preco_final
         );
 
         if net_return >= 0.0 {
@@ -1037,7 +2548,7 @@ impl TradeSimulator {
             println!(
                 "║ 💎 BTC restante:        {:<8.6} BTC │ 💎 Valor BTC: ${:<16.2} ║",
                 self.saldo_btc,
-                self.saldo_btc * 50000.0
+                self.saldo_btc * preco_final
             );
         }
 
@@ -1046,6 +2557,30 @@ impl TradeSimulator {
             "║ 📈 TOTAL DE LUCROS:     ${:<15.2} │ 📉 TOTAL DE PERDAS: ${:<12.2} ║",
             self.stats.total_profit, self.stats.total_loss
         );
+        if self.funding.is_some() || self.stats.total_funding != 0.0 {
+            info!(
+                "💱 FUNDING: P&L de funding ${:.2} | P&L trades + funding ${:.2}",
+                self.stats.total_funding,
+                profit_total + self.stats.total_funding
+            );
+            println!(
+                "║ 💱 P&L DE FUNDING:      ${:<15.2} │ 📊 TRADES + FUNDING: ${:<11.2} ║",
+                self.stats.total_funding,
+                profit_total + self.stats.total_funding
+            );
+        }
+        if self.stats.candles_missing > 0 {
+            info!(
+                "🕳️  DADOS: {} candles ausentes | {} preenchidos ({})",
+                self.stats.candles_missing,
+                self.stats.candles_filled,
+                self.fill_policy.name()
+            );
+            println!(
+                "║ 🕳️  CANDLES AUSENTES:    {:<15} │ 🩹 PREENCHIDOS: {:<13} ║",
+                self.stats.candles_missing, self.stats.candles_filled
+            );
+        }
         println!(
             "║ 🎯 TRADES REALIZADOS:   {:<15} │ 📉 DRAWDOWN MÁXIMO: {:<8.2}% ║",
             self.stats.total_trades, self.stats.max_drawdown
@@ -1066,6 +2601,8 @@ impl TradeSimulator {
             );
         }
 
+        self.display_benchmarks(net_return);
+
         println!("╠{:─<78}╣", "");
         println!("║ 📊 CONFIGURAÇÃO USADA:                                              ║");
         println!(
@@ -1103,11 +2640,11 @@ impl TradeSimulator {
     }
 }
 
-// Função para executar o simulador
-pub fn run_trade_simulation() -> Result<(), Box<dyn std::error::Error>> {
-    info!("🚀 Iniciando simulação de trading BTC");
-
-    let redis_client = RedisClient::from_env()?;
+// Monta o simulador com a configuração padrão e as opções do ambiente
+fn build_simulator(
+    redis_client: RedisClient,
+    state_store: StateStore,
+) -> Result<TradeSimulator, Box<dyn std::error::Error>> {
 
     // Configuração personalizada do trade DCA
     let config = TradeConfig {
@@ -1125,7 +2662,47 @@ pub fn run_trade_simulation() -> Result<(), Box<dyn std::error::Error>> {
         config.initial_balance, config.take_profit_percentage, config.percentual_queda_para_comprar
     );
 
-    let mut simulator = TradeSimulator::from_saved_state(redis_client, config)?;
+    let funding = FundingSchedule::from_env()?;
+    let events = EventPublisher::from_env(&redis_client)?;
+
+    let mut simulator = TradeSimulator::from_saved_state(redis_client, config, state_store)?;
+    simulator.set_funding_schedule(funding);
+    simulator.set_event_publisher(events);
+    match ControlServer::start(&NamedSimulation::from_env()?, simulator.control_handle()) {
+        Ok(server) => simulator.set_control_server(server),
+        Err(e) => {
+            warn!("⚠️  Canal de controle indisponível: {}", e);
+            println!("⚠️  Canal de controle indisponível: {}", e);
+        }
+    }
+    if let Some(address) = http_api::address_from_env()? {
+        simulator.set_http_server(HttpServer::start(address, simulator.control_handle())?);
+    }
+    Ok(simulator)
+}
+
+/// Garante um único processo por simulação nomeada e o encerramento limpo por sinal
+fn claim_simulation() -> Result<(), Box<dyn std::error::Error>> {
+    supervisor::acquire_lock(&NamedSimulation::from_env()?)?;
+    supervisor::install_shutdown_handler()
+}
+
+// Função para executar o simulador
+pub fn run_trade_simulation() -> Result<(), Box<dyn std::error::Error>> {
+    info!("🚀 Iniciando simulação de trading BTC");
+    claim_simulation()?;
+
+    let fill_policy = FillPolicy::from_env()?;
+    let candle_cache = match env::var("CANDLE_CACHE") {
+        Ok(path) => Some(CandleCache::open(&path)?),
+        Err(_) => None,
+    };
+
+    let mut simulator = build_simulator(RedisClient::from_env()?, StateStore::from_env()?)?;
+    simulator.set_fill_policy(fill_policy);
+    if let Some(cache) = candle_cache {
+        simulator.set_candle_cache(cache)?;
+    }
     let result = simulator.run();
 
     match &result {
@@ -1136,10 +2713,342 @@ pub fn run_trade_simulation() -> Result<(), Box<dyn std::error::Error>> {
     result
 }
 
+// Função para executar o simulador diretamente sobre trades (tick a tick)
+pub fn run_tick_simulation(input: &str, fresh: bool) -> Result<(), Box<dyn std::error::Error>> {
+    info!("🚀 Iniciando simulação tick a tick com trades de {}", input);
+    claim_simulation()?;
+
+    let state_store = StateStore::from_env()?;
+    if fresh {
+        TradeSimulator::clear_saved_state(&state_store)?;
+    }
+    let mut ticks = TickReader::open(input)?;
+    let mut simulator = build_simulator(RedisClient::from_env()?, state_store)?;
+    let result = simulator.run_on_ticks(&mut ticks, &format!("ticks:{}", input));
+
+    match &result {
+        Ok(_) => info!("✅ Simulação tick a tick concluída com sucesso"),
+        Err(e) => error!("❌ Simulação tick a tick falhou: {}", e),
+    }
+
+    result
+}
+
+// Função para executar o paper trading sobre uma fonte de candles em tempo real
+pub fn run_live_simulation(
+    source: LiveSource,
+    fresh: bool,
+    idle_timeout: Option<Duration>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    info!("🚀 Iniciando paper trading com candles de {}", source.describe());
+    claim_simulation()?;
+
+    let state_store = StateStore::from_env()?;
+    if fresh {
+        TradeSimulator::clear_saved_state(&state_store)?;
+    }
+    let redis_client = RedisClient::from_env()?;
+    let mut feed = live::spawn_feed(source.clone(), Some(redis_client.clone()))?;
+    let mut simulator = build_simulator(redis_client, state_store)?;
+    let result = simulator.run_live(&mut feed, &source.describe(), idle_timeout);
+
+    match &result {
+        Ok(_) => info!("✅ Paper trading concluído com sucesso"),
+        Err(e) => error!("❌ Paper trading falhou: {}", e),
+    }
+
+    result
+}
+
 // Função para executar simulador limpo (sem estado salvo)
 pub fn run_fresh_simulation() -> Result<(), Box<dyn std::error::Error>> {
     info!("🧹 Limpando estado anterior e iniciando simulação nova");
+    claim_simulation()?;
     
-    TradeSimulator::clear_state_file()?;
+    TradeSimulator::clear_saved_state(&StateStore::from_env()?)?;
     run_trade_simulation()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::control::Speed;
+    use chrono::TimeZone;
+
+    fn at(hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 1, 1, hour, 0, 0).unwrap()
+    }
+
+    fn simulator() -> TradeSimulator {
+        let config = TradeConfig {
+            initial_balance: 1000.0,
+            ..TradeConfig::default()
+        };
+        let mut simulator = TradeSimulator::new(RedisClient::offline(), config).unwrap();
+        simulator.start_at(at(0));
+        simulator
+    }
+
+    fn order(id: u32, btc_quantity: f64, buy_price: f64) -> BuyOrder {
+        BuyOrder {
+            id,
+            btc_quantity,
+            buy_price,
+            buy_time: at(0),
+            invested_amount: btc_quantity * buy_price,
+        }
+    }
+
+    #[test]
+    fn funding_larger_than_fiat_liquidates_newest_orders() {
+        let mut simulator = simulator();
+        simulator.buy_orders = vec![order(1, 0.01, 40_000.0), order(2, 0.01, 45_000.0)];
+        simulator.saldo_btc = 0.02;
+        simulator.saldo_fiat = 5.0;
+        simulator.total_investido = 850.0;
+        simulator.set_funding_schedule(Some(FundingSchedule::Constant(0.01)));
+        assert_eq!(simulator.next_funding_time, Some(at(8)));
+        simulator.current_time = at(8);
+
+        // 1% sobre $1000 de posição: pagamento de $10 com $5 em caixa
+        simulator.aplicar_funding(50_000.0);
+
+        assert_eq!(simulator.stats.total_funding, -10.0);
+        assert_eq!(simulator.next_funding_time, Some(at(16)));
+        let remaining: Vec<u32> = simulator.buy_orders.iter().map(|o| o.id).collect();
+        assert_eq!(remaining, vec![1]);
+        assert!((simulator.saldo_fiat - 495.0).abs() < 1e-9);
+        assert!((simulator.saldo_btc - 0.01).abs() < 1e-12);
+        assert!((simulator.total_investido - 400.0).abs() < 1e-9);
+        assert_eq!(simulator.stats.winning_trades, 1);
+        assert!((simulator.stats.total_profit - 50.0).abs() < 1e-9);
+
+        let history: Vec<(&str, Option<u32>)> = simulator
+            .transaction_history
+            .iter()
+            .map(|t| (t.transaction_type.as_str(), t.buy_order_id))
+            .collect();
+        assert_eq!(history, vec![("FUNDING", None), ("SELL", Some(2))]);
+    }
+
+    #[test]
+    fn funding_liquidation_sells_every_order_when_needed() {
+        let mut simulator = simulator();
+        simulator.buy_orders = vec![order(1, 0.01, 60_000.0), order(2, 0.01, 55_000.0)];
+        simulator.saldo_btc = 0.02;
+        simulator.saldo_fiat = 0.0;
+        simulator.total_investido = 1150.0;
+        simulator.set_funding_schedule(Some(FundingSchedule::Constant(2.0)));
+        simulator.current_time = at(8);
+
+        // Pagamento de 200% da posição: nem vendendo tudo o saldo volta a ser positivo
+        simulator.aplicar_funding(10_000.0);
+
+        assert!(simulator.buy_orders.is_empty());
+        assert_eq!(simulator.saldo_btc, 0.0);
+        assert!((simulator.saldo_fiat - -200.0).abs() < 1e-9);
+        assert_eq!(simulator.stats.losing_trades, 2);
+        assert_eq!(simulator.transaction_history.len(), 3);
+    }
+
+    /// Destino de log em memória para verificar o que a simulação registrou
+    #[derive(Clone, Default)]
+    struct CapturedLog(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+
+    impl std::io::Write for CapturedLog {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn filled_gap_does_not_warn_about_missing_data() {
+        let dir = std::env::temp_dir().join(format!("btc-fill-gap-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let cache_path = dir.join("candles.cache");
+
+        // Dois candles reais, 150 minutos sem dados e mais dois candles reais
+        let candles: Vec<Option<CsvBtcFile>> = (0..154)
+            .map(|minute| {
+                (!(2..152).contains(&minute)).then(|| CsvBtcFile {
+                    open: 40_000.0,
+                    high: 40_000.0,
+                    low: 40_000.0,
+                    close: 40_000.0,
+                    volume: 1.0,
+                    timestamp: at(0) + chrono::Duration::minutes(minute),
+                })
+            })
+            .collect();
+        crate::candle_cache::build_from_candles(&candles, cache_path.to_str().unwrap()).unwrap();
+
+        let mut simulator = simulator();
+        simulator.end_time = at(0) + chrono::Duration::minutes(154);
+        simulator.fill_policy = FillPolicy::ForwardFill;
+        simulator.candle_cache = Some(CandleCache::open(cache_path.to_str().unwrap()).unwrap());
+        let mut manifest = RunManifest::start_in(
+            &dir,
+            &simulator.config,
+            simulator.run_settings(),
+            "cache",
+            0,
+            at(0),
+        )
+        .unwrap();
+
+        let log = CapturedLog::default();
+        let subscriber = tracing_subscriber::fmt()
+            .with_ansi(false)
+            .with_writer({
+                let log = log.clone();
+                move || log.clone()
+            })
+            .finish();
+        let mut batch = None;
+        tracing::subscriber::with_default(subscriber, || {
+            simulator.replay_candles(&mut manifest, &mut batch).unwrap();
+        });
+        simulator.close_batch(&mut batch);
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(simulator.stats.candles_missing, 150);
+        assert_eq!(simulator.stats.candles_filled, 150);
+        assert_eq!(simulator.data_index, 154);
+        let output = String::from_utf8(log.0.lock().unwrap().clone()).unwrap();
+        assert!(
+            !output.contains("iterações sem dados"),
+            "aviso de gap emitido com o gap preenchido:\n{}",
+            output
+        );
+    }
+
+    #[test]
+    fn control_set_changes_only_active_fields() {
+        let mut simulator = simulator();
+        let previous = simulator.config.take_profit_percentage;
+        let reply = simulator.apply_control(&ControlCommand::Set {
+            field: "take_profit_percentage".to_string(),
+            value: 8.0,
+        });
+        assert!(reply.ok, "{}", reply.message);
+        assert_eq!(simulator.config.take_profit_percentage, 8.0);
+        assert!(reply.message.contains(&previous.to_string()));
+
+        for field in INACTIVE_FIELDS {
+            let reply = simulator.apply_control(&ControlCommand::Set {
+                field: field.to_string(),
+                value: 3.0,
+            });
+            assert!(!reply.ok);
+            assert!(reply.message.contains("não é usado"), "{}", reply.message);
+        }
+        assert_eq!(
+            simulator.config.stop_loss_percentage,
+            TradeConfig::default().stop_loss_percentage
+        );
+
+        for (field, value) in [
+            ("initial_balance", 5.0),
+            ("trade_percentage", 0.0),
+            ("trade_percentage", 101.0),
+            ("trade_percentage", f64::NAN),
+        ] {
+            let reply = simulator.apply_control(&ControlCommand::Set {
+                field: field.to_string(),
+                value,
+            });
+            assert!(!reply.ok, "{} = {} deveria falhar", field, value);
+        }
+        assert_eq!(
+            simulator.config.trade_percentage,
+            TradeConfig::default().trade_percentage
+        );
+    }
+
+    #[test]
+    fn control_pause_step_and_resume_drive_the_run_state() {
+        let mut simulator = simulator();
+        assert!(simulator.apply_control(&ControlCommand::Pause).ok);
+        assert_eq!(simulator.control.state, RunState::Paused);
+        assert!(!simulator.apply_control(&ControlCommand::Step { count: 0 }).ok);
+
+        assert!(simulator.apply_control(&ControlCommand::Step { count: 2 }).ok);
+        simulator.control.consume_step();
+        assert_eq!(simulator.control.state, RunState::Stepping { remaining: 1 });
+        simulator.control.consume_step();
+        assert_eq!(simulator.control.state, RunState::Paused);
+
+        assert!(simulator.apply_control(&ControlCommand::Resume).ok);
+        assert_eq!(simulator.control.state, RunState::Running);
+        let reply = simulator.apply_control(&ControlCommand::Speed {
+            speed: Speed::CandlesPerSec(0.0),
+        });
+        assert!(!reply.ok);
+    }
+
+    #[test]
+    fn trade_events_carry_structured_fields() {
+        let mut simulator = simulator();
+        let log = CapturedLog::default();
+        let subscriber = tracing_subscriber::fmt()
+            .json()
+            .with_writer({
+                let log = log.clone();
+                move || log.clone()
+            })
+            .finish();
+        let candle = |close: f64, hour: u32| CsvBtcFile {
+            open: close,
+            high: close,
+            low: close,
+            close,
+            volume: 1.0,
+            timestamp: at(hour),
+        };
+        tracing::subscriber::with_default(subscriber, || {
+            simulator.current_time = at(1);
+            simulator.process_tick(&candle(40_000.0, 1)).unwrap();
+            simulator.current_time = at(2);
+            simulator.process_tick(&candle(44_000.0, 2)).unwrap();
+        });
+
+        let output = String::from_utf8(log.0.lock().unwrap().clone()).unwrap();
+        let events: Vec<serde_json::Value> = output
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        let event = |name: &str| {
+            events
+                .iter()
+                .find(|entry| entry["fields"]["event"] == name)
+                .unwrap_or_else(|| panic!("evento '{}' ausente:\n{}", name, output))["fields"]
+                .clone()
+        };
+
+        let signal = event("buy_signal");
+        assert_eq!(signal["reason"], BuyKind::First.as_str());
+        assert_eq!(signal["side"], "buy");
+
+        let buy = event("buy");
+        assert_eq!(buy["order_id"], 1);
+        assert_eq!(buy["price"], 40_000.0);
+        assert_eq!(buy["reason"], BuyKind::First.as_str());
+        assert!(buy["qty"].as_f64().unwrap() > 0.0);
+        assert_eq!(buy["sim_time"], TradeSimulator::sim_time(at(1)).as_str());
+
+        let sell = event("sell");
+        assert_eq!(sell["order_id"], 1);
+        assert_eq!(sell["side"], "sell");
+        assert_eq!(sell["reason"], "take_profit");
+        assert_eq!(sell["buy_price"], 40_000.0);
+        assert_eq!(sell["holding_hours"], 1);
+        assert!((sell["pnl_pct"].as_f64().unwrap() - 10.0).abs() < 1e-9);
+        assert!(sell["message"].as_str().unwrap().starts_with("💚 VENDA COM LUCRO"));
+    }
+}
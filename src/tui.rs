@@ -0,0 +1,625 @@
+use chrono::{DateTime, Utc};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::symbols::Marker;
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{
+    Axis, Block, Borders, Cell, Chart, Dataset, Gauge, GraphType, Paragraph, Row, Table,
+};
+use ratatui::Frame;
+use std::time::{Duration, Instant};
+
+use crate::control::{self, ControlCommand, LiveStatus, RunState, TransactionPage};
+use crate::metrics::TickRate;
+use crate::simulations::NamedSimulation;
+use crate::trade_btc::{EquityPoint, Transaction};
+
+/// Intervalo entre consultas ao processo da simulação
+const REFRESH: Duration = Duration::from_secs(1);
+
+/// Pontos de preço mostrados no gráfico
+const CHART_POINTS: usize = 240;
+
+/// Transações buscadas a cada atualização (marcadores do gráfico e tabela)
+const RECENT_TRANSACTIONS: usize = 200;
+
+/// Painel em tela cheia de uma simulação rodando, alimentado pelo canal de controle
+struct Dashboard {
+    simulation: NamedSimulation,
+    status: Option<LiveStatus>,
+    transactions: Vec<Transaction>,
+    total_transactions: usize,
+    /// (horário simulado em segundos, preço): curva de patrimônio + preços lidos ao vivo
+    prices: Vec<(f64, f64)>,
+    rate: TickRate,
+    current_rate: f64,
+    connected: bool,
+    message: String,
+}
+
+impl Dashboard {
+    fn new(simulation: NamedSimulation) -> Self {
+        Self {
+            simulation,
+            status: None,
+            transactions: Vec::new(),
+            total_transactions: 0,
+            prices: Vec::new(),
+            rate: TickRate::default(),
+            current_rate: 0.0,
+            connected: false,
+            message: String::new(),
+        }
+    }
+
+    fn request(&self, command: ControlCommand) -> Result<control::ControlReply, String> {
+        let reply = control::send_command(&self.simulation, &command).map_err(|e| e.to_string())?;
+        if reply.ok {
+            Ok(reply)
+        } else {
+            Err(reply.message)
+        }
+    }
+
+    /// Busca status e transações recentes; ao (re)conectar, preenche o gráfico com a
+    /// curva de patrimônio
+    fn refresh(&mut self) {
+        let status = match self.request(ControlCommand::Status) {
+            Ok(reply) => reply.status,
+            Err(e) => {
+                self.connected = false;
+                self.message = e;
+                return;
+            }
+        };
+        let Some(status) = status else {
+            return;
+        };
+
+        if !self.connected {
+            self.connected = true;
+            self.message = format!("Conectado à simulação '{}'", self.simulation.name());
+            self.rate = TickRate::default();
+            self.prices = self
+                .request(ControlCommand::EquityCurve)
+                .ok()
+                .and_then(|reply| reply.data)
+                .and_then(|data| serde_json::from_value::<Vec<EquityPoint>>(data).ok())
+                .unwrap_or_default()
+                .iter()
+                .map(|point| (point.time.timestamp() as f64, point.price))
+                .collect();
+        }
+
+        if let Some(price) = status.portfolio.last_price {
+            let time = status.portfolio.sim_time.timestamp() as f64;
+            if self.prices.last().is_none_or(|(last, _)| time > *last) {
+                self.prices.push((time, price));
+            }
+        }
+        let excess = self.prices.len().saturating_sub(CHART_POINTS * 4);
+        self.prices.drain(..excess);
+
+        let page = self
+            .request(ControlCommand::Transactions {
+                offset: 0,
+                limit: RECENT_TRANSACTIONS,
+                newest_first: true,
+            })
+            .ok()
+            .and_then(|reply| reply.data)
+            .and_then(|data| serde_json::from_value::<TransactionPage>(data).ok());
+        if let Some(page) = page {
+            self.total_transactions = page.total;
+            self.transactions = page.items;
+        }
+
+        self.current_rate = self.rate.update(&status);
+        self.status = Some(*status);
+    }
+
+    /// Ações das teclas; a resposta aparece no rodapé
+    fn send(&mut self, command: ControlCommand) {
+        self.message = match self.request(command) {
+            Ok(reply) => reply.message,
+            Err(e) => e,
+        };
+        self.refresh();
+    }
+
+    fn toggle_pause(&mut self) {
+        let running = self
+            .status
+            .as_ref()
+            .is_some_and(|status| status.state == RunState::Running);
+        self.send(if running {
+            ControlCommand::Pause
+        } else {
+            ControlCommand::Resume
+        });
+    }
+
+    fn draw(&self, frame: &mut Frame) {
+        let [header, body, progress, tables, footer] = Layout::vertical([
+            Constraint::Length(3),
+            Constraint::Min(10),
+            Constraint::Length(3),
+            Constraint::Length(12),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        self.draw_footer(frame, footer);
+        let Some(status) = &self.status else {
+            frame.render_widget(
+                Paragraph::new(format!(
+                    "Aguardando a simulação '{}'... {}",
+                    self.simulation.name(),
+                    self.message
+                ))
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .title(" BTC Simulator "),
+                ),
+                body,
+            );
+            return;
+        };
+
+        let [chart, portfolio] =
+            Layout::horizontal([Constraint::Percentage(68), Constraint::Percentage(32)])
+                .areas(body);
+        let [orders, transactions] =
+            Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)])
+                .areas(tables);
+
+        self.draw_header(frame, header, status);
+        self.draw_chart(frame, chart);
+        draw_portfolio(frame, portfolio, status);
+        self.draw_progress(frame, progress, status);
+        draw_orders(frame, orders, status);
+        self.draw_transactions(frame, transactions);
+    }
+
+    fn draw_header(&self, frame: &mut Frame, area: Rect, status: &LiveStatus) {
+        let (state, color) = match status.state {
+            RunState::Running => ("RODANDO".to_string(), Color::Green),
+            RunState::Paused => ("PAUSADA".to_string(), Color::Yellow),
+            RunState::Stepping { remaining } => (format!("PASSO ({})", remaining), Color::Cyan),
+        };
+        let connection = if self.connected {
+            Span::raw("")
+        } else {
+            Span::styled(" DESCONECTADA ", Style::default().fg(Color::Red))
+        };
+        let line = Line::from(vec![
+            Span::styled(
+                format!(" {} ", state),
+                Style::default().fg(Color::Black).bg(color),
+            ),
+            connection,
+            Span::raw(format!(
+                "  {} | PID {} | {} | velocidade: {} | {}",
+                status.simulation,
+                status.pid,
+                status.source.as_deref().unwrap_or("-"),
+                status.speed,
+                status.portfolio.sim_time.format("%Y-%m-%d %H:%M")
+            )),
+        ]);
+        frame.render_widget(
+            Paragraph::new(line).block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(" BTC Simulator "),
+            ),
+            area,
+        );
+    }
+
+    fn draw_chart(&self, frame: &mut Frame, area: Rect) {
+        let visible = &self.prices[self.prices.len().saturating_sub(CHART_POINTS)..];
+        let block = Block::default().borders(Borders::ALL).title(" Preço ");
+        let (Some((x_min, _)), Some((x_max, _))) = (visible.first(), visible.last()) else {
+            frame.render_widget(Paragraph::new("Sem preços ainda").block(block), area);
+            return;
+        };
+        let (x_min, x_max) = (*x_min, x_max.max(x_min + 60.0));
+
+        let markers = |kind: &str| -> Vec<(f64, f64)> {
+            self.transactions
+                .iter()
+                .filter(|tx| tx.transaction_type == kind)
+                .map(|tx| (tx.time.timestamp() as f64, tx.price))
+                .filter(|(time, _)| *time >= x_min && *time <= x_max)
+                .collect()
+        };
+        let buys = markers("BUY");
+        let sells = markers("SELL");
+
+        let (mut y_min, mut y_max) = visible
+            .iter()
+            .chain(&buys)
+            .chain(&sells)
+            .fold((f64::MAX, f64::MIN), |(lo, hi), (_, price)| {
+                (lo.min(*price), hi.max(*price))
+            });
+        let padding = ((y_max - y_min) * 0.05).max(1.0);
+        y_min -= padding;
+        y_max += padding;
+
+        let datasets = vec![
+            Dataset::default()
+                .name("preço")
+                .marker(Marker::Braille)
+                .graph_type(GraphType::Line)
+                .style(Style::default().fg(Color::Cyan))
+                .data(visible),
+            Dataset::default()
+                .name("compras")
+                .marker(Marker::Dot)
+                .graph_type(GraphType::Scatter)
+                .style(Style::default().fg(Color::Green))
+                .data(&buys),
+            Dataset::default()
+                .name("vendas")
+                .marker(Marker::Dot)
+                .graph_type(GraphType::Scatter)
+                .style(Style::default().fg(Color::Red))
+                .data(&sells),
+        ];
+        let chart = Chart::new(datasets)
+            .block(block)
+            .x_axis(
+                Axis::default()
+                    .bounds([x_min, x_max])
+                    .labels([time_label(x_min), time_label(x_max)]),
+            )
+            .y_axis(Axis::default().bounds([y_min, y_max]).labels([
+                format!("{:.0}", y_min),
+                format!("{:.0}", (y_min + y_max) / 2.0),
+                format!("{:.0}", y_max),
+            ]));
+        frame.render_widget(chart, area);
+    }
+
+    fn draw_progress(&self, frame: &mut Frame, area: Rect, status: &LiveStatus) {
+        let eta = if status.speed == "ritmo da fonte" {
+            "tempo real".to_string()
+        } else if self.current_rate > 0.0 {
+            let remaining = status.total_records.saturating_sub(status.data_index) as f64;
+            format_duration(Duration::from_secs_f64(remaining / self.current_rate))
+        } else {
+            "-".to_string()
+        };
+        let label = format!(
+            "{:.1}% | {}/{} | {:.0}/s | ETA {}",
+            status.progress_pct, status.data_index, status.total_records, self.current_rate, eta
+        );
+        let gauge = Gauge::default()
+            .block(Block::default().borders(Borders::ALL).title(" Progresso "))
+            .gauge_style(Style::default().fg(Color::Blue))
+            .ratio((status.progress_pct / 100.0).clamp(0.0, 1.0))
+            .label(label);
+        frame.render_widget(gauge, area);
+    }
+
+    fn draw_transactions(&self, frame: &mut Frame, area: Rect) {
+        let rows = self.transactions.iter().map(|tx| {
+            let color = match tx.transaction_type.as_str() {
+                "BUY" => Color::Green,
+                "SELL" => Color::Red,
+                _ => Color::Yellow,
+            };
+            Row::new(vec![
+                Cell::from(tx.time.format("%m-%d %H:%M").to_string()),
+                Cell::from(tx.transaction_type.clone()).style(Style::default().fg(color)),
+                Cell::from(format!("{:.2}", tx.price)),
+                Cell::from(format!("{:.2}", tx.amount)),
+                pnl_cell(tx.profit_loss, None),
+            ])
+        });
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(11),
+                Constraint::Length(7),
+                Constraint::Length(10),
+                Constraint::Length(9),
+                Constraint::Min(8),
+            ],
+        )
+        .header(header_row(["Hora", "Tipo", "Preço", "Valor", "P&L"]))
+        .block(Block::default().borders(Borders::ALL).title(format!(
+            " Transações recentes ({} no total) ",
+            self.total_transactions
+        )));
+        frame.render_widget(table, area);
+    }
+
+    fn draw_footer(&self, frame: &mut Frame, area: Rect) {
+        let line = Line::from(vec![
+            Span::styled(
+                " [p/espaço] pausar/retomar  [n] passo  [s] snapshot  [q] sair ",
+                Style::default().add_modifier(Modifier::REVERSED),
+            ),
+            Span::raw(format!("  {}", self.message)),
+        ]);
+        frame.render_widget(Paragraph::new(line), area);
+    }
+}
+
+fn draw_portfolio(frame: &mut Frame, area: Rect, status: &LiveStatus) {
+    let portfolio = &status.portfolio;
+    let pnl_color = if portfolio.profit_loss >= 0.0 {
+        Color::Green
+    } else {
+        Color::Red
+    };
+    let lines = vec![
+        Line::from(format!("Patrimônio:     ${:.2}", portfolio.equity)),
+        Line::styled(
+            format!(
+                "P&L:            ${:.2} ({:+.2}%)",
+                portfolio.profit_loss, portfolio.profit_loss_pct
+            ),
+            Style::default().fg(pnl_color),
+        ),
+        Line::from(format!("Fiat:           ${:.2}", portfolio.fiat_balance)),
+        Line::from(format!(
+            "BTC:            {:.6} (${:.2})",
+            portfolio.btc_balance, portfolio.btc_value
+        )),
+        Line::from(format!(
+            "Preço:          ${:.2}",
+            portfolio.last_price.unwrap_or(0.0)
+        )),
+        Line::from(format!("Investido:      ${:.2}", portfolio.total_invested)),
+        Line::from(format!("Realizado:      ${:.2}", status.realized_pnl)),
+        Line::from(format!("Não realizado:  ${:.2}", portfolio.unrealized_pnl)),
+        Line::from(format!(
+            "Drawdown:       {:.2}% (máx {:.2}%)",
+            status.current_drawdown, status.max_drawdown
+        )),
+        Line::from(format!(
+            "Trades:         {} ({} ganhos / {} perdas)",
+            status.total_trades, status.winning_trades, status.losing_trades
+        )),
+        Line::from(format!(
+            "Quedas:         {}/{}",
+            status.drops_detected, status.drops_to_buy
+        )),
+    ];
+    frame.render_widget(
+        Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title(" Carteira ")),
+        area,
+    );
+}
+
+fn draw_orders(frame: &mut Frame, area: Rect, status: &LiveStatus) {
+    let rows = status.orders.iter().map(|order| {
+        Row::new(vec![
+            Cell::from(order.id.to_string()),
+            Cell::from(format!("{:.6}", order.btc_quantity)),
+            Cell::from(format!("{:.2}", order.buy_price)),
+            Cell::from(format!("{:.2}", order.take_profit_price)),
+            pnl_cell(Some(order.unrealized_pnl), Some(order.unrealized_pnl_pct)),
+        ])
+    });
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(5),
+            Constraint::Length(10),
+            Constraint::Length(10),
+            Constraint::Length(10),
+            Constraint::Min(16),
+        ],
+    )
+    .header(header_row([
+        "ID",
+        "BTC",
+        "Compra",
+        "Alvo TP",
+        "P&L não realizado",
+    ]))
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(format!(" Ordens abertas ({}) ", status.orders.len())),
+    );
+    frame.render_widget(table, area);
+}
+
+fn header_row<const N: usize>(titles: [&'static str; N]) -> Row<'static> {
+    Row::new(titles).style(Style::default().add_modifier(Modifier::BOLD))
+}
+
+fn pnl_cell(value: Option<f64>, percent: Option<f64>) -> Cell<'static> {
+    let Some(value) = value else {
+        return Cell::from("-");
+    };
+    let color = if value >= 0.0 {
+        Color::Green
+    } else {
+        Color::Red
+    };
+    let text = match percent {
+        Some(percent) => format!("{:.2} ({:+.1}%)", value, percent),
+        None => format!("{:.2}", value),
+    };
+    Cell::from(text).style(Style::default().fg(color))
+}
+
+fn time_label(secs: f64) -> String {
+    DateTime::<Utc>::from_timestamp(secs as i64, 0)
+        .map(|time| time.format("%m-%d %H:%M").to_string())
+        .unwrap_or_default()
+}
+
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    match secs {
+        0..60 => format!("{}s", secs),
+        60..3600 => format!("{}m {}s", secs / 60, secs % 60),
+        _ => format!("{}h {}m", secs / 3600, secs % 3600 / 60),
+    }
+}
+
+/// Comando `tui [nome]`: painel interativo de uma simulação rodando (ex.: um `daemon`)
+pub fn run_tui_command(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let simulation = match args {
+        [] => NamedSimulation::from_env()?,
+        [name] => NamedSimulation::new(name)?,
+        [flag, name] if flag == "--name" => NamedSimulation::new(name)?,
+        _ => return Err("Uso: tui [nome]".into()),
+    };
+
+    let mut dashboard = Dashboard::new(simulation);
+    dashboard.refresh();
+    if !dashboard.connected {
+        return Err(dashboard.message.into());
+    }
+
+    let mut terminal = ratatui::try_init()?;
+    let result = run_dashboard(&mut terminal, &mut dashboard);
+    ratatui::restore();
+    result
+}
+
+fn run_dashboard(
+    terminal: &mut ratatui::DefaultTerminal,
+    dashboard: &mut Dashboard,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut last_refresh = Instant::now();
+    loop {
+        if last_refresh.elapsed() >= REFRESH {
+            dashboard.refresh();
+            last_refresh = Instant::now();
+        }
+        terminal.draw(|frame| dashboard.draw(frame))?;
+
+        if !event::poll(Duration::from_millis(200))? {
+            continue;
+        }
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+            KeyCode::Char('p') | KeyCode::Char(' ') => dashboard.toggle_pause(),
+            KeyCode::Char('n') => dashboard.send(ControlCommand::Step { count: 1 }),
+            KeyCode::Char('s') => dashboard.send(ControlCommand::Snapshot),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use ratatui::backend::TestBackend;
+    use ratatui::Terminal;
+
+    fn dashboard() -> Dashboard {
+        Dashboard::new(NamedSimulation::new("papel").unwrap())
+    }
+
+    fn screen(dashboard: &Dashboard) -> String {
+        let mut terminal = Terminal::new(TestBackend::new(140, 40)).unwrap();
+        terminal.draw(|frame| dashboard.draw(frame)).unwrap();
+        terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect()
+    }
+
+    fn transaction(id: u32, kind: &str, minute: i64) -> Transaction {
+        Transaction {
+            id,
+            transaction_type: kind.to_string(),
+            btc_quantity: 0.001,
+            price: 48_000.0,
+            time: Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap()
+                + chrono::Duration::minutes(minute),
+            amount: 48.0,
+            profit_loss: (kind == "SELL").then_some(1.5),
+            buy_order_id: None,
+        }
+    }
+
+    #[test]
+    fn formats_durations_and_chart_times() {
+        assert_eq!(format_duration(Duration::from_secs(42)), "42s");
+        assert_eq!(format_duration(Duration::from_secs(125)), "2m 5s");
+        assert_eq!(format_duration(Duration::from_secs(7_380)), "2h 3m");
+        let noon = Utc.with_ymd_and_hms(2024, 3, 5, 12, 30, 0).unwrap();
+        assert_eq!(time_label(noon.timestamp() as f64), "03-05 12:30");
+    }
+
+    #[test]
+    fn waits_for_the_simulation_before_the_first_status() {
+        let mut dashboard = dashboard();
+        dashboard.message = "sem resposta".to_string();
+        let screen = screen(&dashboard);
+        assert!(screen.contains("Aguardando a simulação 'papel'... sem resposta"));
+        assert!(screen.contains("[q] sair"));
+    }
+
+    #[test]
+    fn draws_status_chart_and_recent_transactions() {
+        let mut dashboard = dashboard();
+        dashboard.connected = true;
+        dashboard.status = Some(LiveStatus {
+            state: RunState::Paused,
+            ..LiveStatus::sample(125)
+        });
+        let start = Utc
+            .with_ymd_and_hms(2024, 1, 1, 0, 0, 0)
+            .unwrap()
+            .timestamp() as f64;
+        dashboard.prices = (0..10)
+            .map(|i| (start + i as f64 * 60.0, 48_000.0 + i as f64))
+            .collect();
+        dashboard.transactions = vec![transaction(2, "SELL", 5), transaction(1, "BUY", 1)];
+        dashboard.total_transactions = 2;
+        dashboard.current_rate = 25.0;
+
+        let screen = screen(&dashboard);
+        assert!(screen.contains("PAUSADA"));
+        assert!(!screen.contains("DESCONECTADA"));
+        assert!(screen.contains("Transações recentes (2 no total)"));
+        assert!(screen.contains("125/1000"));
+        // 875 restantes a 25/s
+        assert!(screen.contains("ETA 35s"));
+        assert!(!screen.contains("Sem preços ainda"));
+    }
+
+    #[test]
+    fn chart_without_prices_says_so() {
+        let mut dashboard = dashboard();
+        dashboard.status = Some(LiveStatus::sample(0));
+        let screen = screen(&dashboard);
+        assert!(screen.contains("Sem preços ainda"));
+        assert!(screen.contains("DESCONECTADA"));
+    }
+
+    #[test]
+    fn tui_needs_a_valid_name_and_a_running_simulation() {
+        let args =
+            |line: &str| -> Vec<String> { line.split_whitespace().map(str::to_string).collect() };
+        assert!(run_tui_command(&args("a b c")).is_err());
+        assert!(run_tui_command(&args("../fora")).is_err());
+        // Sem o socket de controle a conexão falha antes de abrir o terminal
+        assert!(run_tui_command(&args("--name tui-sem-processo")).is_err());
+    }
+}